@@ -1,127 +1,469 @@
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, SubCommand};
 
-pub fn get_app<'a, S>(name: S) -> App<'a, 'a>
-where
-    S: AsRef<str>
-{
-    let app = App::new(name.as_ref())
-        .arg(
-            Arg::with_name("verbose")
-                .short("v")
-                .long("verbose")
-                .takes_value(false)
-                .multiple(true)
-                .help("How verbose the output should be.")
-                .long_help(
-                    "By default, the program only outputs what the program \
-                    tells the virtual machine to output.
-                    
-                    However, you can show debugging information by increasing \
-                    the number of occurrences of the verbosity flag."
-                )
-        )
-        .arg(
-            Arg::with_name("input")
-                .takes_value(true)
-                .multiple(false)
-                .required_unless("raw")
-                .help("The input program.")
-                .long_help("The input program to be run. This input can be \
-                an absolute or a relative path. If you want to input a raw \
-                program (by inputting the full program that is not stored in \
-                a file), use `-r`.")
-        )
-        .arg(
-            Arg::with_name("raw")
-                .short("r")
-                .long("raw")
-                .value_name("RAW-PROGRAM")
-                .takes_value(true)
-                .multiple(false)
-                .required_unless("input")
-                .help("The input program as a raw string in the command \
-                line.")
-                .long_help("The input program that is not stored as a file. \
-                Instead it is a raw string that you can pass to the \
-                interpreter to run.")
-        )
-        .arg(
-            Arg::with_name("celllower")
-                .short("c")
-                .long("cell-lower")
-                .value_name("CELL-LOWER")
-                .takes_value(true)
-                .multiple(false)
-                .help("The lower bound of a cell.")
-                .long_help("By default, the lower bound of each memory cell \
-                is 0. However, you can edit this by using this option.
+/// Arguments shared by running and compiling a program, which choose the
+/// program and configure the virtual machine.
+fn program_args<'a>() -> Vec<Arg<'a, 'a>> {
+    return vec![
+        Arg::with_name("verbose")
+            .short("v")
+            .long("verbose")
+            .takes_value(false)
+            .multiple(true)
+            .help("How verbose the output should be.")
+            .long_help(
+                "By default, the program only outputs what the program \
+                tells the virtual machine to output.
                 
-                An error is returned if this option is greater than \
-                cellupper or if a non-integer is entered.")
-        )
+                However, you can show debugging information by increasing \
+                the number of occurrences of the verbosity flag."
+            ),
+        Arg::with_name("input")
+            .takes_value(true)
+            .multiple(false)
+            .required_unless("raw")
+            .help("The input program.")
+            .long_help("The input program to be run. This input can be \
+            an absolute or a relative path, or `-` to read the program \
+            from stdin. If you want to input a raw program (by inputting \
+            the full program that is not stored in a file), use `-r`."),
+        Arg::with_name("raw")
+            .short("r")
+            .long("raw")
+            .value_name("RAW-PROGRAM")
+            .takes_value(true)
+            .multiple(false)
+            .required_unless("input")
+            .help("The input program as a raw string in the command \
+            line.")
+            .long_help("The input program that is not stored as a file. \
+            Instead it is a raw string that you can pass to the \
+            interpreter to run."),
+        Arg::with_name("celllower")
+            .short("c")
+            .long("cell-lower")
+            .value_name("CELL-LOWER")
+            .takes_value(true)
+            .multiple(false)
+            .help("The lower bound of a cell.")
+            .long_help("By default, the lower bound of each memory cell \
+            is 0. However, you can edit this by using this option.
+            
+            Cells start at 0, and loops end when the current cell is 0. \
+            If 0 is not between the lower and upper bounds, the lower \
+            bound takes its place, and characters are read and written \
+            relative to it, so with bounds of 1 and 256 a cell holding 66 \
+            is output as `A`.
+            
+            An error is returned if this option is greater than \
+            cellupper or if a non-integer is entered."),
+        Arg::with_name("cellupper")
+            .short("C")
+            .long("cell-upper")
+            .value_name("CELL-UPPER")
+            .takes_value(true)
+            .multiple(false)
+            .help("The upper bound of a cell.")
+            .long_help("By default, the upper bound of each memory cell \
+            is 256. However, you can edit this by using this option.
+            
+            An error is returned if this option is smaller than \
+            celllower or if a non-integer is entered."),
+        Arg::with_name("memorysize")
+            .short("m")
+            .long("memory")
+            .value_name("MEMORY-SIZE")
+            .takes_value(true)
+            .multiple(false)
+            .help("The number of memory cell in the memory tape.")
+            .long_help("By default, the lower bound of each memory cell \
+            is 65535. However, you can edit this by using this option.
+            
+            An error is returned if this option is less than 1 or \
+            a non-integer is passed."),
+        Arg::with_name("variablelength")
+            .short("l")
+            .long("variable-length")
+            .value_name("VARIABLE-LENGTH")
+            .takes_value(false)
+            .multiple(false)
+            .help("Whether the memory tape can increase in length.")
+            .long_help("By default, the memory tape cannot increase in \
+            length. When the memory pointer reaches the end of the tape \
+            but is commanded to go to the next cell, it wraps around to \
+            the beginning.
+            
+            If this flag is present, then the memory tape expands in \
+            size when the pointer reaches the end of the tape, and cells \
+            are added to the start of the tape when the pointer moves to \
+            the left of cell 0. This is the same as \
+            `--pointer-policy=grow`."),
+        Arg::with_name("infinite")
+            .short("I")
+            .long("infinite")
+            .takes_value(false)
+            .multiple(false)
+            .help("Whether the memory tape is infinite in both directions.")
+            .long_help("If this flag is present, the memory tape acts as if \
+            it had no ends. The pointer can move as far as it likes to \
+            the left or right of the cell it started at, and its position \
+            is shown relative to that cell, so cells to the left of it \
+            have negative positions.
+            
+            This is the same as `--pointer-policy=infinite`, and is faster \
+            than `--variable-length` for programs which move far to the \
+            left."),
+        Arg::with_name("eof")
+            .short("e")
+            .long("eof")
+            .value_name("EOF-POLICY")
+            .takes_value(true)
+            .multiple(false)
+            .possible_values(&["unchanged", "zero", "max"])
+            .help("What `,` stores in a cell when there is no more \
+            input.")
+            .long_help("By default, when `,` is run after the input has \
+            ended, the memory cell is left unchanged.
+            
+            `zero` sets the cell to 0 (or to the lower bound of a cell, if \
+            0 is not between the bounds) instead, while `max` sets the cell \
+            to the upper bound of a cell (which is the same as -1 for \
+            wrapping 8-bit cells)."),
+        Arg::with_name("cellmode")
+            .short("w")
+            .long("cell-mode")
+            .value_name("CELL-MODE")
+            .takes_value(true)
+            .multiple(false)
+            .possible_values(&["wrap", "saturate", "error", "unbounded"])
+            .help("What happens when a cell goes past its lower or upper \
+            bound.")
+            .long_help("By default, a cell which goes past its upper bound \
+            wraps around to its lower bound and vice versa.
+            
+            `saturate` keeps the cell at the bound instead, while `error` \
+            stops the program with a CellOverflow error which says which \
+            cell overflowed and where in the program it happened. This is \
+            useful for finding programs which depend on wrapping cells.
+            
+            `unbounded` ignores the bounds altogether, so that cells can \
+            hold integers of any size. This needs the `bignum` feature, and \
+            stores every cell as a `big` integer.
+            
+            Only wrapping cells can be optimized, compiled or run by the \
+            JIT, so `--optimize` and `--jit` are ignored with a warning for \
+            other modes."),
+        Arg::with_name("pointerpolicy")
+            .short("p")
+            .long("pointer-policy")
+            .value_name("POINTER-POLICY")
+            .takes_value(true)
+            .multiple(false)
+            .possible_values(&["wrap", "grow", "infinite", "error"])
+            .help("What happens when the pointer moves past either end of \
+            the memory tape.")
+            .long_help("By default, the pointer wraps around to the other \
+            end of the memory tape.
+            
+            `grow` adds cells to whichever end the pointer moves past, the \
+            same as `--variable-length`. `infinite` does the same but acts \
+            as if the tape had no ends, the same as `--infinite`. `error` \
+            stops the program with an OutOfBounds error which says where in \
+            the program it happened, which is useful for finding programs \
+            which move off the left edge of the tape by mistake.
+            
+            Programs whose pointer raises errors cannot be optimized, \
+            compiled or run by the JIT, so `--optimize` and `--jit` are \
+            ignored with a warning for them."),
+        Arg::with_name("preloadfile")
+            .long("preload-file")
+            .value_name("PRELOAD-FILE")
+            .takes_value(true)
+            .multiple(false)
+            .conflicts_with_all(&["preloadnumbers", "preloadstring"])
+            .help("A file whose bytes are written to the memory tape before \
+            the program starts.")
+            .long_help("By default, every cell in the memory tape starts at \
+            0. If this option is present, each byte of the file is written \
+            to a cell instead, starting from the cell the pointer starts at \
+            (or `--preload-offset`), the same way `,` would store it.
+            
+            An error is returned if the file cannot be read or if the tape \
+            is too short to hold every byte."),
+        Arg::with_name("preloadnumbers")
+            .long("preload-numbers")
+            .value_name("PRELOAD-NUMBERS")
+            .takes_value(true)
+            .multiple(false)
+            .conflicts_with_all(&["preloadfile", "preloadstring"])
+            .help("A list of numbers which are written to the memory tape \
+            before the program starts.")
+            .long_help("The numbers are separated by commas or whitespace, \
+            such as `72,105,-1`, and are written to the cells as they are, \
+            starting from the cell the pointer starts at (or \
+            `--preload-offset`).
+            
+            An error is returned if a number is not an integer, if it is not \
+            between the lower and upper bounds of a cell or if the tape is \
+            too short to hold every number."),
+        Arg::with_name("preloadstring")
+            .long("preload-string")
+            .value_name("PRELOAD-STRING")
+            .takes_value(true)
+            .multiple(false)
+            .conflicts_with_all(&["preloadfile", "preloadnumbers"])
+            .help("A string which is written to the memory tape before the \
+            program starts.")
+            .long_help("The code point of each character in the string is \
+            written to a cell, starting from the cell the pointer starts at \
+            (or `--preload-offset`), the same way `,` would store it.
+            
+            An error is returned if a character does not fit in a cell or \
+            if the tape is too short to hold every character."),
+        Arg::with_name("preloadoffset")
+            .long("preload-offset")
+            .value_name("PRELOAD-OFFSET")
+            .takes_value(true)
+            .multiple(false)
+            .help("The cell the preloaded cells start from.")
+            .long_help("By default, the preloaded cells start from the cell \
+            the pointer starts at. This option moves them this many cells \
+            to the right instead.
+            
+            An error is returned if a non-negative integer is not \
+            passed."),
+        Arg::with_name("splitinput")
+            .long("split-input")
+            .takes_value(false)
+            .multiple(false)
+            .help("Whether to read the input of the program from after the \
+            first `!` in the program.")
+            .long_help("Many Brainfuck programs are distributed with their \
+            input in the same file, after a `!`. If this flag is present, \
+            the program is split at the first `!`: only the part before it \
+            is parsed as the program, and the part after it is read by `,` \
+            instead of stdin, unless `--input-file` or `--input-string` is \
+            given.
+            
+            With `--new-parser`, a `!` in a comment does not split the \
+            program."),
+        Arg::with_name("newparser")
+            .short("N")
+            .long("new-parser")
+            .takes_value(false)
+            .multiple(false)
+            .help("Whether to use the new parser.")
+            .long_help("This implementation of brainfuck has 2 parsers. \
+            The first one is the default brainfuck parser that should \
+            work with any of the programs you see on `Wikipedia`.
+            
+            The second parser allows you to comment any part of a line \
+            after `#`. This might break code if you use punctuation \
+            in your comments."),
+        Arg::with_name("preprocess")
+            .short("P")
+            .long("preprocess")
+            .takes_value(false)
+            .multiple(false)
+            .help("Whether to expand includes and macros before parsing. \
+            Implies `--new-parser`.")
+            .long_help("If this flag is present, the program is \
+            preprocessed before it is parsed with the new parser. A line \
+            `#include \"file.b\"` is replaced by the program in `file.b`, \
+            found next to the file including it. A line \
+            `#define move(n) >*$n` defines a macro, which is used as \
+            `@move(3)` and becomes `>>>`.
+            
+            In the body of a macro, `$n` is replaced by the argument given \
+            for `n`, and a character or a group in brackets followed by \
+            `*` and a number is repeated that many times.
+            
+            Errors point at the file and line each command came from."),
+    ];
+}
+
+/// Arguments which only make sense when running a program.
+fn runner_args<'a>() -> Vec<Arg<'a, 'a>> {
+    #[allow(unused_mut)]
+    let mut cell_types = vec!["auto", "u8", "u16", "u32", "u64", "i128"];
+    #[cfg(feature = "bignum")]
+    cell_types.push("big");
+    #[allow(unused_mut)]
+    let mut args = vec![
+        Arg::with_name("inputmode")
+            .short("i")
+            .long("input-mode")
+            .value_name("INPUT-MODE")
+            .takes_value(true)
+            .multiple(false)
+            .possible_values(&["byte", "char", "line"])
+            .help("How much input each `,` consumes.")
+            .long_help("By default, each `,` reads exactly one byte of \
+            input, and a newline is read as 10.
+            
+            `char` reads one UTF-8 encoded character instead, while \
+            `line` reads a whole line and discards everything but its \
+            first character."),
+        Arg::with_name("inputfile")
+            .long("input-file")
+            .value_name("INPUT-FILE")
+            .takes_value(true)
+            .multiple(false)
+            .conflicts_with("inputstring")
+            .help("A file whose contents are read by `,`.")
+            .long_help("By default, `,` reads from stdin. If this option is \
+            present, `,` reads from this file instead, which is useful when \
+            the program itself is read from stdin with `-`.
+            
+            An error is returned if the file cannot be opened."),
+        Arg::with_name("inputstring")
+            .long("input-string")
+            .value_name("INPUT-STRING")
+            .takes_value(true)
+            .multiple(false)
+            .conflicts_with("inputfile")
+            .help("A string which is read by `,`.")
+            .long_help("By default, `,` reads from stdin. If this option is \
+            present, `,` reads this string instead, and the input ends once \
+            all of it has been read."),
+        Arg::with_name("optimize")
+            .short("O")
+            .long("optimize")
+            .takes_value(false)
+            .multiple(false)
+            .help("Whether to optimize the program before running it.")
+            .long_help("By default, the program is run one command at a \
+            time.
+            
+            If this flag is present, the program is lowered into an \
+            intermediate representation first, where runs of `+`, `-`, \
+            `<` and `>` are folded together and the targets of jumps are \
+            worked out in advance. Common loops such as `[-]`, \
+            `[->+<]` and `[>]` are also replaced with single \
+            instructions. This makes long-running programs a lot faster."),
+        Arg::with_name("sparse")
+            .short("S")
+            .long("sparse")
+            .takes_value(false)
+            .multiple(false)
+            .help("Whether to only allocate the memory cells the program \
+            uses.")
+            .long_help("By default, every cell in the memory tape is \
+            allocated before the program starts, which takes a lot of \
+            memory for very long tapes.
+            
+            If this flag is present, the tape is split into pages which \
+            are only allocated when a cell in them is changed, so a \
+            program can be given a tape of billions of cells and only pay \
+            for the ones it uses. This is slower for programs which use \
+            few cells, and the program is run as if `--optimize` were \
+            given instead of `--jit`."),
+        Arg::with_name("celltype")
+            .short("T")
+            .long("cell-type")
+            .value_name("CELL-TYPE")
+            .takes_value(true)
+            .multiple(false)
+            .possible_values(&cell_types)
+            .help("The type each memory cell is stored as.")
+            .long_help("By default (`auto`), each memory cell is stored as \
+            the smallest of `u8`, `u16`, `u32`, `u64` and `i128` which can \
+            hold every value between the lower and upper bounds of a \
+            cell, or as `big` if `--cell-mode=unbounded` is given. `big` \
+            is only available with the `bignum` feature.
+            
+            If the bounds are exactly those of the type (such as 0 and 255 \
+            for `u8`) and cells wrap around, arithmetic on the cells wraps \
+            around natively, which is faster. An error is returned if the \
+            bounds do not fit in the chosen type."),
+        Arg::with_name("dumpmemory")
+            .short("D")
+            .long("dump-memory")
+            .value_name("DUMP-FORMAT")
+            .takes_value(true)
+            .multiple(false)
+            .possible_values(&["hex", "decimal", "ascii"])
+            .help("Prints the memory tape to stderr once the program \
+            stops.")
+            .long_help("If this option is present, the cells of the memory \
+            tape are printed to stderr once the program stops, even if it \
+            stopped because of an error. Only the cells from the first to \
+            the last one which is not zero are printed, along with the \
+            cell the pointer is pointing at, which is surrounded by square \
+            brackets.
+            
+            `hex` and `decimal` print each cell as a number followed by the \
+            cells as ASCII, while `ascii` only prints the cells as ASCII. \
+            Cells which are not printable ASCII characters are shown as \
+            `.`."),
+    ];
+    #[cfg(feature = "jit")]
+    args.push(
+        Arg::with_name("jit")
+            .short("j")
+            .long("jit")
+            .takes_value(false)
+            .multiple(false)
+            .help("Whether to compile the program into machine code before \
+            running it.")
+            .long_help("If this flag is present, the program is optimized \
+            the same way as with `--optimize` and then compiled into x86-64 \
+            machine code, which runs much faster than the interpreter.
+            
+            Only fixed-length tapes of cells which range from 0 to 2^8, \
+            2^16, 2^32 or 2^64 - 1 are compiled. Otherwise, or on other \
+            architectures, the program is run as if `--optimize` were \
+            given instead.")
+    );
+    return args;
+}
+
+/// The `compile` subcommand, which compiles a program instead of running it.
+fn compile_subcommand<'a>() -> App<'a, 'a> {
+    return SubCommand::with_name("compile")
+        .about("Compiles a Brainfuck program into another language.")
+        .args(&program_args())
         .arg(
-            Arg::with_name("cellupper")
-                .short("C")
-                .long("cell-upper")
-                .value_name("CELL-UPPER")
+            Arg::with_name("target")
+                .short("t")
+                .long("target")
+                .value_name("TARGET")
                 .takes_value(true)
                 .multiple(false)
-                .help("The upper bound of a cell.")
-                .long_help("By default, the upper bound of each memory cell \
-                is 256. However, you can edit this by using this option.
+                .required(true)
+                .possible_values(&["c", "rust", "rust-module", "wat"])
+                .help("The language to compile the program into.")
+                .long_help("The language to compile the program into. The \
+                compiled program behaves the same way as the interpreter \
+                does with the same memory options.
                 
-                An error is returned if this option is smaller than \
-                celllower or if a non-integer is entered.")
+                `c` emits a standalone C file, `rust` emits a `main.rs` and \
+                `rust-module` emits a Rust module with a `run` function which \
+                takes any reader and writer, and `wat` emits a WebAssembly \
+                text module which imports `getchar` and `putchar` from \
+                `env`.")
         )
         .arg(
-            Arg::with_name("memorysize")
-                .short("m")
-                .long("memory")
-                .value_name("MEMORY-SIZE")
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("OUTPUT")
                 .takes_value(true)
                 .multiple(false)
-                .help("The number of memory cell in the memory tape.")
-                .long_help("By default, the lower bound of each memory cell \
-                is 65535. However, you can edit this by using this option.
-                
-                An error is returned if this option is less than 1 or \
-                a non-integer is passed.")
-        )
-        .arg(
-            Arg::with_name("variablelength")
-                .short("l")
-                .long("variable-length")
-                .value_name("VARIABLE-LENGTH")
-                .takes_value(false)
-                .multiple(false)
-                .help("Whether the memory tape can increase in length.")
-                .long_help("By default, the memory tape cannot increase in \
-                length. When the memory pointer reaches the end of the tape \
-                but is commanded to go to the next cell, it wraps around to \
-                the beginning.
-                
-                If this flag is present, then the memory tape expands in \
-                size when the pointer reaches the end of the tape.
-                
-                However, no matter the presence of this flag, when the \
-                pointer is commanded to go to the previous cell when it is \
-                at cell 0, it will still wrap to the last cell.")
-        )
-        .arg(
-            Arg::with_name("newparser")
-                .short("N")
-                .long("new-parser")
-                .takes_value(false)
-                .multiple(false)
-                .help("Whether to use the new parser.")
-                .long_help("This implementation of brainfuck has 2 parsers. \
-                The first one is the default brainfuck parser that should \
-                work with any of the programs you see on `Wikipedia`.
-                
-                The second parser allows you to comment any part of a line \
-                after `#`. This might break code if you use punctuation \
-                in your comments.")
-        )
+                .help("The file to write the compiled program to.")
+                .long_help("The file to write the compiled program to. By \
+                default, the compiled program is written to stdout.")
+        );
+}
+
+pub fn get_app<'a, S>(name: S) -> App<'a, 'a>
+where
+    S: AsRef<str>
+{
+    let app = App::new(name.as_ref())
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .args(&program_args())
+        .args(&runner_args())
+        .subcommand(compile_subcommand())
     ;
     return app;
 }
\ No newline at end of file
@@ -3,5 +3,5 @@ pub mod options;
 
 pub use self::{
     clargs::get_app,
-    options::{AppOptions, ProgramOptions},
+    options::{AppOptions, CompileOptions, InputOptions, ProgramOptions},
 };
\ No newline at end of file
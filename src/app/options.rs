@@ -4,7 +4,11 @@ use std::{
     path::PathBuf
 };
 
-use crate::vm::MemoryOptions;
+use crate::{
+    code::InputMode,
+    compile::CompileTarget,
+    vm::{CellType, DumpFormat, MemoryOptions, Preload},
+};
 
 macro_rules! unwrapparse {
     ($result: expr) => {
@@ -24,19 +28,45 @@ macro_rules! unwrapparse {
 pub enum ProgramOptions {
     Path(PathBuf),
     Raw(String),
+    Stdin,
+}
+
+#[derive(Clone, Debug)]
+pub enum InputOptions {
+    Stdin,
+    Path(PathBuf),
+    Raw(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct CompileOptions {
+    pub target: CompileTarget,
+    pub output: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug)]
 pub struct AppOptions {
     pub program: ProgramOptions,
+    pub input: InputOptions,
     pub verbosity: u8,
     pub memory_options: MemoryOptions,
+    pub input_mode: InputMode,
+    pub optimize: bool,
+    pub jit: bool,
+    pub sparse: bool,
+    pub cell_type: Option<CellType>,
+    pub dump_memory: Option<DumpFormat>,
     pub new_parser: bool,
+    pub preprocess: bool,
+    pub split_input: bool,
+    pub compile: Option<CompileOptions>,
 }
 
 impl AppOptions {
     pub fn from_matches(matches: &ArgMatches) -> Result<Self, Error> {
-        let program = if let Some(thing) = matches.value_of("input") {
+        let program = if let Some("-") = matches.value_of("input") {
+            ProgramOptions::Stdin
+        } else if let Some(thing) = matches.value_of("input") {
             ProgramOptions::Path(PathBuf::from(thing))
         } else if let Some(thing) = matches.value_of("raw") {
             ProgramOptions::Raw(thing.to_string())
@@ -63,18 +93,98 @@ impl AppOptions {
                 unwrapparse!(thing.parse())
             );
         }
-        if let Some(thing) = matches.value_of("variablelength") {
-            memory_options.variable_length(
+        memory_options.variable_length(matches.is_present("variablelength"));
+        if matches.is_present("infinite") {
+            memory_options.infinite(true);
+        }
+        if let Some(thing) = matches.value_of("eof") {
+            memory_options.eof_policy(
                 unwrapparse!(thing.parse())
             );
         }
-        let new_parser = matches.is_present("newparser");
-        return Ok(Self {program, verbosity, memory_options, new_parser});
+        if let Some(thing) = matches.value_of("pointerpolicy") {
+            memory_options.pointer_policy(
+                unwrapparse!(thing.parse())
+            );
+        }
+        if let Some(thing) = matches.value_of("cellmode") {
+            memory_options.cell_mode(
+                unwrapparse!(thing.parse())
+            );
+        }
+        let preload = if let Some(thing) = matches.value_of("preloadfile") {
+            Some(Preload::from_file(thing)?)
+        } else if let Some(thing) = matches.value_of("preloadnumbers") {
+            Some(unwrapparse!(Preload::parse_numbers(thing)))
+        } else {
+            matches.value_of("preloadstring").map(Preload::text)
+        };
+        if let Some(preload) = preload {
+            let offset = match matches.value_of("preloadoffset") {
+                Some(thing) => unwrapparse!(thing.parse()),
+                None => 0,
+            };
+            memory_options.preload(preload.at(offset));
+        }
+        if !memory_options.is_valid() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid memory options: {:?}", memory_options)
+            ));
+        }
+        let input_mode = match matches.value_of("inputmode") {
+            Some(thing) => unwrapparse!(thing.parse()),
+            None => InputMode::default(),
+        };
+        let input = if let Some(thing) = matches.value_of("inputfile") {
+            InputOptions::Path(PathBuf::from(thing))
+        } else if let Some(thing) = matches.value_of("inputstring") {
+            InputOptions::Raw(thing.to_string())
+        } else {
+            InputOptions::Stdin
+        };
+        let optimize = matches.is_present("optimize");
+        let jit = matches.is_present("jit");
+        let sparse = matches.is_present("sparse");
+        let cell_type = match matches.value_of("celltype") {
+            Some("auto") | None => None,
+            Some(thing) => Some(unwrapparse!(thing.parse())),
+        };
+        let dump_memory = match matches.value_of("dumpmemory") {
+            Some(thing) => Some(unwrapparse!(thing.parse())),
+            None => None,
+        };
+        let preprocess = matches.is_present("preprocess");
+        let new_parser = matches.is_present("newparser") || preprocess;
+        let split_input = matches.is_present("splitinput");
+        let compile = match matches.value_of("target") {
+            Some(thing) => Some(CompileOptions {
+                target: unwrapparse!(thing.parse()),
+                output: matches.value_of("output").map(PathBuf::from),
+            }),
+            None => None,
+        };
+        return Ok(Self {
+            program,
+            input,
+            verbosity,
+            memory_options,
+            input_mode,
+            optimize,
+            jit,
+            sparse,
+            cell_type,
+            dump_memory,
+            new_parser,
+            preprocess,
+            split_input,
+            compile,
+        });
     }
 
     pub fn with_verbosity<F>(&self, minimum: u8, callback: F)
     where
-        F: FnOnce(&Self) -> ()
+        F: FnOnce(&Self)
     {
         if self.verbosity >= minimum {
             callback(self);
@@ -1,39 +1,399 @@
+#![allow(clippy::needless_return)]
+
 use brainfucklib::{
     app::{
         get_app,
         AppOptions,
+        CompileOptions,
+        InputOptions,
         ProgramOptions,
     },
-    code::{BfProgram, BfRunner},
-    debug::{Debugger, BfDebugger},
-    parser::{NewParser, NormalParser, BfParser},
-    vm::Memory,
+    code::{
+        ir::recognize_idioms,
+        BfIr,
+        BfProgram,
+        BfRunner,
+        IrRunner,
+        SourceMap,
+    },
+    debug::{
+        Debugger,
+        BfDebugger,
+        BfError,
+        BfErrorKind,
+        Diagnostics,
+        ErrEvent,
+    },
+    parser::{NewParser, NormalParser, BfParser, Preprocessor},
+    vm::{
+        CellMode,
+        CellNumber,
+        CellType,
+        CellValue,
+        Memory,
+        PointerPolicy,
+        SparseMemory,
+    },
 };
+#[cfg(feature = "jit")]
+use brainfucklib::code::JitRunner;
+#[cfg(feature = "bignum")]
+use num_bigint::BigInt;
 
 use std::{
+    borrow::Cow,
     cell::RefCell,
-    io::{Error},
+    fs::File,
+    io::{stdin, stdout, Cursor, Error, ErrorKind, Read},
+    path::PathBuf,
     process::exit,
     rc::Rc,
 };
 
-const VERSION_NO: &'static str = "0.3.0a";
+const VERSION_NO: &str = "0.3.0a";
 
 macro_rules! someerror {
     ($last: expr, $code: expr) => {
         {
-            if let Some(event) = $last {
-                if let Err(error) = event {
-                    return Some(($code, std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("{}", error)
-                    )));
+            if let Some(Err(error)) = $last {
+                return Some(($code, std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("{}", error)
+                )));
+            }
+        }
+    };
+    ($last: expr, $code: expr, $source: expr) => {
+        {
+            if let Some(Err(error)) = $last {
+                let message = match error {
+                    ErrEvent::Error(error) if error.span().is_some() => {
+                        $source.render_error(error)
+                    },
+                    other => format!("{}", other),
+                };
+                return Some(($code, std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    message
+                )));
+            }
+        }
+    };
+}
+
+macro_rules! runloop {
+    ($runner: expr, $options: expr, $debugger: expr) => {
+        {
+            let mut runner = $runner;
+            let options = &$options;
+            options.with_verbosity(1, |_options| {
+                println!("Runner created.");
+            });
+            while runner.run_once().is_some() {
+                options.with_verbosity(2, |_options| {
+                    println!("Running once.");
+                });
+                if let Some(event) = $debugger.borrow_mut().last_event() {
+                    options.with_verbosity(2, |_options| {
+                        println!("\n{:?}", event);
+                    });
                 }
+                options.with_verbosity(3, {
+                    let runner = &runner;
+                    move |_options| {
+                        runner.print_pointer();
+                        runner.print_cell();
+                    }
+                });
+            }
+            if let Some(format) = options.dump_memory {
+                eprintln!();
+                eprint!("{}", runner.memory().used().format(format));
             }
         }
     }
 }
 
+/// Where the text of the program can be found, to show where diagnostics
+/// and errors are in it.
+enum Source {
+    /// The text is kept in memory.
+    Text(SourceMap),
+    /// The program was streamed from a file, which is read again if
+    /// something has to be shown.
+    File(PathBuf),
+    /// The program was streamed from stdin, so its text is gone.
+    Stdin,
+}
+
+impl Source {
+    /// Gets the text of the program, unless it cannot be read again.
+    fn map(&self) -> Option<Cow<'_, SourceMap>> {
+        return match self {
+            Source::Text(source) => Some(Cow::Borrowed(source)),
+            Source::File(path) => {
+                SourceMap::from_file(path).ok().map(Cow::Owned)
+            },
+            Source::Stdin => None,
+        };
+    }
+
+    /// Shows `diagnostics` with the lines they point at, or only with
+    /// their positions if the text of the program is gone.
+    fn render(&self, diagnostics: &Diagnostics) -> String {
+        if let Some(source) = self.map() {
+            return diagnostics.render(&source);
+        }
+        let mut text = String::new();
+        for diagnostic in diagnostics.iter() {
+            text.push_str(&format!("{}\n", diagnostic));
+        }
+        text.push_str(&diagnostics.summary());
+        return text;
+    }
+
+    /// Shows `error` like [`Source::render`].
+    fn render_error(&self, error: &BfError) -> String {
+        return match self.map() {
+            Some(source) => error.render(&source),
+            None => format!("{}", error),
+        };
+    }
+}
+
+fn compile(
+    program: &BfProgram,
+    options: &AppOptions,
+    compile_options: &CompileOptions,
+) -> Option<(i32, Error)> {
+    options.with_verbosity(1, |_options| {
+        println!("Trying to compile to {}.", compile_options.target);
+    });
+    let event = match compile_options.output {
+        Some(ref path) => match File::create(path) {
+            Ok(mut file) => compile_options.target.compile(
+                program,
+                &options.memory_options,
+                &mut file
+            ),
+            Err(error) => return Some((6, error)),
+        },
+        None => compile_options.target.compile(
+            program,
+            &options.memory_options,
+            &mut stdout()
+        ),
+    };
+    if let Err(error) = event {
+        return Some((6, Error::other(format!("{}", error))));
+    }
+    return None;
+}
+
+/// Opens whatever `,` reads from.
+fn open_input(input: &InputOptions) -> Result<Box<dyn Read>, Error> {
+    return Ok(match input {
+        InputOptions::Stdin => Box::new(stdin()),
+        InputOptions::Path(path) => match File::open(path) {
+            Ok(file) => Box::new(file),
+            Err(error) => return Err(Error::new(
+                error.kind(),
+                format!("Could not open input file {:?}: {}", path, error)
+            )),
+        },
+        InputOptions::Raw(raw) => Box::new(Cursor::new(raw.clone())),
+    });
+}
+
+/// Runs `program` on a virtual machine whose cells are stored as `T`.
+fn execute<T>(
+    program: Box<BfProgram>,
+    options: &AppOptions,
+    debugger: &Rc<RefCell<BfDebugger>>,
+) -> Option<(i32, Error)>
+where
+    T: CellValue,
+{
+    let input = match open_input(&options.input) {
+        Ok(input) => input,
+        Err(error) => return Some((1, error)),
+    };
+    let optimizable = options.memory_options.on_overflow() == CellMode::Wrap
+        && options.memory_options.on_out_of_bounds() != PointerPolicy::Error;
+    if (options.optimize || options.jit) && !optimizable {
+        eprintln!(
+            "Warning: only programs whose cells wrap around and whose \
+            pointer does not raise errors can be optimized, so the program \
+            runs without --optimize or --jit."
+        );
+    }
+    if options.jit && options.sparse && optimizable {
+        eprintln!(
+            "Warning: sparse memory cannot be run by the JIT, so the program \
+            runs as if --optimize were given instead."
+        );
+    }
+    if (options.optimize || options.jit) && optimizable {
+        options.with_verbosity(1, |_options| {
+            println!("Trying to lower program.");
+        });
+        let mut ir = match BfIr::lower(&program) {
+            Ok(ir) => Box::new(ir),
+            Err(error) => return Some((5, Error::other(format!("{}", error)))),
+        };
+        recognize_idioms(&mut ir);
+        options.with_verbosity(3, {
+            let ir = &ir;
+            move |_options| {
+                println!("Lowered program:");
+                println!("{:#?}", ir);
+            }
+        });
+        if options.sparse {
+            let memory: Box<SparseMemory<T>> = Box::new(
+                (&options.memory_options).into()
+            );
+            runloop!(
+                IrRunner::with_io(
+                    ir,
+                    memory,
+                    Some(Rc::clone(debugger)),
+                    input,
+                    stdout()
+                ).with_input_mode(options.input_mode),
+                options,
+                debugger
+            );
+        } else if options.jit {
+            #[cfg(feature = "jit")]
+            {
+                let memory: Box<Memory<T>> = Box::new(
+                    (&options.memory_options).into()
+                );
+                runloop!(
+                    JitRunner::with_io(
+                        ir,
+                        memory,
+                        Some(Rc::clone(debugger)),
+                        input,
+                        stdout()
+                    ).with_input_mode(options.input_mode),
+                    options,
+                    debugger
+                );
+            }
+        } else {
+            let memory: Box<Memory<T>> = Box::new(
+                (&options.memory_options).into()
+            );
+            runloop!(
+                IrRunner::with_io(
+                    ir,
+                    memory,
+                    Some(Rc::clone(debugger)),
+                    input,
+                    stdout()
+                ).with_input_mode(options.input_mode),
+                options,
+                debugger
+            );
+        }
+    } else if options.sparse {
+        let memory: Box<SparseMemory<T>> = Box::new(
+            (&options.memory_options).into()
+        );
+        runloop!(
+            BfRunner::with_io(
+                program,
+                memory,
+                Some(Rc::clone(debugger)),
+                input,
+                stdout()
+            ).with_input_mode(options.input_mode),
+            options,
+            debugger
+        );
+    } else {
+        let memory: Box<Memory<T>> = Box::new(
+            (&options.memory_options).into()
+        );
+        runloop!(
+            BfRunner::with_io(
+                program,
+                memory,
+                Some(Rc::clone(debugger)),
+                input,
+                stdout()
+            ).with_input_mode(options.input_mode),
+            options,
+            debugger
+        );
+    }
+    return None;
+}
+
+/// Reads the whole text of the program, splits the input off it and
+/// preprocesses it if asked to, and then parses it.
+fn check_text(
+    options: &mut AppOptions,
+    program: &mut BfProgram
+) -> Result<(Source, Diagnostics), (i32, Error)> {
+    let mut source = match options.program {
+        ProgramOptions::Path(ref path) => match SourceMap::from_file(path) {
+            Ok(source) => source,
+            Err(error) => return Err((2, Error::new(
+                error.kind(),
+                format!(
+                    "Could not open file with path: {:?}\n\
+                    Error by `read_to_string`: {}",
+                    path,
+                    error
+                )
+            ))),
+        },
+        ProgramOptions::Raw(ref prog) => SourceMap::new("<raw>", prog),
+        ProgramOptions::Stdin => {
+            let mut text = String::new();
+            if let Err(error) = stdin().read_to_string(&mut text) {
+                return Err((2, Error::new(
+                    error.kind(),
+                    format!("Could not read program from stdin: {}", error)
+                )));
+            }
+            SourceMap::new("<stdin>", text)
+        },
+    };
+    let (code, embedded) = match options.split_input {
+        true if options.new_parser => NewParser::split_input(source.text()),
+        true => NormalParser::split_input(source.text()),
+        false => (source.text(), None),
+    };
+    if let (Some(embedded), InputOptions::Stdin) = (embedded, &options.input) {
+        options.input = InputOptions::Raw(embedded.to_string());
+    }
+    let code = code.to_string();
+    let expansion = match options.preprocess {
+        true => match Preprocessor::new().process(&code, &mut source) {
+            Ok(expansion) => Some(expansion),
+            Err(error) => {
+                return Err((2, Error::other(error.render(&source))));
+            },
+        },
+        false => None,
+    };
+    let code = expansion.as_ref().map_or(code.as_str(), |e| e.text());
+    let mut diagnostics = if options.new_parser {
+        NewParser::check_string(code, program)
+    } else {
+        NormalParser::check_string(code, program)
+    };
+    if let Some(ref expansion) = expansion {
+        expansion.map_program(program);
+        expansion.map_diagnostics(&mut diagnostics);
+    }
+    return Ok((Source::Text(source), diagnostics));
+}
+
 fn run() -> Option<(i32, Error)> {
     let mut app = get_app("Brainfuck");
     app = app
@@ -42,7 +402,11 @@ fn run() -> Option<(i32, Error)> {
         .author("GrayChrysTea <gray.chrysanthemum@gmail.com>")
     ;
     let matches = app.get_matches();
-    let options = match AppOptions::from_matches(&matches) {
+    let matches = match matches.subcommand_matches("compile") {
+        Some(compile_matches) => compile_matches,
+        None => &matches,
+    };
+    let mut options = match AppOptions::from_matches(matches) {
         Ok(o) => o,
         Err(error) => return Some((1, error)),
     };
@@ -50,27 +414,95 @@ fn run() -> Option<(i32, Error)> {
         println!("Processing arguments.");
         println!("Verbosity: {}", options.verbosity);
         println!("Input type: {:?}", options.program);
+        println!("Program input: {:?}", options.input);
         println!("Memory Options: {:#?}", options.memory_options);
+        println!("Input mode: {}", options.input_mode);
+        println!("Optimize: {}", options.optimize);
+        println!("JIT: {}", options.jit);
+        println!("Sparse memory: {}", options.sparse);
+        println!("Cell type: {:?}", options.cell_type);
+        println!("Dump memory: {:?}", options.dump_memory);
         println!("Use new parser: {}", options.new_parser);
+        println!("Preprocess: {}", options.preprocess);
+        println!("Split input: {}", options.split_input);
     });
     let debugger = Rc::new(RefCell::new(BfDebugger::new()));
     let mut program = Box::new(BfProgram::new());
-    let event = match options.program {
+    options.with_verbosity(1, |_options| {
+        println!("Checking program.");
+    });
+    // Splitting the input off and preprocessing need the whole text of the
+    // program, otherwise files and stdin are parsed while they are read, so
+    // that their text is never held in memory all at once.
+    let whole = options.split_input || options.preprocess;
+    let (source, diagnostics) = match options.program {
+        _ if whole => {
+            match check_text(&mut options, &mut program) {
+                Ok(checked) => checked,
+                Err(error) => return Some(error),
+            }
+        },
         ProgramOptions::Path(ref path) => {
-            if options.new_parser {
-                NewParser::parse_file(path, &mut program)
+            let file = match File::open(path) {
+                Ok(file) => file,
+                Err(error) => return Some((2, Error::new(
+                    error.kind(),
+                    format!(
+                        "Could not open file with path: {:?}\n\
+                        Error by `open`: {}",
+                        path,
+                        error
+                    )
+                ))),
+            };
+            let diagnostics = if options.new_parser {
+                NewParser::check_reader(file, &mut program)
             } else {
-                NormalParser::parse_file(path, &mut program)
-            }
+                NormalParser::check_reader(file, &mut program)
+            };
+            (Source::File(path.clone()), diagnostics)
         },
         ProgramOptions::Raw(ref prog) => {
-            if options.new_parser {
-                NewParser::parse_string(prog, &mut program)
+            let diagnostics = if options.new_parser {
+                NewParser::check_string(prog, &mut program)
             } else {
-                NormalParser::parse_string(prog, &mut program)
-            }
-        }
+                NormalParser::check_string(prog, &mut program)
+            };
+            (Source::Text(SourceMap::new("<raw>", prog)), diagnostics)
+        },
+        ProgramOptions::Stdin => {
+            let stdin = stdin();
+            let diagnostics = if options.new_parser {
+                NewParser::check_reader(stdin.lock(), &mut program)
+            } else {
+                NormalParser::check_reader(stdin.lock(), &mut program)
+            };
+            (Source::Stdin, diagnostics)
+        },
     };
+    if diagnostics.has_errors() {
+        // Keep the exit code of unmatched brackets apart from other errors.
+        let brackets = [
+            BfErrorKind::UnmatchedLeftBracket.to_string(),
+            BfErrorKind::UnmatchedRightBracket.to_string(),
+        ];
+        let code = match diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.is_error())
+            .all(|diagnostic| brackets.iter().any(|b| b == diagnostic.name()))
+        {
+            true => 4,
+            false => 2,
+        };
+        return Some((code, Error::other(source.render(&diagnostics))));
+    }
+    let map = source.map();
+    for warning in diagnostics.iter() {
+        match map {
+            Some(ref map) => eprintln!("{}", warning.render(map)),
+            None => eprintln!("{}", warning),
+        }
+    }
     options.with_verbosity(1, |_options| {
         println!("Parsing ok.");
     });
@@ -81,49 +513,47 @@ fn run() -> Option<(i32, Error)> {
             println!("{:#?}", program);
         }
     });
-    debugger.borrow_mut().push(event);
-    someerror!(debugger.borrow_mut().last_event(), 2);
-    options.with_verbosity(1, |_options| {
-        println!("Trying to populate bracket map.");
-    });
-    debugger.borrow_mut().push(program.populate_map());
-    someerror!(debugger.borrow_mut().last_event(), 3);
-    options.with_verbosity(1, |_options| {
-        println!("Trying to calculate bracket map.");
-    });
-    debugger.borrow_mut().push(program.calculate_map());
-    someerror!(debugger.borrow_mut().last_event(), 4);
 
-    let memory: Box<Memory> = Box::new(options.memory_options.clone().into());
-    let mut runner = BfRunner::new(
-        program,
-        memory,
-        Some(Rc::clone(&debugger)),
-    );
+    if let Some(ref compile_options) = options.compile {
+        return compile(&program, &options, compile_options);
+    }
 
+    let cell_type = match options.cell_type {
+        Some(cell_type) => cell_type,
+        None => CellType::fitting(&options.memory_options),
+    };
+    if !cell_type.holds(&options.memory_options) {
+        let message = match options.memory_options.on_overflow() {
+            CellMode::Unbounded if !cfg!(feature = "bignum") => {
+                "Unbounded cells need the `bignum` feature.".to_string()
+            },
+            CellMode::Unbounded => {
+                format!("Unbounded cells cannot be stored as {}.", cell_type)
+            },
+            _ => {
+                format!("Cells with these bounds do not fit in {}.", cell_type)
+            },
+        };
+        return Some((1, Error::new(ErrorKind::InvalidInput, message)));
+    }
     options.with_verbosity(1, |_options| {
-        println!("Runner created.");
+        println!("Cell type: {}", cell_type);
     });
-
-    while let Some(_) = runner.run_once() {
-        options.with_verbosity(2, |_options| {
-            println!("Running once.");
-        });
-        if let Some(event) = debugger.borrow_mut().last_event() {
-            options.with_verbosity(2, |_options| {
-                println!("\n{:?}", event);
-            });
-        }
-        options.with_verbosity(3, {
-            let runner = &runner;
-            move |_options| {
-                runner.print_pointer();
-                runner.print_cell();
-            }
-        });
+    let result = match cell_type {
+        CellType::U8 => execute::<u8>(program, &options, &debugger),
+        CellType::U16 => execute::<u16>(program, &options, &debugger),
+        CellType::U32 => execute::<u32>(program, &options, &debugger),
+        CellType::U64 => execute::<u64>(program, &options, &debugger),
+        CellType::I128 => execute::<CellNumber>(program, &options, &debugger),
+        #[cfg(feature = "bignum")]
+        CellType::Big => execute::<BigInt>(program, &options, &debugger),
+    };
+    if result.is_some() {
+        return result;
     }
 
     println!();
+    someerror!(debugger.borrow_mut().last_event(), 7, source);
     options.with_verbosity(1, |_options| {
         println!("All OK.");
     });
@@ -94,7 +94,7 @@ impl PartialEq for _Bracket {
 
 impl PartialOrd for _Bracket {
     fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
-        return self.location.partial_cmp(&rhs.location);
+        return Some(self.cmp(rhs));
     }
 }
 
@@ -133,7 +133,7 @@ impl BracketMap {
     fn get_sorted_brackets(&self) -> Vec<_Bracket> {
         let mut brackets: Vec<_Bracket> = Vec::new();
         for (location, bracket) in self.map.iter() {
-            let location = location.clone();
+            let location = *location;
             let bracket = bracket.clone();
             let bracket = _Bracket {location, bracket};
             brackets.push(bracket);
@@ -237,16 +237,58 @@ impl BracketMap {
             }
         }
 
+        // Any left bracket still on the stack is never closed.
+        if let Some(top) = stack.last() {
+            return Err((BfErrorKind::UnmatchedLeftBracket, top.location));
+        }
+
         // Set the new map as the true map.
         self.map = map;
         return Ok(());
     }
 
+    /// Finds every [`Bracket`] which does not have a counterpart, sorted by
+    /// location. Unlike [`BracketMap::pair_up`], this does not stop at the
+    /// first one, and the map is left as it is.
+    pub fn unmatched(&self) -> Vec<(BfErrorKind, usize)> {
+        let mut unmatched = Vec::new();
+        let mut stack: Vec<_Bracket> = Vec::new();
+        for _bracket in self.get_sorted_brackets() {
+            if _bracket.bracket.btype().is_left() {
+                stack.push(_bracket);
+                continue;
+            }
+            let kind = _bracket.bracket.kind();
+            match stack.last() {
+                Some(top) if top.bracket.kind() == kind => {
+                    stack.pop();
+                },
+                _ => unmatched.push((
+                    BfErrorKind::UnmatchedRightBracket,
+                    _bracket.location
+                )),
+            }
+        }
+        for _bracket in stack {
+            let location = _bracket.location;
+            unmatched.push((BfErrorKind::UnmatchedLeftBracket, location));
+        }
+        unmatched.sort_by_key(|&(_, location)| location);
+        return unmatched;
+    }
+
     pub fn get_counterpart(&self, index: usize) -> Option<usize> {
         return self.map.get(&index)?.counterpart();
     }
 }
 
+impl Default for BracketMap {
+    /// Creates an empty [`BracketMap`].
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
 impl AsRef<BMap> for BracketMap {
     fn as_ref(&self) -> &BMap {
         return &self.map;
@@ -3,7 +3,10 @@
 //! This module defines traits for virtual machines which run Brainfuck
 //! commands.
 
-use crate::debug::Event;
+use crate::{
+    debug::{Event, OkEvent, Status},
+    vm::CellNumber,
+};
 
 /// A [`CommandRunner`] can run Brainfuck commands.
 pub trait CommandRunner {
@@ -19,12 +22,111 @@ pub trait CommandRunner {
     /// Move the pointer to the right.
     fn next(&mut self) -> Event;
 
+    /// Add `amount` to the memory cell, which has the same effect as
+    /// incrementing (or decrementing if `amount` is negative) the cell
+    /// `amount` times.
+    /// 
+    /// The default implementation does exactly that, so virtual machines
+    /// should override it with something faster.
+    fn add(&mut self, amount: CellNumber) -> Event {
+        let mut event = OkEvent::Status(Status::new("Add: OK"));
+        for _i in 0..amount.unsigned_abs() {
+            event = match amount > 0 {
+                true => self.increment(),
+                false => self.decrement(),
+            }?;
+        }
+        return Ok(event);
+    }
+
+    /// Move the pointer `amount` cells to the right (or to the left if
+    /// `amount` is negative).
+    /// 
+    /// The default implementation calls [`CommandRunner::next`] or
+    /// [`CommandRunner::previous`] `amount` times.
+    fn shift(&mut self, amount: isize) -> Event {
+        let mut event = OkEvent::Status(Status::new("Shift: OK"));
+        for _i in 0..amount.unsigned_abs() {
+            event = match amount > 0 {
+                true => self.next(),
+                false => self.previous(),
+            }?;
+        }
+        return Ok(event);
+    }
+
+    /// Sets the memory cell to zero, which has the same effect as `[-]`.
+    /// 
+    /// The default implementation decrements the cell until it is zero.
+    fn set_zero(&mut self) -> Event {
+        let mut event = OkEvent::Status(Status::new("Set zero: OK"));
+        loop {
+            let mut is_zero = false;
+            self.is_zero(&mut is_zero)?;
+            if is_zero {
+                return Ok(event);
+            }
+            event = self.decrement()?;
+        }
+    }
+
+    /// Adds the memory cell multiplied by `factor` to the cell `offset`
+    /// cells away, leaving the current cell as it is. This is what a loop
+    /// such as `[->+++<]` does to the cell next to it, except that the loop
+    /// also clears the current cell: [`crate::code::ir::recognize_idioms`]
+    /// always follows the [`crate::code::ir::BfOp::MulAdd`]s of a loop with
+    /// a [`crate::code::ir::BfOp::SetZero`], which callers have to do as
+    /// well.
+    /// 
+    /// The default implementation runs the loop and adds the number of
+    /// iterations back to the cell afterwards.
+    fn multiply_add(&mut self, offset: isize, factor: CellNumber) -> Event {
+        let mut count: CellNumber = 0;
+        loop {
+            let mut is_zero = false;
+            self.is_zero(&mut is_zero)?;
+            if is_zero {
+                break;
+            }
+            self.decrement()?;
+            self.shift(offset)?;
+            self.add(factor)?;
+            self.shift(-offset)?;
+            count += 1;
+        }
+        return self.add(count);
+    }
+
+    /// Moves the pointer `step` cells at a time until it reaches a cell
+    /// which is zero, which has the same effect as `[>]` or `[<]`.
+    /// 
+    /// The default implementation calls [`CommandRunner::shift`] until the
+    /// cell is zero.
+    fn scan(&mut self, step: isize) -> Event {
+        let mut event = OkEvent::Status(Status::new("Scan: OK"));
+        loop {
+            let mut is_zero = false;
+            self.is_zero(&mut is_zero)?;
+            if is_zero {
+                return Ok(event);
+            }
+            event = self.shift(step)?;
+        }
+    }
+
     /// Read out the memory cell as a character.
     fn read_out(&self, output: &mut char) -> Event;
 
     /// Write a character into the memory cell.
     fn write_in(&mut self, input: char) -> Event;
 
+    /// Handles a `,` when there is no more input to be read.
+    /// 
+    /// By default, the memory cell is left unchanged.
+    fn end_of_input(&mut self) -> Event {
+        return Ok(OkEvent::Status(Status::new("End of input: OK")));
+    }
+
     /// Checks if the current memory cell is zero.
     fn is_zero(&self, output: &mut bool) -> Event;
 
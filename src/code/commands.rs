@@ -10,7 +10,7 @@ use super::{Bracket, BType, Span};
 
 /// This is a representation of Brainfuck commands, which can take any of the
 /// 8 variants here.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum BfCommand {
     /// '+'
     Increment,
@@ -1,19 +1,39 @@
 //! [`brainfucklib::code::context`]
 //! 
 //! This module provides implementations to give Brainfuck code a context.
+//! 
+//! A [`Span`] records which bytes of a program a command came from, while a
+//! [`SourceMap`] holds the text of the program so that a [`Span`] can be
+//! turned into a line and column, and shown underneath the line it is on.
+//! 
+//! A program can be made of more than one file when it is preprocessed with
+//! [`crate::parser::Preprocessor`], so every [`Span`] also records which of
+//! the files in its [`SourceMap`] it points into.
+
+use std::{fs::read_to_string, io, path::Path};
 
 /// A [`Span`] represents a range of locations a segment of bytes occur,
-/// starting from `start` and ending at but not including `end`.
-#[derive(Clone, Copy, Debug)]
+/// starting from `start` and ending at but not including `end`, in one of
+/// the files of a [`SourceMap`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Span {
     start: usize,
     end: usize,
+    file: usize,
 }
 
 impl Span {
-    /// Creates a new [`Span`].
+    /// Creates a new [`Span`] in the first file of a [`SourceMap`], which is
+    /// the program itself.
     pub fn new(start: usize, end: usize) -> Self {
-        return Self {start, end};
+        return Self {start, end, file: 0};
+    }
+
+    /// Moves the [`Span`] into the file numbered `file`, as returned by
+    /// [`SourceMap::add_file`].
+    pub fn in_file(mut self, file: usize) -> Self {
+        self.file = file;
+        return self;
     }
 
     /// Get the starting location of the segment of bytes.
@@ -26,10 +46,256 @@ impl Span {
     pub fn end(&self) -> usize {
         return self.end;
     }
+
+    /// Get the number of the file the segment of bytes is in.
+    pub fn file(&self) -> usize {
+        return self.file;
+    }
+
+    /// Creates a [`Span`] from the start of this one to the end of `other`.
+    /// If `other` is in another file or comes before this one, this
+    /// [`Span`] is returned as it is.
+    pub fn to(self, other: Span) -> Self {
+        if other.file != self.file || other.end < self.start {
+            return self;
+        }
+        return Self {end: other.end, ..self};
+    }
 }
 
 impl From<pest::Span<'_>> for Span {
     fn from(span: pest::Span) -> Self {
         return Self::new(span.start(), span.end());
     }
-}
\ No newline at end of file
+}
+
+/// The text of one file of a program, with where each of its lines start.
+#[derive(Clone, Debug)]
+struct SourceFile {
+    name: String,
+    text: String,
+    lines: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(name: String, text: String) -> Self {
+        let mut lines = vec![0];
+        for (index, byte) in text.bytes().enumerate() {
+            if byte == b'\n' {
+                lines.push(index + 1);
+            }
+        }
+        return Self {name, text, lines};
+    }
+
+    fn line_column(&self, offset: usize) -> (usize, usize) {
+        let offset = self.floor(offset);
+        let line = match self.lines.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        let column = self.text[self.lines[line]..offset].chars().count();
+        return (line + 1, column + 1);
+    }
+
+    fn line(&self, number: usize) -> Option<&str> {
+        let start = *self.lines.get(number.checked_sub(1)?)?;
+        let end = match self.lines.get(number) {
+            Some(&next) => next,
+            None => self.text.len(),
+        };
+        return Some(self.text[start..end].trim_end_matches(['\n', '\r']));
+    }
+
+    /// Moves `offset` back to the start of the character it is in, and to
+    /// the end of the file if it is past it.
+    fn floor(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        return offset;
+    }
+}
+
+/// The text of a Brainfuck program, along with the name it is shown with
+/// and where each of its lines start.
+/// 
+/// The program is the first file of the [`SourceMap`]. Files which it
+/// includes are added with [`SourceMap::add_file`].
+/// 
+/// Lines and columns start from 1, like in the output of `rustc`, and
+/// columns count characters instead of bytes.
+#[derive(Clone, Debug)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    /// Creates a new [`SourceMap`] of `text`, which is shown as `name`.
+    pub fn new<N, S>(name: N, text: S) -> Self
+    where
+        N: AsRef<str>,
+        S: AsRef<str>
+    {
+        let file = SourceFile::new(
+            name.as_ref().to_string(),
+            text.as_ref().to_string()
+        );
+        return Self {files: vec![file]};
+    }
+
+    /// Reads the program in the file at `path`, which is shown as `path`.
+    pub fn from_file<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>
+    {
+        let text = read_to_string(&path)?;
+        return Ok(Self::new(path.as_ref().display().to_string(), text));
+    }
+
+    /// Adds another file, which is shown as `name`, and returns its number.
+    pub fn add_file<N, S>(&mut self, name: N, text: S) -> usize
+    where
+        N: AsRef<str>,
+        S: AsRef<str>
+    {
+        self.files.push(SourceFile::new(
+            name.as_ref().to_string(),
+            text.as_ref().to_string()
+        ));
+        return self.files.len() - 1;
+    }
+
+    /// Gets the number of files, including the program.
+    pub fn file_count(&self) -> usize {
+        return self.files.len();
+    }
+
+    /// Gets the name the program is shown with.
+    pub fn name(&self) -> &str {
+        return &self.files[0].name;
+    }
+
+    /// Gets the text of the program.
+    pub fn text(&self) -> &str {
+        return &self.files[0].text;
+    }
+
+    /// Gets the number of lines in the program.
+    pub fn line_count(&self) -> usize {
+        return self.files[0].lines.len();
+    }
+
+    /// Gets the name the file numbered `file` is shown with.
+    pub fn file_name(&self, file: usize) -> Option<&str> {
+        return self.files.get(file).map(|file| file.name.as_str());
+    }
+
+    /// Gets the text of the file numbered `file`.
+    pub fn file_text(&self, file: usize) -> Option<&str> {
+        return self.files.get(file).map(|file| file.text.as_str());
+    }
+
+    /// Gets the line and column `span` starts at in its file. Offsets past
+    /// the end of the file are moved to the end of the file.
+    pub fn line_column(&self, span: Span) -> (usize, usize) {
+        return self.file(span).line_column(span.start());
+    }
+
+    /// Gets the text of line `number` of the file numbered `file`, without
+    /// its line break.
+    pub fn line(&self, file: usize, number: usize) -> Option<&str> {
+        return self.files.get(file)?.line(number);
+    }
+
+    /// Gets where `span` starts, as `name:line:column`.
+    pub fn location(&self, span: Span) -> String {
+        let (line, column) = self.line_column(span);
+        return format!("{}:{}:{}", self.file(span).name, line, column);
+    }
+
+    /// Shows the lines each [`Span`] in `labels` starts on, with carets
+    /// underneath the [`Span`] followed by its label, like this:
+    /// 
+    /// ```text
+    ///   |
+    /// 1 | +[>+<-
+    ///   |  ^ this `[` is never closed
+    /// ```
+    /// 
+    /// A [`Span`] which covers more than one line is only underlined up to
+    /// the end of its first line. The lines of the file the first label is
+    /// in are shown first, and the lines of every other file are shown
+    /// after a `::: name:line:column` header. `gutter` is the smallest width
+    /// of the column of line numbers, so that the snippet can be lined up
+    /// with other text.
+    pub fn snippet(&self, labels: &[(Span, &str)], gutter: usize) -> String {
+        let first = labels.first().map_or(0, |&(span, _)| span.file());
+        let mut labels: Vec<(usize, usize, Span, &str)> = labels
+            .iter()
+            .map(|&(span, label)| {
+                let (line, column) = self.line_column(span);
+                (line, column, span, label)
+            })
+            .collect();
+        labels.sort_by_key(|&(line, column, span, _)| {
+            (span.file() != first, span.file(), line, column)
+        });
+        let gutter = labels
+            .iter()
+            .map(|&(line, _, _, _)| line.to_string().len())
+            .fold(gutter, usize::max);
+        let mut snippet = format!("{:1$} |\n", "", gutter);
+        let mut previous = None;
+        let mut file = first;
+        for (line, column, span, label) in labels {
+            if span.file() != file {
+                file = span.file();
+                previous = None;
+                snippet.push_str(&format!(
+                    "{0:2$} |\n{0:2$}::: {1}\n{0:2$} |\n",
+                    "",
+                    self.location(span),
+                    gutter
+                ));
+            }
+            let source = self.file(span);
+            let text = source.line(line).unwrap_or("");
+            if previous.is_some_and(|previous| previous + 1 < line) {
+                snippet.push_str(&format!("{:1$}...\n", "", gutter));
+            }
+            if previous != Some(line) {
+                snippet.push_str(
+                    &format!("{:>1$} | {2}\n", line, gutter, text)
+                );
+            }
+            previous = Some(line);
+            // Tabs are kept so that the carets line up with the line above.
+            let indent: String = text
+                .chars()
+                .take(column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let start = source.floor(span.start());
+            let end = source.floor(span.end()).min(
+                source.lines.get(line).map_or(source.text.len(), |&n| n)
+            );
+            let width = source.text[start..end.max(start)]
+                .trim_end_matches(['\n', '\r'])
+                .chars()
+                .count()
+                .max(1);
+            let row = format!("{}{} {}", indent, "^".repeat(width), label);
+            snippet.push_str(
+                &format!("{:1$} | {2}\n", "", gutter, row.trim_end())
+            );
+        }
+        return snippet;
+    }
+
+    /// Gets the file `span` is in, or the program if there is no such file.
+    fn file(&self, span: Span) -> &SourceFile {
+        return self.files.get(span.file()).unwrap_or(&self.files[0]);
+    }
+}
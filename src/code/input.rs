@@ -0,0 +1,168 @@
+//! [`brainfucklib::code::input`]
+//! 
+//! This module defines [`BfInput`], which reads the input consumed by `,`
+//! from a buffered reader, and [`InputMode`], which decides how much of that
+//! input each `,` takes. [`write_char`] writes the output of `.` in the same
+//! [`InputMode`], so that whatever `,` reads can be written back unchanged.
+
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+use crate::debug::{BfError, BfErrorKind};
+
+/// How much input a single `,` consumes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InputMode {
+    /// Read a whole line and keep only its first character. The rest of the
+    /// line, including the newline, is discarded.
+    Line,
+    /// Read exactly one byte. A newline is delivered as 10. `.` writes the
+    /// cell as one byte as well, so bytes which are not ASCII are written
+    /// back as they were read.
+    Byte,
+    /// Read exactly one UTF-8 encoded character. Invalid sequences are
+    /// delivered as U+FFFD.
+    Char,
+}
+
+impl Default for InputMode {
+    /// Reads one byte at a time by default.
+    fn default() -> Self {
+        return InputMode::Byte;
+    }
+}
+
+impl FromStr for InputMode {
+    type Err = BfError;
+
+    /// Parses "line", "byte" or "char" into an [`InputMode`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Ok(match s {
+            "line" => InputMode::Line,
+            "byte" => InputMode::Byte,
+            "char" => InputMode::Char,
+            _ => return Err(BfError::new(
+                BfErrorKind::Other,
+                format!("{} is not a valid input mode.", s)
+            )),
+        });
+    }
+}
+
+impl fmt::Display for InputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", match self {
+            InputMode::Line => "line",
+            InputMode::Byte => "byte",
+            InputMode::Char => "char",
+        });
+    }
+}
+
+/// The source of input for `,`, which hands out one character at a time
+/// according to its [`InputMode`]. Anything read but not yet consumed is
+/// kept in the buffer of the reader for the next `,`.
+#[derive(Debug)]
+pub struct BfInput<R>
+where
+    R: BufRead,
+{
+    reader: R,
+    mode: InputMode,
+}
+
+impl<R> BfInput<R>
+where
+    R: BufRead,
+{
+    /// Creates a new [`BfInput`].
+    pub fn new(reader: R, mode: InputMode) -> Self {
+        return Self {reader, mode};
+    }
+
+    /// Get the [`InputMode`] of the input.
+    pub fn mode(&self) -> InputMode {
+        return self.mode;
+    }
+
+    /// Set the [`InputMode`] of the input.
+    pub fn set_mode(&mut self, mode: InputMode) {
+        self.mode = mode;
+    }
+
+    /// Reads the next character. [`None`] is returned if there is no more
+    /// input.
+    pub fn read_char(&mut self) -> io::Result<Option<char>> {
+        return match self.mode {
+            InputMode::Line => self.read_line(),
+            InputMode::Byte => Ok(self.read_byte()?.map(char::from)),
+            InputMode::Char => self.read_utf8(),
+        };
+    }
+
+    /// Reads a single byte.
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let buffer = self.reader.fill_buf()?;
+        let byte = match buffer.first() {
+            Some(byte) => *byte,
+            None => return Ok(None),
+        };
+        self.reader.consume(1);
+        return Ok(Some(byte));
+    }
+
+    /// Reads a whole line and returns its first character.
+    fn read_line(&mut self) -> io::Result<Option<char>> {
+        let mut line = String::new();
+        self.reader.read_line(&mut line)?;
+        return Ok(line.chars().next());
+    }
+
+    /// Reads a single UTF-8 encoded character.
+    fn read_utf8(&mut self) -> io::Result<Option<char>> {
+        let first = match self.read_byte()? {
+            Some(byte) => byte,
+            None => return Ok(None),
+        };
+        let width = match first {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return Ok(Some(char::REPLACEMENT_CHARACTER)),
+        };
+        let mut bytes = vec![first];
+        for _i in 1..width {
+            match self.read_byte()? {
+                Some(byte) => bytes.push(byte),
+                None => return Ok(Some(char::REPLACEMENT_CHARACTER)),
+            }
+        }
+        return Ok(Some(
+            std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|s| s.chars().next())
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+        ));
+    }
+}
+
+/// Writes `character`, the output of a `.`, to `output`. In
+/// [`InputMode::Byte`], it is written as a single byte (its lowest 8 bits),
+/// like `putchar` in C. Otherwise, it is encoded as UTF-8.
+pub fn write_char<W>(
+    output: &mut W,
+    character: char,
+    mode: InputMode
+) -> io::Result<()>
+where
+    W: Write
+{
+    return match mode {
+        InputMode::Byte => output.write_all(&[character as u32 as u8]),
+        InputMode::Line | InputMode::Char => write!(output, "{}", character),
+    };
+}
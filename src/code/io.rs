@@ -0,0 +1,139 @@
+//! [`brainfucklib::code::io`]
+//! 
+//! This module defines [`BfIo`], the input and output of a runner. Both
+//! [`crate::code::BfRunner`] and [`crate::code::IrRunner`] are made from a
+//! [`BfIo`] and hand every `.` and `,` to it, so that they read and write in
+//! exactly the same way.
+
+use std::{
+    fs::File,
+    io::{self, stdin, stdout, BufReader, Cursor, Stdin, Stdout},
+    path::Path,
+};
+use crate::debug::{Event, ErrEvent, BfError, BfErrorKind};
+use super::{input::write_char, BfInput, CommandRunner, InputMode};
+
+/// Where a runner reads the input for `,` from and writes the output of `.`
+/// to, along with the [`InputMode`] both of them use.
+#[derive(Debug)]
+pub struct BfIo<R = Stdin, W = Stdout>
+where
+    R: io::Read,
+    W: io::Write,
+{
+    input: BfInput<BufReader<R>>,
+    output: W,
+}
+
+impl BfIo<Stdin, Stdout> {
+    /// Creates a new [`BfIo`] which reads from [`Stdin`] and writes to
+    /// [`Stdout`].
+    pub fn stdio() -> Self {
+        return Self::new(stdin(), stdout());
+    }
+}
+
+impl BfIo<Cursor<Vec<u8>>, Vec<u8>> {
+    /// Creates a new [`BfIo`] which reads from an in-memory buffer and
+    /// writes to another one.
+    pub fn buffers(input: impl Into<Vec<u8>>) -> Self {
+        return Self::new(Cursor::new(input.into()), Vec::new());
+    }
+}
+
+impl BfIo<File, File> {
+    /// Creates a new [`BfIo`] which reads from the file at `input_path` and
+    /// writes to the file at `output_path`. The output file is created if it
+    /// does not exist and truncated if it does.
+    pub fn files<P, Q>(input_path: P, output_path: Q) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let input = File::open(input_path)?;
+        let output = File::create(output_path)?;
+        return Ok(Self::new(input, output));
+    }
+}
+
+impl<R, W> BfIo<R, W>
+where
+    R: io::Read,
+    W: io::Write,
+{
+    /// Creates a new [`BfIo`] which reads from `input` and writes to
+    /// `output`, in the default [`InputMode`].
+    pub fn new(input: R, output: W) -> Self {
+        let input = BfInput::new(BufReader::new(input), InputMode::default());
+        return Self {input, output};
+    }
+
+    /// Get the [`InputMode`] of the input and output.
+    pub fn mode(&self) -> InputMode {
+        return self.input.mode();
+    }
+
+    /// Set the [`InputMode`] of the input and output.
+    pub fn set_mode(&mut self, mode: InputMode) {
+        self.input.set_mode(mode);
+    }
+
+    /// Get a reference to the output.
+    pub fn output(&self) -> &W {
+        return &self.output;
+    }
+
+    /// Get a mutable reference to the output.
+    pub fn output_mut(&mut self) -> &mut W {
+        return &mut self.output;
+    }
+
+    /// Runs a `.`, writing the current cell of `memory` to the output with
+    /// [`write_char`].
+    pub fn output_from<M>(&mut self, memory: &M) -> Event
+    where
+        M: CommandRunner + ?Sized
+    {
+        let mut output: char = 'f';
+        let event = memory.read_out(&mut output);
+        let mode = self.mode();
+        let written = write_char(&mut self.output, output, mode)
+            .and_then(|_| self.output.flush());
+        return match written {
+            Ok(_) => event,
+            Err(error) => Err(ErrEvent::Error(BfError::new(
+                BfErrorKind::Other,
+                format!("Could not write output.\n\
+                Output Error: {}", error)
+            ))),
+        };
+    }
+
+    /// Runs a `,`, reading the next character into the current cell of
+    /// `memory`. The output is flushed first, so that a prompt is shown
+    /// before the program waits for input.
+    pub fn input_into<M>(&mut self, memory: &mut M) -> Event
+    where
+        M: CommandRunner + ?Sized
+    {
+        let _ = self.output.flush();
+        return match self.input.read_char() {
+            Err(error) => Err(ErrEvent::Error(BfError::new(
+                BfErrorKind::Other,
+                format!("Could not read user input.\n\
+                Input Error: {}", error)
+            ))),
+            Ok(Some(character)) => memory.write_in(character),
+            Ok(None) => memory.end_of_input(),
+        };
+    }
+
+    /// Splits the [`BfIo`] into its input and output, so that another
+    /// engine can read and write with them.
+    #[cfg(feature = "jit")]
+    pub(crate) fn parts_mut(
+        &mut self
+    ) -> (&mut BfInput<BufReader<R>>, &mut W) {
+        return (&mut self.input, &mut self.output);
+    }
+}
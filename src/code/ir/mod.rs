@@ -0,0 +1,25 @@
+//! [`brainfucklib::code::ir`]
+//! 
+//! This module defines an intermediate representation of a [`BfProgram`]
+//! which can be run faster than the program itself. Runs of `+`/`-` and
+//! `<`/`>` are folded into single instructions and the targets of jumps are
+//! resolved ahead of time, while the [`Span`]s of the original commands are
+//! kept for diagnostics.
+//! 
+//! Once lowered, [`passes`] can replace common loops with single
+//! instructions.
+//! 
+//! [`BfProgram`]: crate::code::BfProgram
+//! [`Span`]: crate::code::Span
+
+pub mod ops;
+pub mod passes;
+pub mod program;
+pub mod runner;
+
+pub use self::{
+    ops::{BfInstruction, BfOp},
+    passes::recognize_idioms,
+    program::BfIr,
+    runner::IrRunner,
+};
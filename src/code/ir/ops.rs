@@ -0,0 +1,84 @@
+//! [`brainfucklib::code::ir::ops`]
+//! 
+//! This module defines the instructions of the intermediate representation.
+
+use std::fmt;
+
+use crate::{
+    code::Span,
+    vm::CellNumber,
+};
+
+/// An operation in the intermediate representation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BfOp {
+    /// Add a number to the memory cell. A run of `+` and `-`.
+    Add(CellNumber),
+    /// Move the pointer by a number of cells. A run of `<` and `>`.
+    Move(isize),
+    /// '.'
+    Output,
+    /// ','
+    Input,
+    /// '[', jumping to the instruction at the index if the cell is zero.
+    JumpIfZero(usize),
+    /// ']', jumping to the instruction at the index if the cell is not zero.
+    JumpIfNotZero(usize),
+    /// Set the memory cell to zero. A `[-]` or `[+]` loop.
+    SetZero,
+    /// Add the memory cell multiplied by a factor to the cell at an offset.
+    /// Part of a loop such as `[->+>++<<]`, which is always followed by a
+    /// [`BfOp::SetZero`].
+    MulAdd(isize, CellNumber),
+    /// Move the pointer by a number of cells until it reaches a cell which
+    /// is zero. A `[>]` or `[<]` loop.
+    Scan(isize),
+}
+
+impl fmt::Display for BfOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use BfOp::*;
+        return match self {
+            Add(amount) => write!(f, "add {}", amount),
+            Move(amount) => write!(f, "move {}", amount),
+            Output => write!(f, "output"),
+            Input => write!(f, "input"),
+            JumpIfZero(target) => write!(f, "jz {}", target),
+            JumpIfNotZero(target) => write!(f, "jnz {}", target),
+            SetZero => write!(f, "zero"),
+            MulAdd(offset, factor) => {
+                write!(f, "muladd {} {}", offset, factor)
+            },
+            Scan(step) => write!(f, "scan {}", step),
+        };
+    }
+}
+
+/// A [`BfOp`] with the [`Span`] of the commands it was lowered from.
+#[derive(Clone, Copy, Debug)]
+pub struct BfInstruction {
+    op: BfOp,
+    span: Span,
+}
+
+impl BfInstruction {
+    /// Creates a new [`BfInstruction`].
+    pub fn new(op: BfOp, span: Span) -> Self {
+        return Self {op, span};
+    }
+
+    /// Get the operation of the instruction.
+    pub fn op(&self) -> BfOp {
+        return self.op;
+    }
+
+    /// Get a mutable reference to the operation of the instruction.
+    pub fn op_mut(&mut self) -> &mut BfOp {
+        return &mut self.op;
+    }
+
+    /// Get the span of the commands the instruction was lowered from.
+    pub fn span(&self) -> Span {
+        return self.span;
+    }
+}
@@ -0,0 +1,96 @@
+//! [`brainfucklib::code::ir::passes`]
+//! 
+//! This module defines passes which rewrite a [`BfIr`] into something
+//! faster to run. The loops recognized here are:
+//! 1. `[-]` and `[+]`, which become a [`BfOp::SetZero`],
+//! 2. balanced loops such as `[->+>++<<]`, which become a [`BfOp::MulAdd`]
+//!    for every cell changed followed by a [`BfOp::SetZero`], and
+//! 3. `[>]` and `[<]`, which become a [`BfOp::Scan`].
+//! 
+//! These rewrites assume that memory cells wrap around.
+
+use std::collections::BTreeMap;
+
+use crate::vm::CellNumber;
+use super::{BfInstruction, BfIr, BfOp};
+
+/// Replaces clear loops, multiplication loops and scan loops in `ir` with
+/// the equivalent instructions. The [`crate::code::Span`] of every new
+/// instruction covers the whole loop it replaces.
+pub fn recognize_idioms(ir: &mut BfIr) {
+    let instructions: &Vec<BfInstruction> = ir.as_ref();
+    let mut optimized: Vec<BfInstruction> = Vec::new();
+    let mut index = 0;
+    while index < instructions.len() {
+        let instruction = instructions[index];
+        if let BfOp::JumpIfZero(end) = instruction.op() {
+            if let Some(ops) = recognize_loop(&instructions[index + 1..end]) {
+                let span = instruction.span().to(instructions[end].span());
+                for op in ops {
+                    optimized.push(BfInstruction::new(op, span));
+                }
+                index = end + 1;
+                continue;
+            }
+        }
+        optimized.push(instruction);
+        index += 1;
+    }
+    relink(&mut optimized);
+    *ir.as_mut() = optimized;
+}
+
+/// Tries to turn the body of a loop into straight-line instructions.
+fn recognize_loop(body: &[BfInstruction]) -> Option<Vec<BfOp>> {
+    let ops: Vec<BfOp> = body.iter().map(|i| i.op()).collect();
+    match ops.as_slice() {
+        [BfOp::Add(1)] | [BfOp::Add(-1)] => return Some(vec![BfOp::SetZero]),
+        [BfOp::Move(step)] => return Some(vec![BfOp::Scan(*step)]),
+        _ => (),
+    }
+
+    // Work out how much every cell changes in one iteration.
+    let mut offset: isize = 0;
+    let mut deltas: BTreeMap<isize, CellNumber> = BTreeMap::new();
+    for op in ops.iter() {
+        match op {
+            BfOp::Add(amount) => *deltas.entry(offset).or_insert(0) += amount,
+            BfOp::Move(amount) => offset += amount,
+            _ => return None,
+        }
+    }
+    // The loop has to end where it started and count the current cell
+    // down (or up) by exactly 1 each iteration.
+    if offset != 0 {
+        return None;
+    }
+    let step = deltas.remove(&0)?;
+    if step != 1 && step != -1 {
+        return None;
+    }
+    let mut replacement: Vec<BfOp> = deltas.into_iter()
+        .filter(|(_, delta)| *delta != 0)
+        .map(|(offset, delta)| BfOp::MulAdd(offset, -step * delta))
+        .collect();
+    replacement.push(BfOp::SetZero);
+    return Some(replacement);
+}
+
+/// Resolves the targets of all the jumps in `instructions` again after
+/// instructions have been added or removed.
+fn relink(instructions: &mut [BfInstruction]) {
+    let mut stack: Vec<usize> = Vec::new();
+    for index in 0..instructions.len() {
+        match instructions[index].op() {
+            BfOp::JumpIfZero(_) => stack.push(index),
+            BfOp::JumpIfNotZero(_) => {
+                // Lowering already checked that the brackets are balanced.
+                let left = stack.pop()
+                    .expect("Brackets in a BfIr should be balanced.");
+                *instructions[left].op_mut() = BfOp::JumpIfZero(index);
+                *instructions[index].op_mut() = BfOp::JumpIfNotZero(left);
+            },
+            _ => (),
+        }
+    }
+}
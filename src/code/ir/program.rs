@@ -0,0 +1,132 @@
+//! [`brainfucklib::code::ir::program`]
+//! 
+//! A [`BfIr`] is a [`BfProgram`] lowered into the intermediate
+//! representation.
+
+use crate::{
+    code::{BfCommand, BfProgram, BfToken, Span},
+    debug::{BfError, BfErrorKind, BfResult},
+};
+use super::{BfInstruction, BfOp};
+
+/// A [`BfProgram`] lowered into a list of [`BfInstruction`]s.
+#[derive(Clone, Debug)]
+pub struct BfIr {
+    instructions: Vec<BfInstruction>,
+}
+
+impl BfIr {
+    /// Lowers a [`BfProgram`] into a [`BfIr`].
+    /// 
+    /// Consecutive `+`/`-` and `<`/`>` are folded into a single
+    /// [`BfOp::Add`] or [`BfOp::Move`], and runs which cancel out are
+    /// removed altogether. If the brackets in the program are not balanced,
+    /// an error is returned.
+    pub fn lower(program: &BfProgram) -> BfResult<Self> {
+        let tokens: &Vec<BfToken> = program.as_ref();
+        let mut instructions: Vec<BfInstruction> = Vec::new();
+        let mut stack: Vec<(usize, Span)> = Vec::new();
+        let mut index = 0;
+        while index < tokens.len() {
+            let token = &tokens[index];
+            let mut span = token.span();
+            let op = match token.command() {
+                BfCommand::Increment | BfCommand::Decrement => {
+                    let mut amount = 0;
+                    while let Some(token) = tokens.get(index) {
+                        match token.command() {
+                            BfCommand::Increment => amount += 1,
+                            BfCommand::Decrement => amount -= 1,
+                            _ => break,
+                        }
+                        span = span.to(token.span());
+                        index += 1;
+                    }
+                    if amount == 0 {
+                        continue;
+                    }
+                    BfOp::Add(amount)
+                },
+                BfCommand::Previous | BfCommand::Next => {
+                    let mut amount = 0;
+                    while let Some(token) = tokens.get(index) {
+                        match token.command() {
+                            BfCommand::Next => amount += 1,
+                            BfCommand::Previous => amount -= 1,
+                            _ => break,
+                        }
+                        span = span.to(token.span());
+                        index += 1;
+                    }
+                    if amount == 0 {
+                        continue;
+                    }
+                    BfOp::Move(amount)
+                },
+                BfCommand::Read => {
+                    index += 1;
+                    BfOp::Output
+                },
+                BfCommand::Write => {
+                    index += 1;
+                    BfOp::Input
+                },
+                BfCommand::IfZero => {
+                    index += 1;
+                    stack.push((instructions.len(), token.span()));
+                    // The target is filled in when the matching ']' is
+                    // found.
+                    BfOp::JumpIfZero(0)
+                },
+                BfCommand::IfNotZero => {
+                    index += 1;
+                    let (left, _) = match stack.pop() {
+                        Some(left) => left,
+                        None => return Err(BfError::new(
+                            BfErrorKind::UnmatchedRightBracket,
+                            "Unmatched ]"
+                        ).with_span(token.span())),
+                    };
+                    let right = instructions.len();
+                    *instructions[left].op_mut() = BfOp::JumpIfZero(right);
+                    BfOp::JumpIfNotZero(left)
+                },
+            };
+            instructions.push(BfInstruction::new(op, span));
+        }
+        if let Some((_, span)) = stack.pop() {
+            return Err(BfError::new(
+                BfErrorKind::UnmatchedLeftBracket,
+                "Unmatched ["
+            ).with_span(span));
+        }
+        return Ok(Self {instructions});
+    }
+
+    /// Get the instruction at `index`.
+    pub fn instruction(&self, index: usize) -> Option<BfInstruction> {
+        return self.instructions.get(index).copied();
+    }
+
+    /// Get the number of instructions.
+    pub fn len(&self) -> usize {
+        return self.instructions.len();
+    }
+
+    /// Checks if there are no instructions.
+    pub fn is_empty(&self) -> bool {
+        return self.instructions.is_empty();
+    }
+}
+
+impl AsRef<Vec<BfInstruction>> for BfIr {
+    fn as_ref(&self) -> &Vec<BfInstruction> {
+        return &self.instructions;
+    }
+}
+
+impl AsMut<Vec<BfInstruction>> for BfIr {
+    fn as_mut(&mut self) -> &mut Vec<BfInstruction> {
+        return &mut self.instructions;
+    }
+}
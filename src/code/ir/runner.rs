@@ -0,0 +1,236 @@
+//! [`brainfucklib::code::ir::runner`]
+//! 
+//! This module defines [`IrRunner`], which runs a [`BfIr`] on a
+//! [`CommandRunner`] in the same way [`crate::code::BfRunner`] runs a
+//! [`crate::code::BfProgram`].
+
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{self, Cursor, Stdin, Stdout},
+    iter::Iterator,
+    path::Path,
+    rc::Rc,
+};
+#[cfg(feature = "jit")]
+use std::io::BufReader;
+use crate::{
+    code::{runner::print_memory, BfIo, CommandRunner, InputMode},
+    debug::{Debugger, BfDebugger},
+    vm::Memory,
+};
+#[cfg(feature = "jit")]
+use crate::code::BfInput;
+use super::{BfIr, BfOp};
+
+/// An [`IrRunner`] runs a [`BfIr`] one instruction at a time.
+/// 
+/// Input for `,` is read from `R` and output from `.` is written to `W`.
+/// 
+/// A program runs exactly as it would on a [`crate::code::BfRunner`] only
+/// if its cells wrap around and its pointer never raises an error. Folding
+/// `+-` or `<>` or turning `[+]` into a [`BfOp::SetZero`] changes what a
+/// program does when its cells saturate or it raises errors instead.
+#[derive(Debug)]
+pub struct IrRunner<D = BfDebugger, M = Memory, R = Stdin, W = Stdout>
+where
+    D: Debugger,
+    M: CommandRunner,
+    R: io::Read,
+    W: io::Write,
+{
+    program: Box<BfIr>,
+    program_pointer: usize,
+    memory: Box<M>,
+    debugger: Option<Rc<RefCell<D>>>,
+    io: BfIo<R, W>,
+}
+
+impl<D, M> IrRunner<D, M, Stdin, Stdout>
+where
+    D: Debugger,
+    M: CommandRunner,
+{
+    /// Creates a new [`IrRunner`] which reads from [`Stdin`] and writes to
+    /// [`Stdout`].
+    pub fn new(
+        program: Box<BfIr>,
+        memory: Box<M>,
+        debugger: Option<Rc<RefCell<D>>>,
+    ) -> Self {
+        return Self::from_io(program, memory, debugger, BfIo::stdio());
+    }
+}
+
+impl<D, M> IrRunner<D, M, Cursor<Vec<u8>>, Vec<u8>>
+where
+    D: Debugger,
+    M: CommandRunner,
+{
+    /// Creates a new [`IrRunner`] which reads from an in-memory buffer and
+    /// writes to another one. The output can be retrieved with
+    /// [`IrRunner::output`].
+    pub fn with_buffers(
+        program: Box<BfIr>,
+        memory: Box<M>,
+        debugger: Option<Rc<RefCell<D>>>,
+        input: impl Into<Vec<u8>>,
+    ) -> Self {
+        let io = BfIo::buffers(input);
+        return Self::from_io(program, memory, debugger, io);
+    }
+}
+
+impl<D, M> IrRunner<D, M, File, File>
+where
+    D: Debugger,
+    M: CommandRunner,
+{
+    /// Creates a new [`IrRunner`] which reads from the file at `input_path`
+    /// and writes to the file at `output_path`. The output file is created
+    /// if it does not exist and truncated if it does.
+    pub fn with_files<P, Q>(
+        program: Box<BfIr>,
+        memory: Box<M>,
+        debugger: Option<Rc<RefCell<D>>>,
+        input_path: P,
+        output_path: Q,
+    ) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let io = BfIo::files(input_path, output_path)?;
+        return Ok(Self::from_io(program, memory, debugger, io));
+    }
+}
+
+impl<D, M, R, W> IrRunner<D, M, R, W>
+where
+    D: Debugger,
+    M: CommandRunner,
+    R: io::Read,
+    W: io::Write,
+{
+    /// Creates a new [`IrRunner`] which reads from `input` and writes to
+    /// `output`.
+    pub fn with_io(
+        program: Box<BfIr>,
+        memory: Box<M>,
+        debugger: Option<Rc<RefCell<D>>>,
+        input: R,
+        output: W,
+    ) -> Self {
+        let io = BfIo::new(input, output);
+        return Self::from_io(program, memory, debugger, io);
+    }
+
+    /// Creates a new [`IrRunner`] which reads and writes with `io`.
+    pub fn from_io(
+        program: Box<BfIr>,
+        memory: Box<M>,
+        debugger: Option<Rc<RefCell<D>>>,
+        io: BfIo<R, W>,
+    ) -> Self {
+        let program_pointer = 0;
+        return Self {program, program_pointer, memory, debugger, io};
+    }
+
+    /// Sets how much input each `,` consumes.
+    pub fn with_input_mode(mut self, mode: InputMode) -> Self {
+        self.io.set_mode(mode);
+        return self;
+    }
+
+    pub fn run_once(&mut self) -> Option<()> {
+        use BfOp::*;
+        let instruction = self.program.instruction(self.program_pointer)?;
+        let event = match instruction.op() {
+            Add(amount) => self.memory.add(amount),
+            Move(amount) => self.memory.shift(amount),
+            Output => self.io.output_from(&*self.memory),
+            Input => self.io.input_into(&mut *self.memory),
+            JumpIfZero(target) => {
+                let mut is_zero = false;
+                let event = self.memory.is_zero(&mut is_zero);
+                if event.is_ok() && is_zero {
+                    self.program_pointer = target;
+                }
+                event
+            },
+            JumpIfNotZero(target) => {
+                let mut is_zero = false;
+                let event = self.memory.is_zero(&mut is_zero);
+                if event.is_ok() && !is_zero {
+                    self.program_pointer = target;
+                }
+                event
+            },
+            SetZero => self.memory.set_zero(),
+            MulAdd(offset, factor) => {
+                self.memory.multiply_add(offset, factor)
+            },
+            Scan(step) => self.memory.scan(step),
+        };
+        let event = event.map_err(|error| {
+            error.with_span(instruction.span())
+        });
+        self.program_pointer += 1;
+        let output = event.is_ok();
+        if let Some(ref debugger) = self.debugger {
+            debugger.borrow_mut().push(event);
+        }
+        return match output {
+            true => Some(()),
+            false => None,
+        };
+    }
+
+    pub fn get_debugger(&mut self) -> Option<Rc<RefCell<D>>> {
+        return match self.debugger {
+            Some(ref mut d) => Some(Rc::clone(d)),
+            None => None,
+        };
+    }
+
+    /// Get a reference to the virtual machine the program runs on.
+    pub fn memory(&self) -> &M {
+        return &self.memory;
+    }
+
+    /// Get a reference to the output the runner writes to.
+    pub fn output(&self) -> &W {
+        return self.io.output();
+    }
+
+    /// Get a mutable reference to the output the runner writes to.
+    pub fn output_mut(&mut self) -> &mut W {
+        return self.io.output_mut();
+    }
+
+    /// Splits the runner into its program, virtual machine, input and
+    /// output, so that another engine can run the program with them.
+    #[cfg(feature = "jit")]
+    pub(crate) fn parts_mut(
+        &mut self
+    ) -> (&BfIr, &mut M, &mut BfInput<BufReader<R>>, &mut W) {
+        let (input, output) = self.io.parts_mut();
+        return (&self.program, &mut self.memory, input, output);
+    }
+}
+
+print_memory!(IrRunner);
+
+impl<D, M, R, W> Iterator for IrRunner<D, M, R, W>
+where
+    D: Debugger,
+    M: CommandRunner,
+    R: io::Read,
+    W: io::Write,
+{
+    type Item = ();
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.run_once();
+    }
+}
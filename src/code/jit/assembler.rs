@@ -0,0 +1,299 @@
+//! [`brainfucklib::code::jit::assembler`]
+//! 
+//! This module defines [`Assembler`], which translates a [`BfIr`] into
+//! x86-64 machine code.
+//! 
+//! The machine code is a function with the System V calling convention:
+//! 
+//! ```text
+//! fn(tape: *mut u8, index: *mut u64, context: *mut c_void, length: u64)
+//!     -> u64
+//! ```
+//! 
+//! `index` holds the position of the pointer, which is read when the
+//! function starts and written back when it returns. The function returns 0
+//! if the program finished, or the non-zero value returned by one of the
+//! [`Callbacks`] if it failed.
+//! 
+//! While the program runs, `rbx` holds `tape`, `r12` holds the index of the
+//! pointer, `r13` holds `context` and `r14` holds `length`. The pointer
+//! always wraps around the end of the tape, which cannot grow.
+
+use crate::code::{BfIr, ir::BfOp};
+
+const RAX: u8 = 0;
+const RCX: u8 = 1;
+const RDX: u8 = 2;
+const RBX: u8 = 3;
+const RBP: u8 = 5;
+const RSI: u8 = 6;
+const RDI: u8 = 7;
+const R12: u8 = 12;
+const R13: u8 = 13;
+const R14: u8 = 14;
+const R15: u8 = 15;
+
+/// Addresses of the functions the machine code calls for `.` and `,`.
+/// 
+/// Both are called with `context` and a pointer to the current cell, and
+/// return 0 on success.
+#[derive(Clone, Copy, Debug)]
+pub struct Callbacks {
+    pub output: u64,
+    pub input: u64,
+}
+
+/// Translates a [`BfIr`] into x86-64 machine code.
+#[derive(Debug)]
+pub struct Assembler {
+    code: Vec<u8>,
+    scale: u8,
+    length: u64,
+    exits: Vec<usize>,
+}
+
+impl Assembler {
+    /// Translates `ir` into machine code for a tape of `length` cells which
+    /// are `1 << scale` bytes wide.
+    pub fn compile(
+        ir: &BfIr,
+        scale: u8,
+        length: usize,
+        callbacks: Callbacks
+    ) -> Vec<u8> {
+        let mut assembler = Self {
+            code: Vec::new(),
+            scale,
+            length: length as u64,
+            exits: Vec::new(),
+        };
+        assembler.prologue();
+        let mut loops = Vec::new();
+        for instruction in ir.as_ref().iter() {
+            match instruction.op() {
+                BfOp::Add(amount) => {
+                    assembler.mov_imm(RAX, amount as u64);
+                    assembler.cell(&[0x00], &[0x01], RAX, R12);
+                },
+                BfOp::Move(amount) => assembler.shift(amount),
+                BfOp::Output => assembler.call(callbacks.output),
+                BfOp::Input => assembler.call(callbacks.input),
+                BfOp::JumpIfZero(_) => {
+                    assembler.compare_zero();
+                    let exit = assembler.jump(&[0x0F, 0x84]);
+                    loops.push((exit, assembler.code.len()));
+                },
+                BfOp::JumpIfNotZero(_) => {
+                    let (exit, start) = loops.pop()
+                        .expect("BfIr should have matching jumps.");
+                    assembler.compare_zero();
+                    let back = assembler.jump(&[0x0F, 0x85]);
+                    assembler.patch(back, start);
+                    assembler.patch(exit, assembler.code.len());
+                },
+                BfOp::SetZero => {
+                    assembler.code.extend_from_slice(&[0x31, 0xC0]);
+                    assembler.cell(&[0x88], &[0x89], RAX, R12);
+                },
+                BfOp::MulAdd(offset, factor) => {
+                    assembler.load(RAX);
+                    assembler.mov_imm(RCX, factor as u64);
+                    assembler.register(true, &[0x0F, 0xAF], RAX, RCX);
+                    assembler.register(true, &[0x89], R12, RDX);
+                    assembler.mov_imm(RCX, assembler.offset(offset));
+                    assembler.register(true, &[0x01], RCX, RDX);
+                    assembler.wrap(RDX, RCX);
+                    assembler.cell(&[0x00], &[0x01], RAX, RDX);
+                },
+                BfOp::Scan(step) => {
+                    let start = assembler.code.len();
+                    assembler.compare_zero();
+                    let exit = assembler.jump(&[0x0F, 0x84]);
+                    assembler.shift(step);
+                    let back = assembler.jump(&[0xE9]);
+                    assembler.patch(back, start);
+                    assembler.patch(exit, assembler.code.len());
+                },
+            }
+        }
+        assembler.epilogue();
+        return assembler.code;
+    }
+
+    /// Saves the registers the program uses and loads its arguments.
+    fn prologue(&mut self) {
+        for register in [RBX, RBP, R12, R13, R14, R15].iter() {
+            self.push(*register);
+        }
+        // Keeps the stack aligned to 16 bytes for the callbacks.
+        self.code.extend_from_slice(&[0x48, 0x83, 0xEC, 0x08]);
+        self.register(true, &[0x89], RDI, RBX);
+        self.register(true, &[0x89], RSI, RBP);
+        // mov r12, [rsi]
+        self.code.extend_from_slice(&[0x4C, 0x8B, 0x26]);
+        self.register(true, &[0x89], RDX, R13);
+        self.register(true, &[0x89], RCX, R14);
+    }
+
+    /// Writes back the pointer, restores the registers and returns.
+    fn epilogue(&mut self) {
+        self.code.extend_from_slice(&[0x31, 0xC0]);
+        let end = self.code.len();
+        for exit in std::mem::take(&mut self.exits) {
+            self.patch(exit, end);
+        }
+        // mov [rbp], r12
+        self.code.extend_from_slice(&[0x4C, 0x89, 0x65, 0x00]);
+        self.code.extend_from_slice(&[0x48, 0x83, 0xC4, 0x08]);
+        for register in [R15, R14, R13, R12, RBP, RBX].iter() {
+            self.pop(*register);
+        }
+        self.code.push(0xC3);
+    }
+
+    /// Reduces a shift of the pointer to a number below the length of the
+    /// tape.
+    fn offset(&self, amount: isize) -> u64 {
+        return (amount as i128).rem_euclid(self.length as i128) as u64;
+    }
+
+    /// Moves the pointer by `amount` cells.
+    fn shift(&mut self, amount: isize) {
+        let offset = self.offset(amount);
+        if offset == 0 {
+            return;
+        }
+        self.mov_imm(RAX, offset);
+        self.register(true, &[0x01], RAX, R12);
+        self.wrap(R12, RAX);
+    }
+
+    /// Subtracts the length of the tape from `index` if `index` is past the
+    /// end of the tape, using `scratch` as a temporary register.
+    fn wrap(&mut self, index: u8, scratch: u8) {
+        self.register(true, &[0x89], index, scratch);
+        self.register(true, &[0x29], R14, scratch);
+        // cmovae index, scratch
+        self.register(true, &[0x0F, 0x43], index, scratch);
+    }
+
+    /// Calls `function` with the context and a pointer to the current cell
+    /// and leaves the program if it fails.
+    fn call(&mut self, function: u64) {
+        // lea rsi, [rbx + r12 * scale]
+        self.memory(false, true, &[0x8D], RSI, R12);
+        self.register(true, &[0x89], R13, RDI);
+        self.mov_imm(RAX, function);
+        self.code.extend_from_slice(&[0xFF, 0xD0]);
+        self.register(true, &[0x85], RAX, RAX);
+        let exit = self.jump(&[0x0F, 0x85]);
+        self.exits.push(exit);
+    }
+
+    /// Compares the current cell with 0.
+    fn compare_zero(&mut self) {
+        self.cell(&[0x80], &[0x83], 7, R12);
+        self.code.push(0x00);
+    }
+
+    /// Loads the current cell into `register`, filling the rest of the
+    /// register with zeroes.
+    fn load(&mut self, register: u8) {
+        match self.scale {
+            0 => self.memory(false, false, &[0x0F, 0xB6], register, R12),
+            1 => self.memory(false, false, &[0x0F, 0xB7], register, R12),
+            2 => self.memory(false, false, &[0x8B], register, R12),
+            _ => self.memory(false, true, &[0x8B], register, R12),
+        }
+    }
+
+    /// Emits an instruction which works on the cell at `[rbx + index *
+    /// scale]` with the width of a cell, using `byte` as the opcode for
+    /// 1-byte cells and `wide` as the opcode for wider cells.
+    fn cell(&mut self, byte: &[u8], wide: &[u8], register: u8, index: u8) {
+        match self.scale {
+            0 => self.memory(false, false, byte, register, index),
+            1 => self.memory(true, false, wide, register, index),
+            2 => self.memory(false, false, wide, register, index),
+            _ => self.memory(false, true, wide, register, index),
+        }
+    }
+
+    /// Emits an instruction whose memory operand is `[rbx + index * scale]`.
+    fn memory(
+        &mut self,
+        word: bool,
+        wide: bool,
+        opcode: &[u8],
+        register: u8,
+        index: u8
+    ) {
+        if word {
+            self.code.push(0x66);
+        }
+        let rex = Self::rex(wide, register, index, RBX);
+        if rex != 0x40 {
+            self.code.push(rex);
+        }
+        self.code.extend_from_slice(opcode);
+        self.code.push(0x04 | (register & 7) << 3);
+        self.code.push(self.scale << 6 | (index & 7) << 3 | RBX);
+    }
+
+    /// Emits an instruction which works on two registers, where `register`
+    /// goes into the `reg` field and `rm` into the `r/m` field.
+    fn register(&mut self, wide: bool, opcode: &[u8], register: u8, rm: u8) {
+        let rex = Self::rex(wide, register, 0, rm);
+        if rex != 0x40 {
+            self.code.push(rex);
+        }
+        self.code.extend_from_slice(opcode);
+        self.code.push(0xC0 | (register & 7) << 3 | (rm & 7));
+    }
+
+    /// Emits `mov register, value`.
+    fn mov_imm(&mut self, register: u8, value: u64) {
+        self.code.push(Self::rex(true, 0, 0, register));
+        self.code.push(0xB8 | (register & 7));
+        self.code.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn push(&mut self, register: u8) {
+        if register >= 8 {
+            self.code.push(0x41);
+        }
+        self.code.push(0x50 | (register & 7));
+    }
+
+    fn pop(&mut self, register: u8) {
+        if register >= 8 {
+            self.code.push(0x41);
+        }
+        self.code.push(0x58 | (register & 7));
+    }
+
+    /// Emits a jump with a 32-bit displacement and returns where the
+    /// displacement is, so that it can be patched later.
+    fn jump(&mut self, opcode: &[u8]) -> usize {
+        self.code.extend_from_slice(opcode);
+        let displacement = self.code.len();
+        self.code.extend_from_slice(&[0; 4]);
+        return displacement;
+    }
+
+    /// Points the jump whose displacement is at `displacement` to `target`.
+    fn patch(&mut self, displacement: usize, target: usize) {
+        let relative = target as i64 - (displacement as i64 + 4);
+        let bytes = (relative as i32).to_le_bytes();
+        self.code[displacement..displacement + 4].copy_from_slice(&bytes);
+    }
+
+    /// Builds a REX prefix.
+    fn rex(wide: bool, register: u8, index: u8, base: u8) -> u8 {
+        return 0x40
+            | (wide as u8) << 3
+            | (register >> 3) << 2
+            | (index >> 3) << 1
+            | (base >> 3);
+    }
+}
@@ -0,0 +1,64 @@
+//! [`brainfucklib::code::jit::buffer`]
+//! 
+//! This module defines [`ExecutableBuffer`], a block of memory mapped with
+//! `mmap` which holds machine code and can be executed.
+
+use std::{io, ptr};
+
+/// A block of executable memory which is unmapped when it is dropped.
+#[derive(Debug)]
+pub struct ExecutableBuffer {
+    pointer: *mut libc::c_void,
+    length: usize,
+}
+
+impl ExecutableBuffer {
+    /// Copies `code` into a new block of memory and makes it executable.
+    /// 
+    /// The memory is never writable and executable at the same time.
+    pub fn new(code: &[u8]) -> io::Result<Self> {
+        let length = code.len().max(1);
+        let pointer = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                length,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if pointer == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let buffer = Self {pointer, length};
+        unsafe {
+            ptr::copy_nonoverlapping(
+                code.as_ptr(),
+                pointer as *mut u8,
+                code.len()
+            );
+            if libc::mprotect(
+                pointer,
+                length,
+                libc::PROT_READ | libc::PROT_EXEC
+            ) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        return Ok(buffer);
+    }
+
+    /// Gets a pointer to the start of the machine code.
+    pub fn as_ptr(&self) -> *const u8 {
+        return self.pointer as *const u8;
+    }
+}
+
+impl Drop for ExecutableBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.pointer, self.length);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! [`brainfucklib::code::jit`]
+//! 
+//! This module defines [`JitRunner`], which compiles a [`crate::code::BfIr`]
+//! into x86-64 machine code and runs it, for programs which spend a long
+//! time running. It is only built with the `jit` feature.
+//! 
+//! On other architectures, [`JitRunner`] runs the program with an
+//! [`crate::code::IrRunner`] instead.
+
+#[cfg(all(target_arch = "x86_64", unix))]
+pub mod assembler;
+#[cfg(all(target_arch = "x86_64", unix))]
+pub mod buffer;
+pub mod runner;
+
+#[cfg(all(target_arch = "x86_64", unix))]
+pub use self::{
+    assembler::{Assembler, Callbacks},
+    buffer::ExecutableBuffer,
+};
+pub use self::runner::{JitCell, JitRunner};
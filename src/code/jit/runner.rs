@@ -0,0 +1,409 @@
+//! [`brainfucklib::code::jit::runner`]
+//! 
+//! This module defines [`JitRunner`], which compiles a [`BfIr`] into
+//! machine code and runs it on the tape of a [`Memory`], falling back to an
+//! [`IrRunner`] when it cannot.
+
+use std::{
+    cell::RefCell,
+    io::{self, Cursor, Stdin, Stdout},
+    iter::Iterator,
+    rc::Rc,
+};
+use crate::{
+    code::{BfIr, InputMode, IrRunner},
+    compile::compiler_traits::native_width,
+    debug::{Debugger, BfDebugger},
+    vm::{CellMode, CellNumber, CellValue, Memory, PointerPolicy},
+};
+
+/// An unsigned integer which a cell can be stored as in machine code.
+pub trait JitCell: CellValue + Copy {
+    /// How many bits to shift an index by to get the offset of a cell in
+    /// bytes.
+    const SCALE: u8;
+}
+
+impl JitCell for u8 {
+    const SCALE: u8 = 0;
+}
+
+impl JitCell for u16 {
+    const SCALE: u8 = 1;
+}
+
+impl JitCell for u32 {
+    const SCALE: u8 = 2;
+}
+
+impl JitCell for u64 {
+    const SCALE: u8 = 3;
+}
+
+/// A [`JitRunner`] runs a [`BfIr`] as x86-64 machine code.
+/// 
+/// The program is only compiled if the tape has a fixed length and its
+/// cells wrap around like an unsigned integer (such as 0 to 255). Otherwise,
+/// or on other architectures, the program is run by an [`IrRunner`] one
+/// instruction at a time, which you can check with
+/// [`JitRunner::is_native`].
+/// 
+/// A program which is compiled runs in one go the first time
+/// [`JitRunner::run_once`] is called, and pushes a single event to the
+/// debugger. If the cells of the [`Memory`] are stored as the same unsigned
+/// integer as in machine code (such as [`u8`] for cells from 0 to 255), the
+/// machine code runs on the tape itself. Otherwise the tape is copied
+/// before the program runs and back once it is done.
+#[derive(Debug)]
+pub struct JitRunner<D = BfDebugger, T = CellNumber, R = Stdin, W = Stdout>
+where
+    D: Debugger,
+    T: CellValue,
+    R: io::Read,
+    W: io::Write,
+{
+    runner: IrRunner<D, Memory<T>, R, W>,
+    width: Option<u32>,
+    finished: bool,
+}
+
+impl<D, T> JitRunner<D, T, Stdin, Stdout>
+where
+    D: Debugger,
+    T: CellValue,
+{
+    /// Creates a new [`JitRunner`] which reads from [`Stdin`] and writes to
+    /// [`Stdout`].
+    pub fn new(
+        program: Box<BfIr>,
+        memory: Box<Memory<T>>,
+        debugger: Option<Rc<RefCell<D>>>,
+    ) -> Self {
+        let width = Self::width(&memory);
+        let runner = IrRunner::new(program, memory, debugger);
+        return Self {runner, width, finished: false};
+    }
+}
+
+impl<D, T> JitRunner<D, T, Cursor<Vec<u8>>, Vec<u8>>
+where
+    D: Debugger,
+    T: CellValue,
+{
+    /// Creates a new [`JitRunner`] which reads from an in-memory buffer and
+    /// writes to another one. The output can be retrieved with
+    /// [`JitRunner::output`].
+    pub fn with_buffers(
+        program: Box<BfIr>,
+        memory: Box<Memory<T>>,
+        debugger: Option<Rc<RefCell<D>>>,
+        input: impl Into<Vec<u8>>,
+    ) -> Self {
+        let width = Self::width(&memory);
+        let runner = IrRunner::with_buffers(program, memory, debugger, input);
+        return Self {runner, width, finished: false};
+    }
+}
+
+impl<D, T, R, W> JitRunner<D, T, R, W>
+where
+    D: Debugger,
+    T: CellValue,
+    R: io::Read,
+    W: io::Write,
+{
+    /// Creates a new [`JitRunner`] which reads from `input` and writes to
+    /// `output`.
+    pub fn with_io(
+        program: Box<BfIr>,
+        memory: Box<Memory<T>>,
+        debugger: Option<Rc<RefCell<D>>>,
+        input: R,
+        output: W,
+    ) -> Self {
+        let width = Self::width(&memory);
+        let runner = IrRunner::with_io(
+            program,
+            memory,
+            debugger,
+            input,
+            output
+        );
+        return Self {runner, width, finished: false};
+    }
+
+    /// Sets how much input each `,` consumes.
+    pub fn with_input_mode(mut self, mode: InputMode) -> Self {
+        self.runner = self.runner.with_input_mode(mode);
+        return self;
+    }
+
+    /// Finds the number of bits a cell can be stored in, if the program can
+    /// be compiled for `memory` on this architecture.
+    fn width(memory: &Memory<T>) -> Option<u32> {
+        if !cfg!(all(target_arch = "x86_64", unix))
+            || memory.options().on_out_of_bounds() != PointerPolicy::Wrap
+            || memory.options().on_overflow() != CellMode::Wrap
+        {
+            return None;
+        }
+        return native_width(memory.options());
+    }
+
+    /// Checks whether the program is compiled into machine code.
+    pub fn is_native(&self) -> bool {
+        return self.width.is_some();
+    }
+
+    pub fn run_once(&mut self) -> Option<()> {
+        #[cfg(all(target_arch = "x86_64", unix))]
+        if let Some(width) = self.width {
+            if self.finished {
+                return None;
+            }
+            self.finished = true;
+            let event = match width {
+                8 => native::run::<u8, _, _, _, _>(&mut self.runner),
+                16 => native::run::<u16, _, _, _, _>(&mut self.runner),
+                32 => native::run::<u32, _, _, _, _>(&mut self.runner),
+                _ => native::run::<u64, _, _, _, _>(&mut self.runner),
+            };
+            let output = event.is_ok();
+            if let Some(debugger) = self.runner.get_debugger() {
+                debugger.borrow_mut().push(event);
+            }
+            return match output {
+                true => Some(()),
+                false => None,
+            };
+        }
+        return self.runner.run_once();
+    }
+
+    pub fn get_debugger(&mut self) -> Option<Rc<RefCell<D>>> {
+        return self.runner.get_debugger();
+    }
+
+    /// Get a reference to the [`Memory`] the program runs on.
+    pub fn memory(&self) -> &Memory<T> {
+        return self.runner.memory();
+    }
+
+    /// Get a reference to the output the runner writes to.
+    pub fn output(&self) -> &W {
+        return self.runner.output();
+    }
+
+    /// Get a mutable reference to the output the runner writes to.
+    pub fn output_mut(&mut self) -> &mut W {
+        return self.runner.output_mut();
+    }
+
+    pub fn print_pointer(&self) {
+        self.runner.print_pointer();
+    }
+
+    pub fn print_cell(&self) {
+        self.runner.print_cell();
+    }
+}
+
+impl<D, T, R, W> Iterator for JitRunner<D, T, R, W>
+where
+    D: Debugger,
+    T: CellValue,
+    R: io::Read,
+    W: io::Write,
+{
+    type Item = ();
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.run_once();
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", unix))]
+mod native {
+    //! Compiles and runs the program, and the functions the machine code
+    //! calls for `.` and `,`.
+
+    use std::{
+        any::TypeId,
+        io::{self, BufReader},
+    };
+    use crate::{
+        code::{input::write_char, BfInput, IrRunner},
+        code::jit::{Assembler, Callbacks, ExecutableBuffer},
+        debug::{
+            Debugger,
+            Event,
+            OkEvent,
+            Status,
+            ErrEvent,
+            BfError,
+            BfErrorKind,
+        },
+        vm::{
+            CellValue,
+            EofPolicy,
+            Memory,
+            MemoryCell,
+            MemoryOptions,
+            MemoryRange,
+        },
+    };
+    use super::JitCell;
+
+    /// The signature of the machine code made by [`Assembler`].
+    type JitFunction = unsafe extern "sysv64" fn(
+        *mut u8,
+        *mut u64,
+        *mut libc::c_void,
+        u64,
+    ) -> u64;
+
+    /// What the machine code passes to the callbacks.
+    struct Context<'a, R: io::Read, W: io::Write> {
+        input: &'a mut BfInput<BufReader<R>>,
+        output: &'a mut W,
+        options: MemoryOptions,
+        error: Option<BfError>,
+    }
+
+    /// Compiles the program in `runner` and runs it on its memory, storing
+    /// each cell as `J`.
+    pub fn run<J, D, T, R, W>(
+        runner: &mut IrRunner<D, Memory<T>, R, W>
+    ) -> Event
+    where
+        J: JitCell,
+        D: Debugger,
+        T: CellValue,
+        R: io::Read,
+        W: io::Write,
+    {
+        let (program, memory, input, output) = runner.parts_mut();
+        let options = memory.options().clone();
+        let index = memory.index();
+        let cells = memory.cells_mut();
+        // A MemoryCell<J> has the same layout as a J, so a tape of them can
+        // be used as it is. Any other tape has to be copied.
+        let mut copy: Vec<J> = Vec::new();
+        let tape = match TypeId::of::<T>() == TypeId::of::<J>() {
+            true => cells.as_mut_ptr() as *mut u8,
+            false => {
+                copy = cells.iter()
+                    .map(|cell| J::from_number(cell.number()))
+                    .collect();
+                copy.as_mut_ptr() as *mut u8
+            },
+        };
+        let length = cells.len();
+        let callbacks = Callbacks {
+            output: output_callback::<J, R, W> as *const () as u64,
+            input: input_callback::<J, R, W> as *const () as u64,
+        };
+        let code = Assembler::compile(program, J::SCALE, length, callbacks);
+        let buffer = ExecutableBuffer::new(&code)
+            .map_err(|error| ErrEvent::Error(BfError::new(
+                BfErrorKind::Other,
+                format!("Could not allocate executable memory.\n\
+                Error: {}", error)
+            )))?;
+        let function: JitFunction = unsafe {
+            std::mem::transmute(buffer.as_ptr())
+        };
+        let mut index = index as u64;
+        let mut context = Context {input, output, options, error: None};
+        let status = unsafe {
+            function(
+                tape,
+                &mut index,
+                &mut context as *mut Context<R, W> as *mut libc::c_void,
+                length as u64,
+            )
+        };
+        for (cell, value) in cells.iter_mut().zip(copy) {
+            *cell = MemoryCell::new(value.number());
+        }
+        memory.move_to(index as MemoryRange);
+        return match (status, context.error) {
+            (_, Some(error)) => Err(ErrEvent::Error(error)),
+            _ => Ok(OkEvent::Status(Status::new("Run natively: OK"))),
+        };
+    }
+
+    /// Writes the current cell to the output.
+    unsafe extern "sysv64" fn output_callback<T, R, W>(
+        context: *mut libc::c_void,
+        cell: *mut T,
+    ) -> u64
+    where
+        T: JitCell,
+        R: io::Read,
+        W: io::Write,
+    {
+        let context = &mut *(context as *mut Context<R, W>);
+        let mut output: char = 'f';
+        let zero = context.options.zero();
+        let _ = MemoryCell::from_value(*cell).to_char(zero, &mut output);
+        let written = write_char(
+            &mut context.output,
+            output,
+            context.input.mode()
+        ).and_then(|_| context.output.flush());
+        if let Err(error) = written {
+            context.error = Some(BfError::new(
+                BfErrorKind::Other,
+                format!("Could not write output.\n\
+                Output Error: {}", error)
+            ));
+            return 1;
+        }
+        return 0;
+    }
+
+    /// Reads a character from the input into the current cell.
+    unsafe extern "sysv64" fn input_callback<T, R, W>(
+        context: *mut libc::c_void,
+        cell: *mut T,
+    ) -> u64
+    where
+        T: JitCell,
+        R: io::Read,
+        W: io::Write,
+    {
+        let context = &mut *(context as *mut Context<R, W>);
+        let zero = context.options.zero();
+        let lowest = context.options.lowest();
+        let highest = context.options.highest();
+        let mut number = MemoryCell::from_value(*cell);
+        let _ = context.output.flush();
+        match context.input.read_char() {
+            Err(error) => {
+                context.error = Some(BfError::new(
+                    BfErrorKind::Other,
+                    format!("Could not read user input.\n\
+                    Input Error: {}", error)
+                ));
+                return 1;
+            },
+            Ok(Some(character)) => {
+                let _ = number.from_char(
+                    character,
+                    zero,
+                    lowest,
+                    highest,
+                    context.options.on_overflow()
+                );
+            },
+            Ok(None) => match context.options.on_eof() {
+                EofPolicy::Unchanged => (),
+                EofPolicy::Zero => number.flatten(zero),
+                EofPolicy::Max => number.maximize(highest),
+            },
+        }
+        *cell = *number.value();
+        return 0;
+    }
+}
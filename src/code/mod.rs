@@ -6,6 +6,11 @@ pub mod brackets;
 pub mod command_traits;
 pub mod commands;
 pub mod context;
+pub mod input;
+pub mod io;
+pub mod ir;
+#[cfg(feature = "jit")]
+pub mod jit;
 pub mod program;
 pub mod runner;
 
@@ -13,7 +18,12 @@ pub use self::{
     brackets::{Bracket, BType, BracketMap},
     command_traits::CommandRunner,
     commands::{BfCommand, BfToken},
-    context::Span,
+    context::{SourceMap, Span},
+    input::{BfInput, InputMode},
+    io::BfIo,
+    ir::{BfIr, IrRunner},
     program::BfProgram,
     runner::BfRunner,
-};
\ No newline at end of file
+};
+#[cfg(feature = "jit")]
+pub use self::jit::JitRunner;
\ No newline at end of file
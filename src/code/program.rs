@@ -2,8 +2,17 @@
 //! 
 //! A [`BfProgram`] is a representation of a Brainfuck program.
 
-use crate::debug::{Event, OkEvent, Status, ErrEvent, BfError};
-use super::{BfToken, BracketMap};
+use crate::debug::{
+    Event,
+    OkEvent,
+    Status,
+    ErrEvent,
+    BfError,
+    BfErrorKind,
+    Diagnostic,
+    Diagnostics,
+};
+use super::{BfCommand, BfToken, BracketMap, Span};
 
 /// A [`BfProgram`] is a representation of a Brainfuck program.
 #[derive(Clone, Debug)]
@@ -15,8 +24,8 @@ pub struct BfProgram {
 impl BfProgram {
     /// Create a new [`BfProgram`].
     pub fn new() -> Self {
-        let commands: Box<Vec<BfToken>> = Box::new(Vec::new());
-        let bracket_map: Box<BracketMap> = Box::new(BracketMap::new());
+        let commands: Box<Vec<BfToken>> = Box::default();
+        let bracket_map: Box<BracketMap> = Box::default();
         return Self {commands, bracket_map};
     }
 
@@ -47,21 +56,91 @@ impl BfProgram {
             Ok(_) => Ok(
                 OkEvent::Status(Status::new("Calculate bracket map: OK"))
             ),
-            Err((kind, location)) => {
-                let description = format!(
-                    "Unmatched {} at {}",
-                    self.commands
-                        .get(location)
-                        .unwrap()
-                        .command(),
-                    location
-                );
-                Err(ErrEvent::Error(BfError::new(
-                    kind,
-                    description
-                )))
-            },
+            Err((kind, location)) => Err(ErrEvent::Error(
+                self.unmatched(kind, location)
+            )),
+        }
+    }
+
+    /// Finds every unmatched bracket in the program, along with warnings
+    /// about loops which are empty (`[]`), since they never end if the cell
+    /// is not zero. The diagnostics are sorted by where they are in the
+    /// program.
+    /// 
+    /// Unlike [`BfProgram::calculate_map`], this does not stop at the first
+    /// unmatched bracket. [`BfProgram::populate_map`] has to be called
+    /// first.
+    pub fn check(&self) -> Diagnostics {
+        let mut diagnostics = Diagnostics::new();
+        for (kind, location) in self.bracket_map.unmatched() {
+            diagnostics.push(self.unmatched(kind, location).into());
+        }
+        for pair in self.commands.windows(2) {
+            let (left, right) = (&pair[0], &pair[1]);
+            if left.command() == BfCommand::IfZero
+                && right.command() == BfCommand::IfNotZero
+            {
+                let span = Span::new(left.span().start(), right.span().end());
+                diagnostics.push(Diagnostic::warning(
+                    "EmptyLoop",
+                    "Empty loop never ends if the cell is not zero"
+                ).with_span(span));
+            }
         }
+        diagnostics.sort();
+        return diagnostics;
+    }
+
+    /// Creates an error for the unmatched bracket at `location`, which
+    /// points at the bracket and notes where its counterpart was expected.
+    /// 
+    /// For an unmatched `[`, this is the last `]` after it (which closes a
+    /// loop inside it) or the end of the program. For an unmatched `]`, this
+    /// is the last `[` before it, whose loop is already closed.
+    pub(crate) fn unmatched(
+        &self,
+        kind: BfErrorKind,
+        location: usize
+    ) -> BfError {
+        let token = self.commands[location].clone();
+        let error = BfError::new(
+            kind,
+            format!("Unmatched {}", token.command())
+        ).with_span(token.span());
+        return match token.command() {
+            BfCommand::IfZero => {
+                let last = self.commands[location..]
+                    .iter()
+                    .rev()
+                    .find(|other| other.command() == BfCommand::IfNotZero);
+                match last {
+                    Some(last) => error.with_note(
+                        last.span(),
+                        "the last `]` closes a loop inside this one"
+                    ),
+                    None => {
+                        let end = self.commands.last().unwrap().span().end();
+                        error.with_note(
+                            Span::new(end, end),
+                            "the program ends before this loop is closed"
+                        )
+                    },
+                }
+            },
+            _ => {
+                let last = self.commands[..location]
+                    .iter()
+                    .rev()
+                    .find(|other| other.command() == BfCommand::IfZero);
+                match last {
+                    Some(last) => error.with_note(
+                        last.span(),
+                        "the last `[` before it is already closed"
+                    ),
+                    None => error,
+                }
+            },
+        };
     }
 
     /// Clear the program.
@@ -85,20 +164,27 @@ impl BfProgram {
     }
 }
 
+impl Default for BfProgram {
+    /// Creates an empty [`BfProgram`].
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
 impl AsRef<Vec<BfToken>> for BfProgram {
     fn as_ref(&self) -> &Vec<BfToken> {
-        return &*self.commands;
+        return &self.commands;
     }
 }
 
 impl AsMut<Vec<BfToken>> for BfProgram {
     fn as_mut(&mut self) -> &mut Vec<BfToken> {
-        return &mut *self.commands;
+        return &mut self.commands;
     }
 }
 
 impl AsRef<BracketMap> for BfProgram {
     fn as_ref(&self) -> &BracketMap {
-        return &*self.bracket_map;
+        return &self.bracket_map;
     }
 }
\ No newline at end of file
@@ -1,39 +1,176 @@
+//! [`brainfucklib::code::runner`]
+//! 
+//! This module defines [`BfRunner`], which runs a [`BfProgram`] on a
+//! [`CommandRunner`]. The runner reads the input for `,` from any
+//! [`std::io::Read`] and writes the output of `.` to any [`std::io::Write`],
+//! which defaults to [`Stdin`] and [`Stdout`] respectively.
+
 use std::{
     cell::RefCell,
-    io::{stdin, stdout, prelude::*},
+    fs::File,
+    io::{self, Cursor, Stdin, Stdout},
     iter::Iterator,
+    path::Path,
     rc::Rc,
 };
 use crate::{
     debug::{Debugger, BfDebugger, ErrEvent, BfError, BfErrorKind},
-    vm::Memory
+    vm::{CellValue, Memory, MemorySnapshot},
 };
-use super::{BfCommand, BfProgram, CommandRunner};
+use super::{BfCommand, BfIo, BfProgram, CommandRunner, InputMode};
+
+/// Adds `print_pointer` and `print_cell` to a runner for [`Memory`] and
+/// [`crate::vm::SparseMemory`], so that every runner prints them the same
+/// way. The runner needs a field called `memory`.
+macro_rules! print_memory {
+    ($runner: ident) => {
+        impl<D, T, R, W> $runner<D, $crate::vm::Memory<T>, R, W>
+        where
+            D: $crate::debug::Debugger,
+            T: $crate::vm::CellValue,
+            R: ::std::io::Read,
+            W: ::std::io::Write,
+        {
+            pub fn print_pointer(&self) {
+                println!("{:?}", self.memory.pointer());
+            }
 
+            pub fn print_cell(&self) {
+                println!("{:?}", self.memory.get());
+            }
+        }
+
+        impl<D, T, R, W> $runner<D, $crate::vm::SparseMemory<T>, R, W>
+        where
+            D: $crate::debug::Debugger,
+            T: $crate::vm::CellValue,
+            R: ::std::io::Read,
+            W: ::std::io::Write,
+        {
+            pub fn print_pointer(&self) {
+                println!("{:?}", self.memory.pointer());
+            }
+
+            pub fn print_cell(&self) {
+                println!("{:?}", self.memory.get());
+            }
+        }
+    };
+}
+pub(crate) use print_memory;
+
+/// A [`BfRunner`] runs a [`BfProgram`] one command at a time.
+/// 
+/// Input for `,` is read from `R` and output from `.` is written to `W`.
 #[derive(Debug)]
-pub struct BfRunner<D = BfDebugger, M = Memory>
+pub struct BfRunner<D = BfDebugger, M = Memory, R = Stdin, W = Stdout>
 where
     D: Debugger,
     M: CommandRunner,
+    R: io::Read,
+    W: io::Write,
 {
     program: Box<BfProgram>,
     program_pointer: usize,
     memory: Box<M>,
     debugger: Option<Rc<RefCell<D>>>,
+    io: BfIo<R, W>,
 }
 
-impl<D, M> BfRunner<D, M>
+impl<D, M> BfRunner<D, M, Stdin, Stdout>
 where
     D: Debugger,
     M: CommandRunner,
 {
+    /// Creates a new [`BfRunner`] which reads from [`Stdin`] and writes to
+    /// [`Stdout`].
     pub fn new(
         program: Box<BfProgram>,
         memory: Box<M>,
         debugger: Option<Rc<RefCell<D>>>,
+    ) -> Self {
+        return Self::from_io(program, memory, debugger, BfIo::stdio());
+    }
+}
+
+impl<D, M> BfRunner<D, M, Cursor<Vec<u8>>, Vec<u8>>
+where
+    D: Debugger,
+    M: CommandRunner,
+{
+    /// Creates a new [`BfRunner`] which reads from an in-memory buffer and
+    /// writes to another one. The output can be retrieved with
+    /// [`BfRunner::output`].
+    pub fn with_buffers(
+        program: Box<BfProgram>,
+        memory: Box<M>,
+        debugger: Option<Rc<RefCell<D>>>,
+        input: impl Into<Vec<u8>>,
+    ) -> Self {
+        let io = BfIo::buffers(input);
+        return Self::from_io(program, memory, debugger, io);
+    }
+}
+
+impl<D, M> BfRunner<D, M, File, File>
+where
+    D: Debugger,
+    M: CommandRunner,
+{
+    /// Creates a new [`BfRunner`] which reads from the file at `input_path`
+    /// and writes to the file at `output_path`. The output file is created
+    /// if it does not exist and truncated if it does.
+    pub fn with_files<P, Q>(
+        program: Box<BfProgram>,
+        memory: Box<M>,
+        debugger: Option<Rc<RefCell<D>>>,
+        input_path: P,
+        output_path: Q,
+    ) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let io = BfIo::files(input_path, output_path)?;
+        return Ok(Self::from_io(program, memory, debugger, io));
+    }
+}
+
+impl<D, M, R, W> BfRunner<D, M, R, W>
+where
+    D: Debugger,
+    M: CommandRunner,
+    R: io::Read,
+    W: io::Write,
+{
+    /// Creates a new [`BfRunner`] which reads from `input` and writes to
+    /// `output`.
+    pub fn with_io(
+        program: Box<BfProgram>,
+        memory: Box<M>,
+        debugger: Option<Rc<RefCell<D>>>,
+        input: R,
+        output: W,
+    ) -> Self {
+        let io = BfIo::new(input, output);
+        return Self::from_io(program, memory, debugger, io);
+    }
+
+    /// Creates a new [`BfRunner`] which reads and writes with `io`.
+    pub fn from_io(
+        program: Box<BfProgram>,
+        memory: Box<M>,
+        debugger: Option<Rc<RefCell<D>>>,
+        io: BfIo<R, W>,
     ) -> Self {
         let program_pointer = 0;
-        return Self {program, program_pointer, memory, debugger};
+        return Self {program, program_pointer, memory, debugger, io};
+    }
+
+    /// Sets how much input each `,` consumes.
+    pub fn with_input_mode(mut self, mode: InputMode) -> Self {
+        self.io.set_mode(mode);
+        return self;
     }
 
     pub fn run_once(&mut self) -> Option<()> {
@@ -47,33 +184,8 @@ where
             Decrement => self.memory.decrement(),
             Previous => self.memory.previous(),
             Next => self.memory.next(),
-            Read => {
-                let mut output: char = 'f';
-                let event = self.memory.read_out(&mut output);
-                print!("{}", output);
-                let _ = stdout().flush();
-                event
-            },
-            Write => {
-                let mut input = String::new();
-                let _ = stdout().flush();
-                let inres = stdin().read_line(&mut input);
-                if let Err(error) = inres {
-                    Err(ErrEvent::Error(BfError::new(
-                        BfErrorKind::Other,
-                        format!("Could not read user input.
-                        stdin Error: {}", error)
-                    )))
-                } else {
-                    self.memory.write_in(
-                        input.chars()
-                            .collect::<Vec<char>>()
-                            .get(0)
-                            .unwrap()
-                            .clone()
-                    )
-                }
-            },
+            Read => self.io.output_from(&*self.memory),
+            Write => self.io.input_into(&mut *self.memory),
             IfZero => {
                 let mut is_zero = false;
                 let event = self.memory.is_zero(&mut is_zero);
@@ -131,6 +243,7 @@ where
                 }
             }
         };
+        let event = event.map_err(|error| error.with_span(token.span()));
         self.program_pointer += 1;
         let output = event.is_ok();
         if let Some(ref debugger) = self.debugger {
@@ -148,25 +261,67 @@ where
             None => None,
         };
     }
+
+    /// Get the position of the next command to run in the program.
+    pub fn program_pointer(&self) -> usize {
+        return self.program_pointer;
+    }
+
+    /// Get a reference to the virtual machine the program runs on.
+    pub fn memory(&self) -> &M {
+        return &self.memory;
+    }
+
+    /// Get a reference to the output the runner writes to.
+    pub fn output(&self) -> &W {
+        return self.io.output();
+    }
+
+    /// Get a mutable reference to the output the runner writes to.
+    pub fn output_mut(&mut self) -> &mut W {
+        return self.io.output_mut();
+    }
 }
 
-impl<D> BfRunner<D, Memory>
+impl<D, T, R, W> BfRunner<D, Memory<T>, R, W>
 where
     D: Debugger,
+    T: CellValue,
+    R: io::Read,
+    W: io::Write,
 {
-    pub fn print_pointer(&self) {
-        println!("{:?}", self.memory.pointer());
+    /// Takes a [`MemorySnapshot`] of the memory and the position of the
+    /// next command to run, which can be restored later with
+    /// [`BfRunner::restore`].
+    /// 
+    /// The input and output are not part of the snapshot, so restoring it
+    /// does not take back anything the program has read or written.
+    pub fn snapshot(&self) -> MemorySnapshot<T> {
+        return self.memory
+            .snapshot()
+            .with_program_pointer(self.program_pointer);
     }
 
-    pub fn print_cell(&self) {
-        println!("{:?}", self.memory.get());
+    /// Restores the memory and (if the snapshot was taken from a
+    /// [`BfRunner`]) the position of the next command to run to what they
+    /// were when `snapshot` was taken. The snapshot should have been taken
+    /// while running the same program.
+    pub fn restore(&mut self, snapshot: &MemorySnapshot<T>) {
+        self.memory.restore(snapshot);
+        if let Some(program_pointer) = snapshot.program_pointer() {
+            self.program_pointer = program_pointer;
+        }
     }
 }
 
-impl<D, M> Iterator for BfRunner<D, M>
+print_memory!(BfRunner);
+
+impl<D, M, R, W> Iterator for BfRunner<D, M, R, W>
 where
     D: Debugger,
     M: CommandRunner,
+    R: io::Read,
+    W: io::Write,
 {
     type Item = ();
 
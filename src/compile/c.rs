@@ -0,0 +1,397 @@
+//! [`brainfucklib::compile::c`]
+//! 
+//! This module defines [`CCompiler`], which compiles a [`BfProgram`] into a
+//! standalone C99 file that only needs the C standard library. C99 is
+//! needed for `stdint.h` and `long long`.
+//! 
+//! The program reads and writes one byte at a time, the same way the
+//! interpreter does in [`crate::code::InputMode::Byte`].
+//! 
+//! If the bounds of a cell match an unsigned integer (such as 0 to 255 for
+//! `uint8_t`), the cells are stored as that integer and wrap around
+//! natively. Otherwise they are stored as `int64_t` and wrapped by hand, in
+//! which case the range of a cell cannot be larger than 2^31.
+
+use std::io::Write;
+
+use crate::{
+    code::{BfIr, BfProgram, ir::BfOp},
+    debug::{Event, OkEvent, Status, ErrEvent, BfError, BfErrorKind},
+    vm::{CellNumber, EofPolicy, MemoryOptions},
+};
+use super::{
+    BfCompiler,
+    compiler_traits::{native_width, optimized_ir, tape_length, write_source},
+};
+
+/// The part of the C file shared by every program, which relies on the
+/// macros defined before it.
+const RUNTIME: &str = r#"static cell_t *tape;
+static size_t capacity = LENGTH;
+static size_t ptr;
+
+static void bf_init(void) {
+    size_t i;
+    ptr = 0;
+    tape = (cell_t *)malloc(capacity * sizeof(cell_t));
+    if (tape == NULL) {
+        fputs("Could not allocate memory tape.\n", stderr);
+        exit(1);
+    }
+    for (i = 0; i < capacity; i++) {
+        tape[i] = ZERO;
+    }
+#if USES_PRELOAD
+    for (i = 0; i < sizeof(preload) / sizeof(preload[0]); i++) {
+        tape[PRELOAD_OFFSET + i] = preload[i];
+    }
+#endif
+}
+
+#if USES_MOVE || USES_MUL_ADD
+static size_t length = LENGTH;
+
+#if VARIABLE_LENGTH
+static void bf_grow(size_t new_length) {
+    size_t i;
+    size_t new_capacity = capacity;
+    if (new_length <= length) {
+        return;
+    }
+    if (new_length > capacity) {
+        while (new_capacity < new_length) {
+            new_capacity *= 2;
+        }
+        tape = (cell_t *)realloc(tape, new_capacity * sizeof(cell_t));
+        if (tape == NULL) {
+            fputs("Could not grow memory tape.\n", stderr);
+            exit(1);
+        }
+        for (i = capacity; i < new_capacity; i++) {
+            tape[i] = ZERO;
+        }
+        capacity = new_capacity;
+    }
+    length = new_length;
+}
+
+/* Grows by at least the length of the tape, so that moving left one cell at
+ * a time does not copy the whole tape every time. */
+static void bf_grow_left(size_t extra) {
+    size_t i;
+    size_t old_length = length;
+    if (extra < length) {
+        extra = length;
+    }
+    bf_grow(length + extra);
+    memmove(tape + extra, tape, old_length * sizeof(cell_t));
+    for (i = 0; i < extra; i++) {
+        tape[i] = ZERO;
+    }
+    ptr += extra;
+}
+#endif
+
+static size_t bf_target(long long offset) {
+    size_t back;
+    if (offset >= 0) {
+        size_t target = ptr + (size_t)offset;
+        if (target < length) {
+            return target;
+        }
+#if VARIABLE_LENGTH
+        bf_grow(target + 1);
+        return target;
+#else
+        return target % length;
+#endif
+    }
+    back = (size_t)(-offset);
+#if VARIABLE_LENGTH
+    if (back > ptr) {
+        bf_grow_left(back - ptr);
+    }
+    return ptr - back;
+#else
+    back %= length;
+    return (ptr + length - back) % length;
+#endif
+}
+#endif
+
+#if USES_MOVE
+static void bf_move(long long offset) {
+    ptr = bf_target(offset);
+}
+#endif
+
+#if USES_ADD || USES_MUL_ADD
+#if NATIVE
+static cell_t bf_add(cell_t value, cell_t amount) {
+    return (cell_t)(value + amount);
+}
+#else
+static cell_t bf_add(cell_t value, cell_t amount) {
+    cell_t offset = ((value - LOWER) % RANGE + RANGE) % RANGE;
+    return LOWER + (offset + amount) % RANGE;
+}
+#endif
+#endif
+
+#if USES_MUL_ADD
+#if NATIVE
+static cell_t bf_multiply(cell_t value, cell_t factor) {
+    return (cell_t)((unsigned long long)value * factor);
+}
+#else
+static cell_t bf_multiply(cell_t value, cell_t factor) {
+    return ((value % RANGE + RANGE) % RANGE) * factor % RANGE;
+}
+#endif
+
+static void bf_mul_add(long long offset, cell_t factor) {
+    cell_t value = tape[ptr];
+    size_t target;
+    if (value == ZERO) {
+        return;
+    }
+    target = bf_target(offset);
+    tape[target] = bf_add(tape[target], bf_multiply(value - ZERO, factor));
+}
+#endif
+
+#if USES_OUTPUT
+static void bf_output(cell_t value) {
+    unsigned long c = (unsigned long)(uint32_t)(value - ZERO);
+    if (c > 0x10FFFFUL || (c >= 0xD800UL && c <= 0xDFFFUL)) {
+        c = 0xFFFDUL;
+    }
+    putchar((int)(c & 0xFFUL));
+}
+#endif
+
+#if USES_INPUT
+static void bf_input(void) {
+    int c;
+#if !NATIVE
+    cell_t value;
+#endif
+    fflush(stdout);
+    c = getchar();
+    if (c == EOF) {
+        ON_EOF;
+        return;
+    }
+#if NATIVE
+    tape[ptr] = (cell_t)c;
+#else
+    value = ZERO + (cell_t)c;
+    if (value >= LOWER && value < HIGHEST) {
+        tape[ptr] = value;
+    } else {
+        tape[ptr] = LOWER + ((value - LOWER) % RANGE + RANGE) % RANGE;
+    }
+#endif
+}
+#endif
+"#;
+
+/// Compiles a [`BfProgram`] into C.
+pub struct CCompiler;
+
+impl CCompiler {
+    /// Formats `number` as a C integer literal of type `cell_t`.
+    fn literal(number: CellNumber, native: bool) -> String {
+        return match native {
+            true => format!("{}ULL", number),
+            false if number == i64::MIN as CellNumber => {
+                "(-9223372036854775807LL - 1)".to_string()
+            },
+            false => format!("{}LL", number),
+        };
+    }
+
+    /// Writes the macros which configure the runtime for `options` and leave
+    /// out the parts of it which `ir` does not use.
+    fn header(
+        ir: &BfIr,
+        options: &MemoryOptions
+    ) -> Result<String, ErrEvent> {
+        let lowest = options.lowest();
+        let highest = options.highest();
+        let range = highest - lowest;
+        let mut header = String::new();
+        header.push_str("/* Compiled from a Brainfuck program. */\n");
+        header.push_str("#include <stdint.h>\n");
+        header.push_str("#include <stdio.h>\n");
+        header.push_str("#include <stdlib.h>\n");
+        header.push_str("#include <string.h>\n\n");
+        let native = match native_width(options) {
+            Some(bits) => {
+                header.push_str(&format!("typedef uint{}_t cell_t;\n", bits));
+                header.push_str("#define NATIVE 1\n");
+                true
+            },
+            None => {
+                if lowest < i64::MIN as CellNumber
+                    || highest > i64::MAX as CellNumber
+                    || range > 1 << 31
+                {
+                    return Err(ErrEvent::Error(BfError::new(
+                        BfErrorKind::Other,
+                        format!(
+                            "Cells between {} and {} cannot be compiled \
+                            into C.",
+                            lowest,
+                            highest - 1
+                        )
+                    )));
+                }
+                header.push_str("typedef int64_t cell_t;\n");
+                header.push_str("#define NATIVE 0\n");
+                header.push_str(&format!(
+                    "#define HIGHEST ((cell_t){})\n",
+                    Self::literal(highest, false)
+                ));
+                header.push_str(&format!(
+                    "#define RANGE ((cell_t){})\n",
+                    Self::literal(range, false)
+                ));
+                false
+            },
+        };
+        header.push_str(&format!(
+            "#define LOWER ((cell_t){})\n",
+            Self::literal(lowest, native)
+        ));
+        header.push_str(&format!(
+            "#define ZERO ((cell_t){})\n",
+            Self::literal(options.zero(), native)
+        ));
+        header.push_str(&format!(
+            "#define LENGTH ((size_t){}ULL)\n",
+            tape_length(options)
+        ));
+        header.push_str(&format!(
+            "#define VARIABLE_LENGTH {}\n",
+            options.is_variable_length() as u8
+        ));
+        let uses = |predicate: fn(&BfOp) -> bool| {
+            ir.as_ref().iter().any(|i| predicate(&i.op())) as u8
+        };
+        header.push_str(&format!(
+            "#define USES_ADD {}\n",
+            uses(|op| matches!(op, BfOp::Add(_)))
+        ));
+        header.push_str(&format!(
+            "#define USES_MOVE {}\n",
+            uses(|op| matches!(op, BfOp::Move(_) | BfOp::Scan(_)))
+        ));
+        header.push_str(&format!(
+            "#define USES_OUTPUT {}\n",
+            uses(|op| matches!(op, BfOp::Output))
+        ));
+        header.push_str(&format!(
+            "#define USES_INPUT {}\n",
+            uses(|op| matches!(op, BfOp::Input))
+        ));
+        header.push_str(&format!(
+            "#define USES_MUL_ADD {}\n",
+            uses(|op| matches!(op, BfOp::MulAdd(_, _)))
+        ));
+        let on_eof = match options.on_eof() {
+            EofPolicy::Unchanged => "((void)0)".to_string(),
+            EofPolicy::Zero => "(tape[ptr] = ZERO)".to_string(),
+            EofPolicy::Max => format!(
+                "(tape[ptr] = (cell_t){})",
+                Self::literal(highest - 1, native)
+            ),
+        };
+        header.push_str(&format!("#define ON_EOF {}\n", on_eof));
+        let preload = options.preloaded();
+        header.push_str(&format!(
+            "#define USES_PRELOAD {}\n\n",
+            !preload.is_empty() as u8
+        ));
+        if !preload.is_empty() {
+            header.push_str(&format!(
+                "#define PRELOAD_OFFSET ((size_t){}ULL)\n",
+                preload.offset()
+            ));
+            let values: Vec<String> = preload
+                .values(options.zero())
+                .map(|value| Self::literal(value, native))
+                .collect();
+            header.push_str("static const cell_t preload[] = {\n");
+            for line in values.chunks(8) {
+                header.push_str(&format!("    {},\n", line.join(", ")));
+            }
+            header.push_str("};\n\n");
+        }
+        return Ok(header);
+    }
+
+    /// Formats a number which is added to a cell, which is reduced to
+    /// between 0 and the range of the cell first.
+    fn amount(amount: CellNumber, options: &MemoryOptions) -> String {
+        let range = options.highest() - options.lowest();
+        let native = native_width(options).is_some();
+        return Self::literal(amount.rem_euclid(range), native);
+    }
+}
+
+impl BfCompiler for CCompiler {
+    fn compile<W>(
+        program: &BfProgram,
+        options: &MemoryOptions,
+        output: &mut W
+    ) -> Event
+    where
+        W: Write
+    {
+        let ir = optimized_ir(program, options)?;
+        let mut source = Self::header(&ir, options)?;
+        source.push_str(RUNTIME);
+        source.push_str("\nint main(void) {\n    bf_init();\n");
+        let mut depth = 1;
+        for instruction in ir.as_ref().iter() {
+            if let BfOp::JumpIfNotZero(_) = instruction.op() {
+                depth -= 1;
+            }
+            let line = match instruction.op() {
+                BfOp::Add(amount) => format!(
+                    "tape[ptr] = bf_add(tape[ptr], {});",
+                    Self::amount(amount, options)
+                ),
+                BfOp::Move(amount) => format!("bf_move({}LL);", amount),
+                BfOp::Output => "bf_output(tape[ptr]);".to_string(),
+                BfOp::Input => "bf_input();".to_string(),
+                BfOp::JumpIfZero(_) => {
+                    "while (tape[ptr] != ZERO) {".to_string()
+                },
+                BfOp::JumpIfNotZero(_) => "}".to_string(),
+                BfOp::SetZero => "tape[ptr] = ZERO;".to_string(),
+                BfOp::MulAdd(offset, factor) => format!(
+                    "bf_mul_add({}LL, {});",
+                    offset,
+                    Self::amount(factor, options)
+                ),
+                BfOp::Scan(step) => format!(
+                    "while (tape[ptr] != ZERO) bf_move({}LL);",
+                    step
+                ),
+            };
+            source.push_str(&"    ".repeat(depth));
+            source.push_str(&line);
+            source.push('\n');
+            if let BfOp::JumpIfZero(_) = instruction.op() {
+                depth += 1;
+            }
+        }
+        source.push_str("    fflush(stdout);\n");
+        source.push_str("    free(tape);\n");
+        source.push_str("    return 0;\n}\n");
+        write_source(&source, output)?;
+        return Ok(OkEvent::Status(Status::new("Compile to C: OK")));
+    }
+}
@@ -0,0 +1,110 @@
+//! [`brainfucklib::compile::compiler_traits`]
+//! 
+//! This module defines [`BfCompiler`], a trait for structs which compile a
+//! [`BfProgram`] into source code for another language.
+
+use std::io::Write;
+
+use crate::{
+    code::{ir::recognize_idioms, BfIr, BfProgram},
+    debug::{Event, ErrEvent, BfError, BfErrorKind},
+    vm::{CellMode, CellNumber, MemoryOptions, PointerPolicy},
+};
+
+pub trait BfCompiler {
+    /// Compiles `program` into source code, which is written into `output`.
+    fn compile<W>(
+        program: &BfProgram,
+        options: &MemoryOptions,
+        output: &mut W
+    ) -> Event
+    where
+        W: Write;
+
+    /// Compiles `program` into source code, which is appended to `output`.
+    fn compile_string(
+        program: &BfProgram,
+        options: &MemoryOptions,
+        output: &mut String
+    ) -> Event {
+        let mut buffer: Vec<u8> = Vec::new();
+        let event = Self::compile(program, options, &mut buffer)?;
+        output.push_str(&String::from_utf8_lossy(&buffer));
+        return Ok(event);
+    }
+}
+
+/// Lowers `program` into a [`BfIr`] and replaces the common loops in it,
+/// which is the starting point of every compiler.
+/// 
+/// Both steps assume that cells wrap around and that moving the pointer
+/// back and forth never fails, so cells which saturate or raise errors and
+/// pointers which raise errors cannot be compiled.
+pub fn optimized_ir(
+    program: &BfProgram,
+    options: &MemoryOptions
+) -> Result<BfIr, ErrEvent> {
+    if options.on_overflow() != CellMode::Wrap {
+        return Err(ErrEvent::Error(BfError::new(
+            BfErrorKind::Other,
+            format!(
+                "Programs whose cells {} cannot be compiled, only programs \
+                whose cells wrap around.",
+                match options.on_overflow() {
+                    CellMode::Saturate => "saturate",
+                    _ => "raise errors when they overflow",
+                }
+            )
+        )));
+    }
+    if options.on_out_of_bounds() == PointerPolicy::Error {
+        return Err(ErrEvent::Error(BfError::new(
+            BfErrorKind::Other,
+            "Programs whose pointer raises errors when it leaves the memory \
+            tape cannot be compiled."
+        )));
+    }
+    let mut ir = match BfIr::lower(program) {
+        Ok(ir) => ir,
+        Err(error) => return Err(ErrEvent::Error(error)),
+    };
+    recognize_idioms(&mut ir);
+    return Ok(ir);
+}
+
+/// Gets the number of bits in an unsigned integer which wraps around the
+/// same way as a memory cell configured by `options`, if there is one.
+/// 
+/// This is the case when the lower bound is 0 and the upper bound is one
+/// less than a power of 2 such as 255.
+pub fn native_width(options: &MemoryOptions) -> Option<u32> {
+    if options.lowest() != 0 {
+        return None;
+    }
+    for bits in [8, 16, 32, 64].iter() {
+        if options.highest() == (1 as CellNumber) << bits {
+            return Some(*bits);
+        }
+    }
+    return None;
+}
+
+/// Gets the number of cells the memory tape of a compiled program starts
+/// with, which is long enough to hold the cells preloaded by `options`.
+pub fn tape_length(options: &MemoryOptions) -> usize {
+    return options.length().max(options.preloaded().end());
+}
+
+/// Writes `source` into `output`, turning any error into an [`ErrEvent`].
+pub fn write_source<W>(source: &str, output: &mut W) -> Result<(), ErrEvent>
+where
+    W: Write
+{
+    return output.write_all(source.as_bytes())
+        .and_then(|_| output.flush())
+        .map_err(|error| ErrEvent::Error(BfError::new(
+            BfErrorKind::Other,
+            format!("Could not write compiled program.\n\
+            Output Error: {}", error)
+        )));
+}
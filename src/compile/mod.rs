@@ -0,0 +1,26 @@
+//! [`brainfucklib::compile`]
+//! 
+//! This module allows you to compile Brainfuck programs into source code for
+//! other languages, so that they can be built with their own compilers. The
+//! targets available are:
+//! 1. [`c`], which emits a standalone C file,
+//! 2. [`rust`], which emits a `main.rs` or a module for another crate, and
+//! 3. [`wat`], which emits a WebAssembly module in the text format.
+//! 
+//! The code generated follows the same semantics as [`crate::vm::Memory`]
+//! configured with the same [`crate::vm::MemoryOptions`], so the interpreter
+//! can be used as a reference.
+
+pub mod c;
+pub mod compiler_traits;
+pub mod rust;
+pub mod target;
+pub mod wat;
+
+pub use self::{
+    c::CCompiler,
+    compiler_traits::BfCompiler,
+    rust::RustCompiler,
+    target::CompileTarget,
+    wat::WatCompiler,
+};
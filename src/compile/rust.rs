@@ -0,0 +1,350 @@
+//! [`brainfucklib::compile::rust`]
+//! 
+//! This module defines [`RustCompiler`], which compiles a [`BfProgram`] into
+//! Rust source code that only needs the standard library.
+//! 
+//! The code generated contains a function with the signature
+//! `pub fn run<R: Read, W: Write>(input: &mut R, output: &mut W)
+//! -> io::Result<()>`. [`RustCompiler::compile`] adds a `main` function
+//! which runs it on stdin and stdout, while
+//! [`RustCompiler::compile_module`] leaves it out so the code can be used as
+//! a module in another crate.
+//! 
+//! If the bounds of a cell match an unsigned integer (such as 0 to 255 for
+//! [`u8`]), the cells are stored as that integer and wrap around natively.
+//! Otherwise they are stored as [`i128`] and wrapped by hand.
+//! 
+//! The program reads and writes one byte at a time, the same way the
+//! interpreter does in [`crate::code::InputMode::Byte`].
+
+use std::io::Write;
+
+use crate::{
+    code::{BfProgram, ir::BfOp},
+    debug::{Event, OkEvent, Status},
+    vm::{CellNumber, EofPolicy, MemoryOptions},
+};
+use super::{
+    BfCompiler,
+    compiler_traits::{native_width, optimized_ir, tape_length, write_source},
+};
+
+/// Cell arithmetic for cells which wrap around natively.
+const NATIVE_ARITHMETIC: &str = r#"fn add(value: Cell, amount: Cell) -> Cell {
+    return value.wrapping_add(amount);
+}
+
+fn multiply(value: Cell, factor: Cell) -> Cell {
+    return value.wrapping_mul(factor);
+}
+
+fn from_byte(byte: u8) -> Cell {
+    return byte as Cell;
+}
+"#;
+
+/// Cell arithmetic for cells which have to be wrapped by hand.
+const GENERIC_ARITHMETIC: &str = r#"fn add(value: Cell, amount: Cell) -> Cell {
+    let offset = (value - LOWER).rem_euclid(RANGE);
+    return LOWER + match offset >= RANGE - amount {
+        true => offset - (RANGE - amount),
+        false => offset + amount,
+    };
+}
+
+fn multiply(value: Cell, factor: Cell) -> Cell {
+    let mut value = value.rem_euclid(RANGE);
+    let mut factor = factor;
+    let mut product: Cell = 0;
+    while factor > 0 {
+        if factor & 1 == 1 {
+            product = add(product + LOWER, value) - LOWER;
+        }
+        value = add(value + LOWER, value) - LOWER;
+        factor >>= 1;
+    }
+    return product;
+}
+
+fn from_byte(byte: u8) -> Cell {
+    let value = ZERO + byte as Cell;
+    return match LOWER <= value && value < HIGHEST {
+        true => value,
+        false => LOWER + (value - LOWER).rem_euclid(RANGE),
+    };
+}
+"#;
+
+/// The part of the code shared by every program, which relies on the
+/// constants and functions defined before it.
+const RUNTIME: &str = r#"
+struct Tape {
+    cells: Vec<Cell>,
+    ptr: usize,
+}
+
+impl Tape {
+    fn new() -> Self {
+        let mut cells = vec![ZERO; LENGTH];
+        let end = PRELOAD_OFFSET + PRELOAD.len();
+        cells[PRELOAD_OFFSET..end].copy_from_slice(&PRELOAD);
+        return Self {cells, ptr: 0};
+    }
+
+    fn get(&self) -> Cell {
+        return self.cells[self.ptr];
+    }
+
+    fn set(&mut self, value: Cell) {
+        self.cells[self.ptr] = value;
+    }
+
+    fn target(&mut self, offset: isize) -> usize {
+        let length = self.cells.len();
+        if offset >= 0 {
+            let target = self.ptr + offset as usize;
+            if target < length {
+                return target;
+            } else if VARIABLE_LENGTH {
+                self.cells.resize(target + 1, ZERO);
+                return target;
+            }
+            return target % length;
+        }
+        let back = offset.unsigned_abs();
+        if VARIABLE_LENGTH {
+            if back > self.ptr {
+                // Grow by at least the length of the tape, so that moving
+                // left one cell at a time does not copy it every time.
+                let extra = (back - self.ptr).max(length);
+                self.cells.splice(0..0, std::iter::repeat(ZERO).take(extra));
+                self.ptr += extra;
+            }
+            return self.ptr - back;
+        }
+        let back = back % length;
+        return (self.ptr + length - back) % length;
+    }
+
+    fn shift(&mut self, offset: isize) {
+        self.ptr = self.target(offset);
+    }
+
+    fn add(&mut self, amount: Cell) {
+        self.set(add(self.get(), amount));
+    }
+
+    fn mul_add(&mut self, offset: isize, factor: Cell) {
+        let value = self.get();
+        if value == ZERO {
+            return;
+        }
+        let target = self.target(offset);
+        let amount = multiply(value.wrapping_sub(ZERO), factor);
+        self.cells[target] = add(self.cells[target], amount);
+    }
+
+    fn scan(&mut self, step: isize) {
+        while self.get() != ZERO {
+            self.shift(step);
+        }
+    }
+
+    fn output<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        let code = self.get().wrapping_sub(ZERO) as u32;
+        let character = std::char::from_u32(code)
+            .unwrap_or('\u{FFFD}');
+        return output.write_all(&[character as u32 as u8]);
+    }
+
+    fn input<R: Read, W: Write>(
+        &mut self,
+        input: &mut R,
+        output: &mut W,
+    ) -> io::Result<()> {
+        output.flush()?;
+        let mut byte = [0u8];
+        loop {
+            return match input.read(&mut byte) {
+                Ok(0) => {
+                    let value = on_eof(self.get());
+                    self.set(value);
+                    Ok(())
+                },
+                Ok(_) => {
+                    self.set(from_byte(byte[0]));
+                    Ok(())
+                },
+                Err(ref error)
+                    if error.kind() == io::ErrorKind::Interrupted =>
+                {
+                    continue;
+                },
+                Err(error) => Err(error),
+            };
+        }
+    }
+}
+"#;
+
+/// The `main` function added by [`RustCompiler::compile`].
+const MAIN: &str = r#"
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    if let Err(error) = run(&mut stdin.lock(), &mut stdout.lock()) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+"#;
+
+/// Compiles a [`BfProgram`] into Rust.
+pub struct RustCompiler;
+
+impl RustCompiler {
+    /// Compiles `program` into Rust without a `main` function, so that it
+    /// can be used as a module.
+    pub fn compile_module<W>(
+        program: &BfProgram,
+        options: &MemoryOptions,
+        output: &mut W
+    ) -> Event
+    where
+        W: Write
+    {
+        let source = Self::source(program, options)?;
+        write_source(&source, output)?;
+        return Ok(OkEvent::Status(Status::new("Compile to Rust: OK")));
+    }
+
+    /// Generates everything but the `main` function.
+    fn source(
+        program: &BfProgram,
+        options: &MemoryOptions
+    ) -> Result<String, crate::debug::ErrEvent> {
+        let ir = optimized_ir(program, options)?;
+        let lowest = options.lowest();
+        let highest = options.highest();
+        let range = highest - lowest;
+        let mut source = String::new();
+        source.push_str("// Compiled from a Brainfuck program.\n");
+        source.push_str(
+            "#![allow(dead_code, unused_mut, unused_variables, clippy::all)]\n"
+        );
+        source.push('\n');
+        source.push_str("use std::io::{self, Read, Write};\n\n");
+        match native_width(options) {
+            Some(bits) => {
+                source.push_str(&format!("type Cell = u{};\n", bits));
+            },
+            None => {
+                source.push_str("type Cell = i128;\n");
+                source.push_str(&format!(
+                    "const HIGHEST: Cell = {};\n",
+                    highest
+                ));
+                source.push_str(&format!("const RANGE: Cell = {};\n", range));
+            },
+        }
+        source.push_str(&format!("const LOWER: Cell = {};\n", lowest));
+        source.push_str(&format!(
+            "const ZERO: Cell = {};\n",
+            options.zero()
+        ));
+        source.push_str(&format!(
+            "const LENGTH: usize = {};\n",
+            tape_length(options)
+        ));
+        source.push_str(&format!(
+            "const VARIABLE_LENGTH: bool = {};\n",
+            options.is_variable_length()
+        ));
+        let preload = options.preloaded();
+        let values: Vec<String> = preload
+            .values(options.zero())
+            .map(|value| value.to_string())
+            .collect();
+        source.push_str(&format!(
+            "const PRELOAD_OFFSET: usize = {};\n",
+            preload.offset()
+        ));
+        source.push_str(&format!(
+            "const PRELOAD: [Cell; {}] = [\n",
+            values.len()
+        ));
+        for line in values.chunks(8) {
+            source.push_str(&format!("    {},\n", line.join(", ")));
+        }
+        source.push_str("];\n\n");
+        source.push_str(match native_width(options) {
+            Some(_) => NATIVE_ARITHMETIC,
+            None => GENERIC_ARITHMETIC,
+        });
+        source.push_str(&format!(
+            "\nfn on_eof(value: Cell) -> Cell {{\n    return {};\n}}\n",
+            match options.on_eof() {
+                EofPolicy::Unchanged => "value".to_string(),
+                EofPolicy::Zero => "ZERO".to_string(),
+                EofPolicy::Max => format!("{}", highest - 1),
+            }
+        ));
+        source.push_str(RUNTIME);
+        source.push_str(
+            "\npub fn run<R: Read, W: Write>(\n    \
+            input: &mut R,\n    \
+            output: &mut W,\n\
+            ) -> io::Result<()> {\n    \
+            let mut tape = Tape::new();\n"
+        );
+        let amount = |amount: CellNumber| amount.rem_euclid(range);
+        let mut depth = 1;
+        for instruction in ir.as_ref().iter() {
+            if let BfOp::JumpIfNotZero(_) = instruction.op() {
+                depth -= 1;
+            }
+            let line = match instruction.op() {
+                BfOp::Add(n) => format!("tape.add({});", amount(n)),
+                BfOp::Move(n) => format!("tape.shift({});", n),
+                BfOp::Output => "tape.output(output)?;".to_string(),
+                BfOp::Input => "tape.input(input, output)?;".to_string(),
+                BfOp::JumpIfZero(_) => {
+                    "while tape.get() != ZERO {".to_string()
+                },
+                BfOp::JumpIfNotZero(_) => "}".to_string(),
+                BfOp::SetZero => "tape.set(ZERO);".to_string(),
+                BfOp::MulAdd(offset, factor) => format!(
+                    "tape.mul_add({}, {});",
+                    offset,
+                    amount(factor)
+                ),
+                BfOp::Scan(step) => format!("tape.scan({});", step),
+            };
+            source.push_str(&"    ".repeat(depth));
+            source.push_str(&line);
+            source.push('\n');
+            if let BfOp::JumpIfZero(_) = instruction.op() {
+                depth += 1;
+            }
+        }
+        source.push_str("    return output.flush();\n}\n");
+        return Ok(source);
+    }
+}
+
+impl BfCompiler for RustCompiler {
+    /// Compiles `program` into a `main.rs` which runs on stdin and stdout.
+    fn compile<W>(
+        program: &BfProgram,
+        options: &MemoryOptions,
+        output: &mut W
+    ) -> Event
+    where
+        W: Write
+    {
+        let mut source = Self::source(program, options)?;
+        source.push_str(MAIN);
+        write_source(&source, output)?;
+        return Ok(OkEvent::Status(Status::new("Compile to Rust: OK")));
+    }
+}
@@ -0,0 +1,83 @@
+//! [`brainfucklib::compile::target`]
+//! 
+//! This module defines [`CompileTarget`], which lets you choose a compiler
+//! at runtime.
+
+use std::{fmt, io::Write, str::FromStr};
+
+use crate::{
+    code::BfProgram,
+    debug::{Event, BfError, BfErrorKind},
+    vm::MemoryOptions,
+};
+use super::{BfCompiler, CCompiler, RustCompiler, WatCompiler};
+
+/// The language a [`BfProgram`] can be compiled into.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CompileTarget {
+    /// A standalone C file, compiled by [`CCompiler`].
+    C,
+    /// A `main.rs` file, compiled by [`RustCompiler`].
+    Rust,
+    /// A Rust module without a `main` function, compiled by
+    /// [`RustCompiler::compile_module`].
+    RustModule,
+    /// A WebAssembly text file, compiled by [`WatCompiler`].
+    Wat,
+}
+
+impl CompileTarget {
+    /// Compiles `program` with the compiler for this target.
+    pub fn compile<W>(
+        &self,
+        program: &BfProgram,
+        options: &MemoryOptions,
+        output: &mut W
+    ) -> Event
+    where
+        W: Write
+    {
+        return match self {
+            CompileTarget::C => CCompiler::compile(program, options, output),
+            CompileTarget::Rust => {
+                RustCompiler::compile(program, options, output)
+            },
+            CompileTarget::RustModule => {
+                RustCompiler::compile_module(program, options, output)
+            },
+            CompileTarget::Wat => {
+                WatCompiler::compile(program, options, output)
+            },
+        };
+    }
+}
+
+impl FromStr for CompileTarget {
+    type Err = BfError;
+
+    /// Parses "c", "rust", "rust-module" or "wat" into a
+    /// [`CompileTarget`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Ok(match s {
+            "c" => CompileTarget::C,
+            "rust" => CompileTarget::Rust,
+            "rust-module" => CompileTarget::RustModule,
+            "wat" => CompileTarget::Wat,
+            _ => return Err(BfError::new(
+                BfErrorKind::Other,
+                format!("{} is not a valid compile target.", s)
+            )),
+        });
+    }
+}
+
+impl fmt::Display for CompileTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", match self {
+            CompileTarget::C => "c",
+            CompileTarget::Rust => "rust",
+            CompileTarget::RustModule => "rust-module",
+            CompileTarget::Wat => "wat",
+        });
+    }
+}
@@ -0,0 +1,595 @@
+//! [`brainfucklib::compile::wat`]
+//! 
+//! This module defines [`WatCompiler`], which compiles a [`BfProgram`] into
+//! a WebAssembly module in the text format (WAT).
+//! 
+//! The module imports two functions from `env`:
+//! 1. `getchar: () -> i32`, which returns the next byte of input or a
+//!    negative number if there is no more input, and
+//! 2. `putchar: (i32) -> ()`, which writes a byte of output.
+//! 
+//! It exports its linear memory as `memory`, which holds the tape, and a
+//! function `run: () -> ()` which runs the program. Like the input, the
+//! output is written one byte at a time, the same way the interpreter
+//! writes a cell to stdout in [`crate::code::InputMode::Byte`].
+//! 
+//! Only instructions from the first version of WebAssembly are used, so the
+//! module runs on engines without the bulk memory proposal: growing the tape
+//! to the left copies it one byte at a time instead of using `memory.copy`.
+//! 
+//! If the bounds of a cell match an unsigned integer (such as 0 to 255),
+//! the cells are stored with the same width and wrap around natively.
+//! Otherwise they are stored as 64-bit integers and wrapped by hand, in which
+//! case the range of a cell cannot be larger than 2^31.
+
+use std::io::Write;
+
+use crate::{
+    code::{BfIr, BfProgram, ir::BfOp},
+    debug::{Event, OkEvent, Status, ErrEvent, BfError, BfErrorKind},
+    vm::{CellNumber, EofPolicy, MemoryOptions},
+};
+use super::{
+    BfCompiler,
+    compiler_traits::{native_width, optimized_ir, tape_length, write_source},
+};
+
+/// Size of a page of WebAssembly memory in bytes.
+const PAGE_SIZE: u64 = 65536;
+
+/// Functions shared by every program, which work on cells as `i64`s.
+const RUNTIME: &str = r#"
+  (func $get (result i64)
+    global.get $ptr
+    call $load)
+
+  (func $set (param $value i64)
+    global.get $ptr
+    local.get $value
+    call $store)
+
+  (func $fill (param $from i32) (param $to i32)
+    block $done
+      loop $next
+        local.get $from
+        local.get $to
+        i32.ge_u
+        br_if $done
+        local.get $from
+        global.get $zero
+        call $store
+        local.get $from
+        i32.const 1
+        i32.add
+        local.set $from
+        br $next
+      end
+    end)
+
+  (func $grow (param $length i32)
+    (local $pages i32)
+    local.get $length
+    global.get $len
+    i32.le_u
+    if
+      return
+    end
+    local.get $length
+    i64.extend_i32_u
+    global.get $cell_size
+    i64.mul
+    i64.const 65535
+    i64.add
+    i64.const 65536
+    i64.div_u
+    i32.wrap_i64
+    memory.size
+    i32.sub
+    local.tee $pages
+    i32.const 0
+    i32.gt_s
+    if
+      local.get $pages
+      memory.grow
+      i32.const -1
+      i32.eq
+      if
+        unreachable
+      end
+    end
+    global.get $len
+    local.get $length
+    call $fill
+    local.get $length
+    global.set $len)
+
+  (func $copy_right (param $by i32) (param $length i32)
+    block $done
+      loop $next
+        local.get $length
+        i32.eqz
+        br_if $done
+        local.get $length
+        i32.const 1
+        i32.sub
+        local.tee $length
+        local.get $by
+        i32.add
+        local.get $length
+        i32.load8_u
+        i32.store8
+        br $next
+      end
+    end)
+
+  (func $grow_left (param $extra i32)
+    (local $old i32)
+    local.get $extra
+    global.get $len
+    i32.lt_u
+    if
+      global.get $len
+      local.set $extra
+    end
+    global.get $len
+    local.set $old
+    global.get $len
+    local.get $extra
+    i32.add
+    call $grow
+    local.get $extra
+    i64.extend_i32_u
+    global.get $cell_size
+    i64.mul
+    i32.wrap_i64
+    local.get $old
+    i64.extend_i32_u
+    global.get $cell_size
+    i64.mul
+    i32.wrap_i64
+    call $copy_right
+    i32.const 0
+    local.get $extra
+    call $fill
+    global.get $ptr
+    local.get $extra
+    i32.add
+    global.set $ptr)
+
+  (func $target (param $offset i32) (result i32)
+    (local $target i32)
+    (local $back i32)
+    local.get $offset
+    i32.const 0
+    i32.ge_s
+    if (result i32)
+      global.get $ptr
+      local.get $offset
+      i32.add
+      local.tee $target
+      global.get $len
+      i32.lt_u
+      if (result i32)
+        local.get $target
+      else
+        global.get $variable_length
+        if (result i32)
+          local.get $target
+          i32.const 1
+          i32.add
+          call $grow
+          local.get $target
+        else
+          local.get $target
+          global.get $len
+          i32.rem_u
+        end
+      end
+    else
+      i32.const 0
+      local.get $offset
+      i32.sub
+      local.set $back
+      global.get $variable_length
+      if (result i32)
+        local.get $back
+        global.get $ptr
+        i32.gt_u
+        if
+          local.get $back
+          global.get $ptr
+          i32.sub
+          call $grow_left
+        end
+        global.get $ptr
+        local.get $back
+        i32.sub
+      else
+        global.get $ptr
+        global.get $len
+        i32.add
+        local.get $back
+        global.get $len
+        i32.rem_u
+        i32.sub
+        global.get $len
+        i32.rem_u
+      end
+    end)
+
+  (func $move (param $offset i32)
+    local.get $offset
+    call $target
+    global.set $ptr)
+
+  (func $mul_add (param $offset i32) (param $factor i64)
+    (local $value i64)
+    (local $target i32)
+    call $get
+    local.tee $value
+    global.get $zero
+    i64.eq
+    if
+      return
+    end
+    local.get $offset
+    call $target
+    local.tee $target
+    local.get $target
+    call $load
+    local.get $value
+    global.get $zero
+    i64.sub
+    local.get $factor
+    call $multiply
+    call $add
+    call $store)
+
+  (func $scan (param $step i32)
+    block $done
+      loop $next
+        call $get
+        global.get $zero
+        i64.eq
+        br_if $done
+        local.get $step
+        call $move
+        br $next
+      end
+    end)
+
+  (func $output
+    (local $c i32)
+    call $get
+    global.get $zero
+    i64.sub
+    i32.wrap_i64
+    local.tee $c
+    i32.const 0x10FFFF
+    i32.gt_u
+    local.get $c
+    i32.const 0xD800
+    i32.ge_u
+    local.get $c
+    i32.const 0xDFFF
+    i32.le_u
+    i32.and
+    i32.or
+    if
+      i32.const 0xFFFD
+      local.set $c
+    end
+    local.get $c
+    i32.const 0xFF
+    i32.and
+    call $putchar)
+
+  (func $input
+    (local $c i32)
+    call $getchar
+    local.tee $c
+    i32.const 0
+    i32.lt_s
+    if
+      call $get
+      call $on_eof
+      call $set
+      return
+    end
+    local.get $c
+    i64.extend_i32_u
+    call $from_byte
+    call $set)
+"#;
+
+/// Cell arithmetic for cells which wrap around natively when stored.
+const NATIVE_ARITHMETIC: &str = r#"
+  (func $add (param $value i64) (param $amount i64) (result i64)
+    local.get $value
+    local.get $amount
+    i64.add)
+
+  (func $multiply (param $value i64) (param $factor i64) (result i64)
+    local.get $value
+    local.get $factor
+    i64.mul)
+
+  (func $from_byte (param $byte i64) (result i64)
+    local.get $byte)
+"#;
+
+/// Cell arithmetic for cells which have to be wrapped by hand.
+const GENERIC_ARITHMETIC: &str = r#"
+  (func $modulo (param $value i64) (result i64)
+    local.get $value
+    global.get $range
+    i64.rem_s
+    global.get $range
+    i64.add
+    global.get $range
+    i64.rem_s)
+
+  (func $add (param $value i64) (param $amount i64) (result i64)
+    global.get $lower
+    local.get $value
+    global.get $lower
+    i64.sub
+    call $modulo
+    local.get $amount
+    i64.add
+    global.get $range
+    i64.rem_u
+    i64.add)
+
+  (func $multiply (param $value i64) (param $factor i64) (result i64)
+    local.get $value
+    call $modulo
+    local.get $factor
+    i64.mul
+    global.get $range
+    i64.rem_u)
+
+  (func $from_byte (param $byte i64) (result i64)
+    (local $value i64)
+    local.get $byte
+    global.get $zero
+    i64.add
+    local.tee $value
+    global.get $lower
+    i64.ge_s
+    local.get $value
+    global.get $highest
+    i64.lt_s
+    i32.and
+    if (result i64)
+      local.get $value
+    else
+      global.get $lower
+      local.get $value
+      global.get $lower
+      i64.sub
+      call $modulo
+      i64.add
+    end)
+"#;
+
+/// Compiles a [`BfProgram`] into WebAssembly text.
+pub struct WatCompiler;
+
+impl WatCompiler {
+    /// Writes the imports, memory, globals and the functions which depend
+    /// on how cells are stored.
+    fn header(options: &MemoryOptions) -> Result<String, ErrEvent> {
+        let lowest = options.lowest();
+        let highest = options.highest();
+        let range = highest - lowest;
+        let width = native_width(options);
+        if width.is_none() && (
+            lowest < i64::MIN as CellNumber
+            || highest > i64::MAX as CellNumber
+            || range > 1 << 31
+        ) {
+            return Err(ErrEvent::Error(BfError::new(
+                BfErrorKind::Other,
+                format!(
+                    "Cells between {} and {} cannot be compiled into \
+                    WebAssembly.",
+                    lowest,
+                    highest - 1
+                )
+            )));
+        }
+        let (cell_size, load, store) = match width {
+            Some(8) => (1, "i64.load8_u", "i64.store8"),
+            Some(16) => (2, "i64.load16_u", "i64.store16"),
+            Some(32) => (4, "i64.load32_u", "i64.store32"),
+            _ => (8, "i64.load", "i64.store"),
+        };
+        let bytes = tape_length(options) as u64 * cell_size;
+        if bytes > u32::MAX as u64 {
+            return Err(ErrEvent::Error(BfError::new(
+                BfErrorKind::Other,
+                format!(
+                    "A tape of {} cells does not fit in WebAssembly memory.",
+                    tape_length(options)
+                )
+            )));
+        }
+        let pages = bytes.div_ceil(PAGE_SIZE).max(1);
+        let mut header = String::new();
+        header.push_str(";; Compiled from a Brainfuck program.\n(module\n");
+        header.push_str(
+            "  (import \"env\" \"getchar\" (func $getchar (result i32)))\n"
+        );
+        header.push_str(
+            "  (import \"env\" \"putchar\" (func $putchar (param i32)))\n"
+        );
+        header.push_str(&format!(
+            "  (memory (export \"memory\") {})\n",
+            pages
+        ));
+        header.push_str("  (global $ptr (mut i32) (i32.const 0))\n");
+        header.push_str(&format!(
+            "  (global $len (mut i32) (i32.const {}))\n",
+            tape_length(options)
+        ));
+        header.push_str(&format!(
+            "  (global $variable_length i32 (i32.const {}))\n",
+            options.is_variable_length() as u8
+        ));
+        header.push_str(&format!(
+            "  (global $cell_size i64 (i64.const {}))\n",
+            cell_size
+        ));
+        header.push_str(&format!(
+            "  (global $lower i64 (i64.const {}))\n",
+            lowest as i64
+        ));
+        header.push_str(&format!(
+            "  (global $zero i64 (i64.const {}))\n",
+            options.zero() as i64
+        ));
+        if width.is_none() {
+            header.push_str(&format!(
+                "  (global $highest i64 (i64.const {}))\n",
+                highest as i64
+            ));
+            header.push_str(&format!(
+                "  (global $range i64 (i64.const {}))\n",
+                range as i64
+            ));
+        }
+        header.push_str(&format!(
+            "\n  (func $load (param $index i32) (result i64)\n    \
+            local.get $index\n    \
+            i32.const {size}\n    \
+            i32.mul\n    \
+            {load})\n\n  \
+            (func $store (param $index i32) (param $value i64)\n    \
+            local.get $index\n    \
+            i32.const {size}\n    \
+            i32.mul\n    \
+            local.get $value\n    \
+            {store})\n",
+            size = cell_size,
+            load = load,
+            store = store
+        ));
+        let preload = options.preloaded();
+        if !preload.is_empty() {
+            header.push_str("\n  (func $init_preload");
+            for (index, value) in preload.values(options.zero()).enumerate() {
+                header.push_str(&format!(
+                    "\n    i32.const {}\n    i64.const {}\n    call $store",
+                    preload.offset() + index,
+                    value as i64
+                ));
+            }
+            header.push_str(")\n");
+        }
+        header.push_str(match width {
+            Some(_) => NATIVE_ARITHMETIC,
+            None => GENERIC_ARITHMETIC,
+        });
+        let on_eof = match options.on_eof() {
+            EofPolicy::Unchanged => "local.get $value".to_string(),
+            EofPolicy::Zero => "global.get $zero".to_string(),
+            EofPolicy::Max => format!("i64.const {}", (highest - 1) as i64),
+        };
+        header.push_str(&format!(
+            "\n  (func $on_eof (param $value i64) (result i64)\n    {})\n",
+            on_eof
+        ));
+        return Ok(header);
+    }
+
+    /// Writes the body of the `run` function.
+    fn body(ir: &BfIr, options: &MemoryOptions) -> String {
+        let range = options.highest() - options.lowest();
+        // Cells which wrap around natively only need the amount modulo 2^64,
+        // which is what casting to an i64 does.
+        let amount = |amount: CellNumber| amount.rem_euclid(range) as i64;
+        let mut body = String::new();
+        let mut depth = 2;
+        for instruction in ir.as_ref().iter() {
+            if let BfOp::JumpIfNotZero(_) = instruction.op() {
+                depth -= 2;
+            }
+            let lines: Vec<String> = match instruction.op() {
+                BfOp::Add(n) => vec![
+                    "call $get".to_string(),
+                    format!("i64.const {}", amount(n)),
+                    "call $add".to_string(),
+                    "call $set".to_string(),
+                ],
+                BfOp::Move(n) => vec![
+                    format!("i32.const {}", n),
+                    "call $move".to_string(),
+                ],
+                BfOp::Output => vec!["call $output".to_string()],
+                BfOp::Input => vec!["call $input".to_string()],
+                BfOp::JumpIfZero(_) => vec![
+                    "block".to_string(),
+                    "  loop".to_string(),
+                    "    call $get".to_string(),
+                    "    global.get $zero".to_string(),
+                    "    i64.eq".to_string(),
+                    "    br_if 1".to_string(),
+                ],
+                BfOp::JumpIfNotZero(_) => vec![
+                    "    br 0".to_string(),
+                    "  end".to_string(),
+                    "end".to_string(),
+                ],
+                BfOp::SetZero => vec![
+                    "global.get $zero".to_string(),
+                    "call $set".to_string(),
+                ],
+                BfOp::MulAdd(offset, factor) => vec![
+                    format!("i32.const {}", offset),
+                    format!("i64.const {}", amount(factor)),
+                    "call $mul_add".to_string(),
+                ],
+                BfOp::Scan(step) => vec![
+                    format!("i32.const {}", step),
+                    "call $scan".to_string(),
+                ],
+            };
+            for line in lines {
+                body.push_str(&"  ".repeat(depth));
+                body.push_str(&line);
+                body.push('\n');
+            }
+            if let BfOp::JumpIfZero(_) = instruction.op() {
+                depth += 2;
+            }
+        }
+        return body;
+    }
+}
+
+impl BfCompiler for WatCompiler {
+    fn compile<W>(
+        program: &BfProgram,
+        options: &MemoryOptions,
+        output: &mut W
+    ) -> Event
+    where
+        W: Write
+    {
+        let ir = optimized_ir(program, options)?;
+        let mut source = Self::header(options)?;
+        source.push_str(RUNTIME);
+        source.push_str("\n  (func (export \"run\")\n");
+        source.push_str("    i32.const 0\n");
+        source.push_str("    global.get $len\n");
+        source.push_str("    call $fill\n");
+        if !options.preloaded().is_empty() {
+            source.push_str("    call $init_preload\n");
+        }
+        source.push_str(&Self::body(&ir, options));
+        source.push_str("  )\n)\n");
+        write_source(&source, output)?;
+        return Ok(OkEvent::Status(Status::new("Compile to WAT: OK")));
+    }
+}
@@ -29,6 +29,13 @@ impl ErrorLog {
     }
 }
 
+impl Default for ErrorLog {
+    /// Creates an empty [`ErrorLog`].
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
 impl AsMut<Vec<(usize, ErrEvent)>> for ErrorLog {
     /// Get the inner [`Vec`]tor in the [`ErrorLog`].
     fn as_mut(&mut self) -> &mut Vec<(usize, ErrEvent)> {
@@ -91,7 +98,7 @@ pub struct BfDebugger {
 impl BfDebugger {
     /// Creates a new instance of [`BfDebugger`].
     pub fn new() -> Self {
-        let log: Box<Log> = Box::new(Vec::new());
+        let log: Box<Log> = Box::default();
         let max_length = 5;
         let ok_before = 0;
         let err_before = 0;
@@ -129,9 +136,16 @@ impl BfDebugger {
     }
 }
 
+impl Default for BfDebugger {
+    /// Creates a [`BfDebugger`] with the default maximum length.
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
 impl AsMut<Log> for BfDebugger {
     fn as_mut(&mut self) -> &mut Log {
-        return &mut *self.log;
+        return &mut self.log;
     }
 }
 
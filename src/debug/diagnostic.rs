@@ -0,0 +1,350 @@
+//! [`brainfucklib::debug::diagnostic`]
+//! 
+//! This module defines [`Diagnostic`], an error or warning about a part of a
+//! program which is found before the program is run, and [`Diagnostics`],
+//! which collects every [`Diagnostic`] found in a program so that they can
+//! all be reported at once instead of stopping at the first one.
+//! 
+//! Diagnostics are collected with [`crate::parser::BfParser::check_string`]
+//! and [`crate::code::BfProgram::check`].
+
+use std::fmt;
+
+use crate::code::{SourceMap, Span};
+use super::BfError;
+
+/// How bad the problem a [`Diagnostic`] reports is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    /// The program cannot be run.
+    Error,
+    /// The program can be run, but probably does not do what it should.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        });
+    }
+}
+
+/// An error or warning about a part of a program, with the [`Span`] it
+/// points at and notes about other parts of the program.
+/// 
+/// Every [`Diagnostic`] has a name, such as `UnmatchedLeftBracket` or
+/// `EmptyLoop`. Errors are named after their [`crate::debug::BfErrorKind`].
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    severity: Severity,
+    name: String,
+    message: String,
+    span: Option<Span>,
+    notes: Vec<(Span, String)>,
+    footnotes: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Creates a new [`Diagnostic`] called `name`.
+    pub fn new<N, M>(severity: Severity, name: N, message: M) -> Self
+    where
+        N: AsRef<str>,
+        M: AsRef<str>
+    {
+        return Self {
+            severity,
+            name: name.as_ref().to_string(),
+            message: message.as_ref().to_string(),
+            span: None,
+            notes: Vec::new(),
+            footnotes: Vec::new(),
+        };
+    }
+
+    /// Creates a new warning called `name`.
+    pub fn warning<N, M>(name: N, message: M) -> Self
+    where
+        N: AsRef<str>,
+        M: AsRef<str>
+    {
+        return Self::new(Severity::Warning, name, message);
+    }
+
+    /// Records the [`Span`] the diagnostic points at.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        return self;
+    }
+
+    /// Records a note about another part of the program.
+    pub fn with_note(mut self, span: Span, note: impl AsRef<str>) -> Self {
+        self.notes.push((span, note.as_ref().to_string()));
+        return self;
+    }
+
+    /// Records a note which is shown underneath the source, such as the
+    /// cell an error happened at.
+    pub fn with_footnote(mut self, footnote: impl AsRef<str>) -> Self {
+        self.footnotes.push(footnote.as_ref().to_string());
+        return self;
+    }
+
+    /// Moves every [`Span`] the diagnostic points at with `map`, such as
+    /// into the file it came from with
+    /// [`crate::parser::Expansion::map_span`].
+    pub fn map_spans<F>(&mut self, map: F)
+    where
+        F: Fn(Span) -> Span
+    {
+        self.span = self.span.map(&map);
+        for note in self.notes.iter_mut() {
+            note.0 = map(note.0);
+        }
+    }
+
+    /// Gets the [`Severity`] of the diagnostic.
+    pub fn severity(&self) -> Severity {
+        return self.severity;
+    }
+
+    /// Checks whether the diagnostic is an error.
+    pub fn is_error(&self) -> bool {
+        return self.severity == Severity::Error;
+    }
+
+    /// Gets the name of the diagnostic.
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Gets the message of the diagnostic.
+    pub fn message(&self) -> &str {
+        return &self.message;
+    }
+
+    /// Gets the [`Span`] the diagnostic points at, if there is one.
+    pub fn span(&self) -> Option<Span> {
+        return self.span;
+    }
+
+    /// Gets the notes about other parts of the program, with the [`Span`]
+    /// each of them points at.
+    pub fn notes(&self) -> &[(Span, String)] {
+        return &self.notes;
+    }
+
+    /// Gets the notes which are shown underneath the source.
+    pub fn footnotes(&self) -> &[String] {
+        return &self.footnotes;
+    }
+
+    /// Shows the diagnostic along with the lines of `source` it points at,
+    /// like `rustc` does. See [`BfError::render`] for an example.
+    pub fn render(&self, source: &SourceMap) -> String {
+        let mut text = format!(
+            "{}[{}]: {}\n",
+            self.severity,
+            self.name,
+            self.message
+        );
+        let mut labels: Vec<(Span, &str)> = Vec::new();
+        if let Some(span) = self.span {
+            labels.push((span, ""));
+        }
+        for (span, note) in self.notes.iter() {
+            labels.push((*span, note));
+        }
+        let first = match labels.first() {
+            Some(first) => first.0,
+            None => {
+                for footnote in self.footnotes.iter() {
+                    text.push_str(&format!(" = {}\n", footnote));
+                }
+                return text;
+            },
+        };
+        let gutter = labels
+            .iter()
+            .map(|label| source.line_column(label.0).0)
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+        text.push_str(&format!(
+            "{:2$}--> {1}\n",
+            "",
+            source.location(first),
+            gutter
+        ));
+        text.push_str(&source.snippet(&labels, gutter));
+        if !self.footnotes.is_empty() {
+            text.push_str(&format!("{:1$} |\n", "", gutter));
+        }
+        for footnote in self.footnotes.iter() {
+            text.push_str(&format!("{:2$} = {1}\n", "", footnote, gutter));
+        }
+        return text;
+    }
+}
+
+impl From<BfError> for Diagnostic {
+    /// Creates an error from a [`BfError`], keeping its [`Span`], its
+    /// notes and the cell it happened at.
+    fn from(error: BfError) -> Self {
+        let mut diagnostic = Self::new(
+            Severity::Error,
+            error.kind().to_string(),
+            error.description()
+        );
+        diagnostic.span = error.span();
+        diagnostic.notes = error.notes().to_vec();
+        if let Some(cell) = error.cell() {
+            diagnostic.footnotes.push(format!("cell: {}", cell));
+        }
+        return diagnostic;
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.name, self.message)?;
+        if let Some(span) = self.span {
+            write!(f, "\nPosition: {} to {}", span.start(), span.end())?;
+        }
+        for (span, note) in self.notes.iter() {
+            write!(
+                f,
+                "\nNote ({} to {}): {}",
+                span.start(),
+                span.end(),
+                note
+            )?;
+        }
+        for footnote in self.footnotes.iter() {
+            write!(f, "\n{}", footnote)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Every [`Diagnostic`] found in a program, in the order they were found.
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Creates an empty [`Diagnostics`].
+    pub fn new() -> Self {
+        return Self {diagnostics: Vec::new()};
+    }
+
+    /// Adds a [`Diagnostic`].
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Gets the number of diagnostics.
+    pub fn len(&self) -> usize {
+        return self.diagnostics.len();
+    }
+
+    /// Checks whether nothing was found.
+    pub fn is_empty(&self) -> bool {
+        return self.diagnostics.is_empty();
+    }
+
+    /// Gets the number of errors.
+    pub fn errors(&self) -> usize {
+        return self.diagnostics.iter().filter(|d| d.is_error()).count();
+    }
+
+    /// Gets the number of warnings.
+    pub fn warnings(&self) -> usize {
+        return self.len() - self.errors();
+    }
+
+    /// Checks whether any of the diagnostics is an error.
+    pub fn has_errors(&self) -> bool {
+        return self.errors() > 0;
+    }
+
+    /// Gets an iterator over the diagnostics.
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        return self.diagnostics.iter();
+    }
+
+    /// Moves every [`Span`] the diagnostics point at with `map`, like
+    /// [`Diagnostic::map_spans`].
+    pub fn map_spans<F>(&mut self, map: F)
+    where
+        F: Fn(Span) -> Span
+    {
+        for diagnostic in self.diagnostics.iter_mut() {
+            diagnostic.map_spans(&map);
+        }
+    }
+
+    /// Sorts the diagnostics by the file they point at and where in it,
+    /// keeping those without a [`Span`] at the start.
+    pub fn sort(&mut self) {
+        self.diagnostics.sort_by_key(|d| {
+            d.span().map(|span| (span.file(), span.start()))
+        });
+    }
+
+    /// Shows every diagnostic with [`Diagnostic::render`], followed by how
+    /// many errors and warnings were found.
+    pub fn render(&self, source: &SourceMap) -> String {
+        let mut text = String::new();
+        for diagnostic in self.diagnostics.iter() {
+            text.push_str(&diagnostic.render(source));
+            text.push('\n');
+        }
+        text.push_str(&self.summary());
+        return text;
+    }
+
+    /// Says how many errors and warnings were found, such as
+    /// `2 errors and 1 warning found`.
+    pub fn summary(&self) -> String {
+        let plural = |count: usize, noun: &str| match count {
+            1 => format!("1 {}", noun),
+            _ => format!("{} {}s", count, noun),
+        };
+        return format!(
+            "{} and {} found",
+            plural(self.errors(), "error"),
+            plural(self.warnings(), "warning")
+        );
+    }
+}
+
+impl Extend<Diagnostic> for Diagnostics {
+    fn extend<I>(&mut self, diagnostics: I)
+    where
+        I: IntoIterator<Item = Diagnostic>
+    {
+        self.diagnostics.extend(diagnostics);
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.diagnostics.into_iter();
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.diagnostics.iter();
+    }
+}
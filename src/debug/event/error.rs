@@ -8,6 +8,11 @@ use std::{
     fmt
 };
 
+use crate::{
+    code::{SourceMap, Span},
+    debug::Diagnostic,
+};
+
 /// The kind of error that occurred. This enum can be compared to
 /// [`std::io::Error`].
 #[derive(Clone, Copy, Debug)]
@@ -17,6 +22,7 @@ pub enum BfErrorKind {
     UnmatchedRightBracket,
     BadProgram,
     ParsingError,
+    PreprocessingError,
 
     OutOfBounds,
     PointerError,
@@ -34,6 +40,7 @@ impl fmt::Display for BfErrorKind {
             UnmatchedRightBracket => "UnmatchedRightBracket",
             BadProgram => "BadProgram",
             ParsingError => "ParsingError",
+            PreprocessingError => "PreprocessingError",
             OutOfBounds => "OutOfBounds",
             PointerError => "PointerError",
             CellOverflow => "CellOverflow",
@@ -44,10 +51,22 @@ impl fmt::Display for BfErrorKind {
 
 /// A [`BfError`] is an error which has a [`BfErrorKind`] and a description
 /// which tells you what has gone wrong.
+/// 
+/// Errors which happen while a program is running can also record the
+/// [`Span`] of the command which caused them and the position of the cell
+/// they happened at, relative to the cell the pointer started at. Errors
+/// found while parsing a program can also point at other parts of it with
+/// notes, such as the other end of a loop.
+/// 
+/// With the text of the program, [`BfError::render`] shows where the error
+/// happened like `rustc` does.
 #[derive(Clone, Debug)]
 pub struct BfError {
     kind: BfErrorKind,
     description: String,
+    span: Option<Span>,
+    cell: Option<isize>,
+    notes: Vec<(Span, String)>,
 }
 
 impl BfError {
@@ -58,13 +77,100 @@ impl BfError {
         let description = description
             .as_ref()
             .to_string();
-        return Self {kind, description};
+        return Self {
+            kind,
+            description,
+            span: None,
+            cell: None,
+            notes: Vec::new(),
+        };
+    }
+
+    /// Records the [`Span`] of the command which caused the error.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        return self;
+    }
+
+    /// Records the position of the cell the error happened at.
+    pub fn with_cell(mut self, cell: isize) -> Self {
+        self.cell = Some(cell);
+        return self;
+    }
+
+    /// Records a note about another part of the program, such as the other
+    /// end of a loop.
+    pub fn with_note(mut self, span: Span, note: impl AsRef<str>) -> Self {
+        self.notes.push((span, note.as_ref().to_string()));
+        return self;
+    }
+
+    /// Gets the [`BfErrorKind`] of the error.
+    pub fn kind(&self) -> BfErrorKind {
+        return self.kind;
+    }
+
+    /// Gets the description of the error.
+    pub fn description(&self) -> &str {
+        return &self.description;
+    }
+
+    /// Gets the [`Span`] of the command which caused the error, if it was
+    /// recorded.
+    pub fn span(&self) -> Option<Span> {
+        return self.span;
+    }
+
+    /// Gets the position of the cell the error happened at, if it was
+    /// recorded.
+    pub fn cell(&self) -> Option<isize> {
+        return self.cell;
+    }
+
+    /// Gets the notes about other parts of the program, with the [`Span`]
+    /// each of them points at.
+    pub fn notes(&self) -> &[(Span, String)] {
+        return &self.notes;
+    }
+
+    /// Shows the error along with the lines of `source` it points at, like
+    /// this:
+    /// 
+    /// ```text
+    /// error[UnmatchedLeftBracket]: Unmatched [
+    ///  --> hello.b:1:2
+    ///   |
+    /// 1 | +[>+<-
+    ///   |  ^
+    ///   |       ^ the program ends before this loop is closed
+    /// ```
+    /// 
+    /// `source` should be the program the [`Span`]s of the error were taken
+    /// from.
+    pub fn render(&self, source: &SourceMap) -> String {
+        return Diagnostic::from(self.clone()).render(source);
     }
 }
 
 impl fmt::Display for BfError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        return write!(f, "{}: {}", self.kind, self.description);
+        write!(f, "{}: {}", self.kind, self.description)?;
+        if let Some(cell) = self.cell {
+            write!(f, "\nCell: {}", cell)?;
+        }
+        if let Some(span) = self.span {
+            write!(f, "\nPosition: {} to {}", span.start(), span.end())?;
+        }
+        for (span, note) in self.notes.iter() {
+            write!(
+                f,
+                "\nNote ({} to {}): {}",
+                span.start(),
+                span.end(),
+                note
+            )?;
+        }
+        return Ok(());
     }
 }
 
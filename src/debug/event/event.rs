@@ -4,6 +4,8 @@
 //! are [`OkEvent`] and [`ErrEvent`] respectively.
 
 use std::fmt;
+
+use crate::code::Span;
 use super::{BfError, Status, Warning};
 
 /// A custom [`Result`] returned by a Brainfuck virtual machine and a parser.
@@ -41,6 +43,28 @@ pub enum ErrEvent {
     Error(BfError),
 }
 
+impl ErrEvent {
+    /// Records the [`Span`] of the command which caused the error, unless
+    /// one has already been recorded. Warnings are left as they are.
+    pub fn with_span(self, span: Span) -> Self {
+        return match self {
+            ErrEvent::Error(error) if error.span().is_none() => {
+                ErrEvent::Error(error.with_span(span))
+            },
+            other => other,
+        };
+    }
+
+    /// Records the position of the cell the error happened at. Warnings are
+    /// left as they are.
+    pub fn with_cell(self, cell: isize) -> Self {
+        return match self {
+            ErrEvent::Error(error) => ErrEvent::Error(error.with_cell(cell)),
+            other => other,
+        };
+    }
+}
+
 impl fmt::Display for ErrEvent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         return write!(f, "{}", match self {
@@ -3,19 +3,20 @@
 //! This module defines [`Status`], a struct that tells the user that an
 //! operation was ok.
 
-use std::fmt;
+use std::{borrow::Cow, fmt};
 
 /// A status for an operation involving the Brainfuck virtual machine and
 /// the grammar parser.
 #[derive(Clone, Debug)]
 pub struct Status {
-    description: String,
+    description: Cow<'static, str>,
 }
 
 impl Status {
-    /// Creates a new [`Status`].
-    pub fn new(description: impl AsRef<str>) -> Self {
-        let description = description.as_ref().to_string();
+    /// Creates a new [`Status`]. Statuses are created for every command
+    /// run, so a `&'static str` description is stored without being copied.
+    pub fn new(description: impl Into<Cow<'static, str>>) -> Self {
+        let description = description.into();
         return Self {description};
     }
 }
@@ -4,6 +4,7 @@
 //! program and virtual machine.
 
 pub mod debugger;
+pub mod diagnostic;
 pub mod event;
 
 pub use self::{
@@ -12,6 +13,7 @@ pub use self::{
         Debugger,
         ErrorLog,
     },
+    diagnostic::{Diagnostic, Diagnostics, Severity},
     event::*,
 };
 
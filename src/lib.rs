@@ -3,6 +3,12 @@
 //! This library allows you to run Brainfuck programs using the grammar parser
 //! and virtual machine provided.
 
+#![allow(
+    clippy::box_collection,
+    clippy::module_inception,
+    clippy::needless_return,
+)]
+
 extern crate clap;
 extern crate pest;
 #[macro_use]
@@ -10,6 +16,7 @@ extern crate pest_derive;
 
 pub mod app;
 pub mod code;
+pub mod compile;
 pub mod debug;
 pub mod macros;
 pub mod parser;
@@ -13,13 +13,23 @@
 //! To avoid this problem, you can use [`nparser`], which allows you to
 //! comment any part of a line after `#`, allowing the parser to ignore
 //! Brainfuck code after that token.
+//! 
+//! Both parsers can also read a program a chunk at a time from anything which
+//! implements [`std::io::Read`] with [`BfParser::parse_reader`], using
+//! [`stream`].
+//! 
+//! Before a program is parsed with [`nparser`], its includes and macros can
+//! be expanded with [`preprocess`].
 
 pub mod nparser;
 pub mod parser_traits;
+pub mod preprocess;
 pub mod sparser;
+pub mod stream;
 
 pub use self::{
     nparser::NewParser,
     sparser::NormalParser,
     parser_traits::BfParser,
+    preprocess::{Expansion, Preprocessor},
 };
\ No newline at end of file
@@ -1,10 +1,23 @@
 use pest::{Parser, iterators::{Pair, Pairs}};
+use std::io::Read;
 
 use crate::{
     code::{BfProgram, BfCommand, BfToken, Span},
-    debug::{Event, OkEvent, Status, ErrEvent, BfError, BfErrorKind}
+    debug::{
+        Event,
+        OkEvent,
+        Status,
+        ErrEvent,
+        BfError,
+        BfErrorKind,
+        Diagnostics,
+    },
+};
+use super::{
+    parser_traits::{check_read, pest_error},
+    stream::{read_commands, Grammar},
+    BfParser,
 };
-use super::BfParser;
 
 #[derive(Parser)]
 #[grammar = "./parser/brainfuck.pest"]
@@ -79,11 +92,80 @@ impl BfParser for NewParser {
         let script = script.as_ref();
         let ast = match Self::parse(Rule::FILE, script) {
             Ok(parsed) => parsed,
-            Err(_) => return Err(ErrEvent::Error(BfError::new(
-                BfErrorKind::BadProgram,
-                "Could not parse string."
-            ))),
+            Err(error) => return Err(ErrEvent::Error(pest_error(error))),
         };
         return Self::parse_ast(ast, program);
     }
+
+    /// A `!` in a comment does not split the script.
+    fn split_input(script: &str) -> (&str, Option<&str>) {
+        let mut in_comment = false;
+        for (index, character) in script.char_indices() {
+            match character {
+                '\n' | '\r' => in_comment = false,
+                '#' => in_comment = true,
+                '!' if !in_comment => {
+                    return (&script[..index], Some(&script[index + 1..]));
+                },
+                _ => (),
+            }
+        }
+        return (script, None);
+    }
+
+    /// Skips everything from a `#` up to the end of its line and stops at
+    /// the first bracket which cannot be paired up, like the grammar does.
+    fn parse_reader<R>(reader: R, program: &mut BfProgram) -> Event
+    where
+        R: Read
+    {
+        return read_commands(reader, program, Grammar::New);
+    }
+
+    /// The brackets are not paired up while the program is read, so that
+    /// every unmatched bracket is found afterwards instead of only the
+    /// first one.
+    fn check_reader<R>(reader: R, program: &mut BfProgram) -> Diagnostics
+    where
+        R: Read
+    {
+        let event = read_commands(reader, program, Grammar::Tokens);
+        return check_read(event, program);
+    }
+
+    /// The grammar stops at the first bracket it cannot pair up, so the
+    /// commands in `script` are read again without pairing the brackets
+    /// (while still skipping comments), and every unmatched bracket is
+    /// reported instead of `error`. If none are found, `error` is reported
+    /// along with any warnings.
+    fn recover(
+        script: &str,
+        error: BfError,
+        program: &mut BfProgram
+    ) -> Diagnostics {
+        let mut diagnostics = Diagnostics::new();
+        let _ = program.clear();
+        let ast = match Self::parse(Rule::TOKENS, script) {
+            Ok(parsed) => parsed,
+            Err(_) => {
+                diagnostics.push(error.into());
+                return diagnostics;
+            },
+        };
+        for pair in ast {
+            if let Rule::RESERVED = pair.as_rule() {
+                let span = Span::from(pair.as_span());
+                if let Ok(command) = BfCommand::new(pair.as_str()) {
+                    program.push(BfToken::new(command, span));
+                }
+            }
+        }
+        let _ = program.populate_map();
+        let found = program.check();
+        if !found.has_errors() {
+            diagnostics.push(error.into());
+        }
+        diagnostics.extend(found);
+        return diagnostics;
+    }
 }
\ No newline at end of file
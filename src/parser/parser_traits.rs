@@ -1,13 +1,19 @@
 //use pest::{Parser, RuleType};
+use pest::{
+    error::{Error, ErrorVariant, InputLocation},
+    RuleType,
+};
 use std::{
     fs::read_to_string,
+    io::Read,
     path::Path
 };
 
 use crate::{
-    code::BfProgram,
-    debug::{Event, ErrEvent, BfError, BfErrorKind},
+    code::{BfProgram, Span},
+    debug::{Event, ErrEvent, BfError, BfErrorKind, Diagnostics},
 };
+use super::stream::{read_commands, Grammar};
 
 pub trait BfParser//<R>
 //where
@@ -18,6 +24,85 @@ pub trait BfParser//<R>
     where
         S: AsRef<str>;
 
+    /// Splits `script` at the first `!` into the program before it and the
+    /// input after it, which is how many Brainfuck distributions put the
+    /// input of a program in the same file as the program. If there is no
+    /// `!`, the whole of `script` is the program.
+    /// 
+    /// The program starts at the start of `script`, so the [`Span`]s found
+    /// by parsing it point into `script` as well. The input can be given to
+    /// a runner, such as with [`crate::code::BfRunner::with_buffers`].
+    fn split_input(script: &str) -> (&str, Option<&str>) {
+        return match script.find('!') {
+            Some(index) => (&script[..index], Some(&script[index + 1..])),
+            None => (script, None),
+        };
+    }
+
+    /// Parses the program read from `reader` into `program` a chunk at a
+    /// time, so that its text is never held in memory all at once. This
+    /// works with files, pipes, sockets or anything else which implements
+    /// [`Read`].
+    /// 
+    /// The commands are the same as [`BfParser::parse_string`] would find,
+    /// and the same programs are rejected. By default, nothing is skipped
+    /// and brackets are not paired up, like [`crate::parser::NormalParser`].
+    fn parse_reader<R>(reader: R, program: &mut BfProgram) -> Event
+    where
+        R: Read
+    {
+        return read_commands(reader, program, Grammar::Normal);
+    }
+
+    /// Parses the program read from `reader` into `program` like
+    /// [`BfParser::parse_reader`], and checks it like
+    /// [`BfParser::check_string`], reporting every unmatched bracket and
+    /// warning the same way.
+    /// 
+    /// If the program cannot be read at all, such as when `reader` fails or
+    /// the program is not valid UTF-8, only that problem is reported.
+    fn check_reader<R>(reader: R, program: &mut BfProgram) -> Diagnostics
+    where
+        R: Read
+    {
+        return check_read(Self::parse_reader(reader, program), program);
+    }
+
+    /// Parses `script` into `program` like [`BfParser::parse_string`], but
+    /// carries on past problems so that every one of them can be reported
+    /// at once. This includes every unmatched bracket and warnings found by
+    /// [`BfProgram::check`].
+    /// 
+    /// If there are no errors, the brackets in `program` are paired up and
+    /// it is ready to be run.
+    fn check_string<S>(script: S, program: &mut BfProgram) -> Diagnostics
+    where
+        S: AsRef<str>
+    {
+        let script = script.as_ref();
+        if let Err(ErrEvent::Error(error)) = Self::parse_string(
+            script,
+            program
+        ) {
+            return Self::recover(script, error, program);
+        }
+        return check(program);
+    }
+
+    /// Called by [`BfParser::check_string`] when `script` could not be
+    /// parsed because of `error`, to look for more problems in it.
+    /// 
+    /// By default, only `error` is reported.
+    fn recover(
+        _script: &str,
+        error: BfError,
+        _program: &mut BfProgram
+    ) -> Diagnostics {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.push(error.into());
+        return diagnostics;
+    }
+
     fn parse_file<P>(file_path: P, program: &mut BfProgram) -> Event
     where
         P: AsRef<Path>
@@ -35,4 +120,65 @@ pub trait BfParser//<R>
             ))),
         }, program);
     }
-}
\ No newline at end of file
+}
+
+/// Reports the error in `event` if reading `program` failed, or checks it
+/// with [`check`] otherwise.
+pub(crate) fn check_read(
+    event: Event,
+    program: &mut BfProgram
+) -> Diagnostics {
+    if let Err(ErrEvent::Error(error)) = event {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.push(error.into());
+        return diagnostics;
+    }
+    return check(program);
+}
+
+/// Looks for problems in a `program` which has been parsed, and pairs up
+/// its brackets if there are none.
+fn check(program: &mut BfProgram) -> Diagnostics {
+    let _ = program.populate_map();
+    let diagnostics = program.check();
+    if !diagnostics.has_errors() {
+        let _ = program.calculate_map();
+    }
+    return diagnostics;
+}
+
+/// Converts an error from a `pest` grammar into a [`BfError`] which keeps
+/// the rules the grammar expected and the [`Span`] of where it failed.
+pub fn pest_error<R>(error: Error<R>) -> BfError
+where
+    R: RuleType
+{
+    let span = match error.location {
+        InputLocation::Pos(position) => Span::new(position, position + 1),
+        InputLocation::Span((start, end)) => Span::new(start, end),
+    };
+    let rules = |rules: &[R]| rules
+        .iter()
+        .map(|rule| format!("{:?}", rule))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let description = match error.variant {
+        ErrorVariant::ParsingError {positives, negatives} => {
+            match (positives.is_empty(), negatives.is_empty()) {
+                (false, true) => format!("expected {}", rules(&positives)),
+                (true, false) => format!("unexpected {}", rules(&negatives)),
+                (false, false) => format!(
+                    "unexpected {}; expected {}",
+                    rules(&negatives),
+                    rules(&positives)
+                ),
+                (true, true) => "unknown parsing error".to_string(),
+            }
+        },
+        ErrorVariant::CustomError {message} => message,
+    };
+    return BfError::new(
+        BfErrorKind::BadProgram,
+        format!("Could not parse string: {}", description)
+    ).with_span(span);
+}
@@ -0,0 +1,655 @@
+//! [`brainfucklib::parser::preprocess`]
+//! 
+//! This module provides [`Preprocessor`], an optional stage which expands
+//! includes and macros in a program before it is parsed with
+//! [`crate::parser::NewParser`]. It understands:
+//! 1. `#include "file.b"` on a line of its own, which is replaced by the
+//!    preprocessed text of `file.b`, found next to the file including it,
+//! 2. `#define name(a, b) body` on a line of its own, which defines a macro
+//!    called `name` with the parameters `a` and `b`, and
+//! 3. `@name(1, +)` anywhere else, which is replaced by the body of `name`.
+//! 
+//! In the body of a macro, `$a` is replaced by the argument given for `a`.
+//! After that, a character or a group in brackets followed by `*` and a
+//! number, such as `>*3` or `(+>)*2`, is repeated that many times. A macro
+//! without parameters is defined and used without brackets, so with
+//! 
+//! ```text
+//! #define move(n) >*$n
+//! #define clear [-]
+//! ```
+//! 
+//! `@move(3)@clear` becomes `>>>[-]`. Everything after any other `#` on a
+//! line is a comment, just like in [`crate::parser::NewParser`].
+//! 
+//! A [`Preprocessor`] gives back an [`Expansion`], which remembers where
+//! each part of its text came from. The [`Span`]s of a program parsed from
+//! it are moved back into the files they came from with
+//! [`Expansion::map_program`], so that errors point at the right line of the
+//! right file. Commands which come from a macro point at where the macro was
+//! used.
+
+use std::{
+    collections::HashMap,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    code::{BfProgram, BfToken, SourceMap, Span},
+    debug::{BfError, BfErrorKind, BfResult, Diagnostics},
+};
+
+/// How deep files can be included in each other.
+pub const MAX_DEPTH: usize = 64;
+
+/// How many bytes the text of an [`Expansion`] can be, which is also the
+/// most the expansion of a single use of a macro can be.
+pub const MAX_LENGTH: usize = 1 << 26;
+
+/// A macro defined with `#define`.
+#[derive(Clone, Debug)]
+struct Macro {
+    params: Vec<String>,
+    body: String,
+    span: Span,
+}
+
+/// The start of a part of the text of an [`Expansion`] and where it came
+/// from. If `exact` is `true`, the part was copied from `origin` byte for
+/// byte. Otherwise, it is the expansion of the macro used at `origin`.
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    start: usize,
+    origin: Span,
+    exact: bool,
+}
+
+/// The text of a program after it has been preprocessed, along with where
+/// each part of it came from.
+#[derive(Clone, Debug, Default)]
+pub struct Expansion {
+    text: String,
+    segments: Vec<Segment>,
+}
+
+impl Expansion {
+    /// Gets the preprocessed text, which can be parsed with
+    /// [`crate::parser::NewParser`].
+    pub fn text(&self) -> &str {
+        return &self.text;
+    }
+
+    /// Moves `span`, which points into the preprocessed text, back to where
+    /// it came from. A [`Span`] in the expansion of a macro is moved to
+    /// where the macro was used.
+    pub fn map_span(&self, span: Span) -> Span {
+        let index = self.segments
+            .partition_point(|segment| segment.start <= span.start());
+        let segment = match index.checked_sub(1) {
+            Some(index) => self.segments[index],
+            None => return span,
+        };
+        if !segment.exact {
+            return segment.origin;
+        }
+        let end = self.segments
+            .get(index)
+            .map_or(self.text.len(), |next| next.start);
+        let start = segment.origin.start() + span.start() - segment.start;
+        let length = span.end().min(end).saturating_sub(span.start());
+        return Span::new(start, start + length)
+            .in_file(segment.origin.file());
+    }
+
+    /// Moves the [`Span`] of every command in `program` with
+    /// [`Expansion::map_span`].
+    pub fn map_program(&self, program: &mut BfProgram) {
+        let tokens: &mut Vec<BfToken> = program.as_mut();
+        for token in tokens.iter_mut() {
+            let span = self.map_span(token.span());
+            *token = BfToken::new(token.command(), span);
+        }
+    }
+
+    /// Moves every [`Span`] in `diagnostics` with [`Expansion::map_span`].
+    pub fn map_diagnostics(&self, diagnostics: &mut Diagnostics) {
+        diagnostics.map_spans(|span| self.map_span(span));
+    }
+
+    /// Adds `text`, which was copied from where `origin` starts.
+    fn push_exact(&mut self, text: &str, origin: Span) -> BfResult<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let start = origin.start();
+        let copied = Span::new(start, start + text.len())
+            .in_file(origin.file());
+        self.fits(text, copied)?;
+        let follows = self.segments.last().is_some_and(|last| {
+            last.exact
+                && last.origin.file() == origin.file()
+                && last.origin.start() + self.text.len() - last.start
+                    == origin.start()
+        });
+        if !follows {
+            self.segments.push(Segment {
+                start: self.text.len(),
+                origin,
+                exact: true,
+            });
+        }
+        self.text.push_str(text);
+        return Ok(());
+    }
+
+    /// Adds `text`, which is the expansion of the macro used at `origin`.
+    fn push_macro(&mut self, text: &str, origin: Span) -> BfResult<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.fits(text, origin)?;
+        self.segments.push(Segment {
+            start: self.text.len(),
+            origin,
+            exact: false,
+        });
+        self.text.push_str(text);
+        return Ok(());
+    }
+
+    /// Checks that `text`, which comes from `origin`, can be added without
+    /// the text growing past [`MAX_LENGTH`] bytes.
+    fn fits(&self, text: &str, origin: Span) -> BfResult<()> {
+        if self.text.len().saturating_add(text.len()) <= MAX_LENGTH {
+            return Ok(());
+        }
+        return Err(preprocessing_error(format!(
+            "The preprocessed program is longer than {} bytes",
+            MAX_LENGTH
+        )).with_span(origin));
+    }
+}
+
+/// A use of a macro, such as `@move(3)`, found in some text.
+struct Call<'a> {
+    name: &'a str,
+    args: Vec<&'a str>,
+    end: usize,
+}
+
+/// Expands includes and macros in a program. See
+/// [`crate::parser::preprocess`] for what it understands.
+/// 
+/// Macros stay defined after [`Preprocessor::process`] returns, so the same
+/// [`Preprocessor`] can be used to process more programs with them.
+#[derive(Clone, Debug, Default)]
+pub struct Preprocessor {
+    macros: HashMap<String, Macro>,
+    includes: Vec<PathBuf>,
+}
+
+impl Preprocessor {
+    /// Creates a new [`Preprocessor`] without any macros.
+    pub fn new() -> Self {
+        return Self {macros: HashMap::new(), includes: Vec::new()};
+    }
+
+    /// Preprocesses `code`, which is the text of the program in `source`,
+    /// or the start of it, such as the part before the input found by
+    /// [`crate::parser::BfParser::split_input`].
+    /// 
+    /// Files are included from the folder the program is in, and added to
+    /// `source` so that errors in them can be shown. Errors found while
+    /// preprocessing have the kind [`BfErrorKind::PreprocessingError`].
+    pub fn process(
+        &mut self,
+        code: &str,
+        source: &mut SourceMap
+    ) -> BfResult<Expansion> {
+        let path = PathBuf::from(source.name());
+        let mut expansion = Expansion::default();
+        self.includes.push(path.canonicalize().unwrap_or(path.clone()));
+        let result = self.expand_file(code, 0, &path, source, &mut expansion);
+        self.includes.pop();
+        result?;
+        return Ok(expansion);
+    }
+
+    /// Expands `text`, which is the file numbered `file` in `source`, found
+    /// at `path`, into `expansion`.
+    fn expand_file(
+        &mut self,
+        text: &str,
+        file: usize,
+        path: &Path,
+        source: &mut SourceMap,
+        expansion: &mut Expansion
+    ) -> BfResult<()> {
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let directive = line.trim();
+            let start = offset + line.len() - line.trim_start().len();
+            let span = Span::new(start, start + directive.len()).in_file(file);
+            if let Some(rest) = strip_directive(directive, "#include") {
+                let at = start + directive.len() - rest.len();
+                self.include(rest, at, span, path, source, expansion)?;
+            } else if let Some(rest) = strip_directive(directive, "#define") {
+                let at = start + directive.len() - rest.len();
+                self.define(rest, Span::new(at, at).in_file(file), span)?;
+            } else {
+                self.expand_line(line, offset, file, expansion)?;
+            }
+            offset += line.len();
+        }
+        return Ok(());
+    }
+
+    /// Includes the file named in `rest`, which starts at `at` in the
+    /// directive at `span`.
+    fn include(
+        &mut self,
+        rest: &str,
+        at: usize,
+        span: Span,
+        path: &Path,
+        source: &mut SourceMap,
+        expansion: &mut Expansion
+    ) -> BfResult<()> {
+        let quoted = rest.trim_start();
+        let at = at + rest.len() - quoted.len();
+        let name = match quoted
+            .strip_prefix('"')
+            .and_then(|quoted| Some(&quoted[..quoted.find('"')?]))
+        {
+            Some(name) => name,
+            None => return Err(preprocessing_error(
+                "Expected the name of a file in quotes after `#include`"
+            ).with_span(span)),
+        };
+        let name_span = Span::new(at, at + name.len() + 2)
+            .in_file(span.file());
+        let after = quoted[name.len() + 2..].trim_start();
+        if !after.is_empty() && !after.starts_with('#') {
+            return Err(preprocessing_error(format!(
+                "Unexpected `{}` after the name of the file",
+                after
+            )).with_span(span));
+        }
+        if self.includes.len() > MAX_DEPTH {
+            return Err(preprocessing_error(format!(
+                "Files are included more than {} deep",
+                MAX_DEPTH
+            )).with_span(name_span));
+        }
+        let target = path.parent().unwrap_or(Path::new("")).join(name);
+        let text = match read_to_string(&target) {
+            Ok(text) => text,
+            Err(error) => return Err(preprocessing_error(format!(
+                "Could not open file with path: {:?}\n\
+                Error by `read_to_string`: {}",
+                target,
+                error
+            )).with_span(name_span)),
+        };
+        let canonical = target.canonicalize().unwrap_or(target.clone());
+        if self.includes.contains(&canonical) {
+            return Err(preprocessing_error(format!(
+                "{:?} includes itself",
+                target
+            )).with_span(name_span));
+        }
+        let file = source.add_file(target.display().to_string(), &text);
+        self.includes.push(canonical);
+        let result = self.expand_file(&text, file, &target, source, expansion);
+        self.includes.pop();
+        return result;
+    }
+
+    /// Defines the macro in `rest`, which starts at `at` in the directive at
+    /// `span`.
+    fn define(&mut self, rest: &str, at: Span, span: Span) -> BfResult<()> {
+        let definition = rest.trim_start();
+        let start = at.start() + rest.len() - definition.len();
+        let name = identifier(definition);
+        if name.is_empty() {
+            return Err(preprocessing_error(
+                "Expected the name of a macro after `#define`"
+            ).with_span(span));
+        }
+        let name_span = Span::new(start, start + name.len())
+            .in_file(at.file());
+        let mut body = &definition[name.len()..];
+        let mut params = Vec::new();
+        if let Some(list) = body.strip_prefix('(') {
+            let close = match list.find(')') {
+                Some(close) => close,
+                None => return Err(preprocessing_error(format!(
+                    "Expected `)` after the parameters of `@{}`",
+                    name
+                )).with_span(span)),
+            };
+            if !list[..close].trim().is_empty() {
+                for param in list[..close].split(',').map(str::trim) {
+                    if identifier(param) != param || param.is_empty() {
+                        return Err(preprocessing_error(format!(
+                            "`{}` is not a valid parameter name",
+                            param
+                        )).with_span(span));
+                    }
+                    params.push(param.to_string());
+                }
+            }
+            body = &list[close + 1..];
+        }
+        // Comments are left out so that they do not hide the rest of the
+        // line the macro is used on.
+        let body = body.split('#').next().unwrap_or("").trim();
+        self.macros.insert(name.to_string(), Macro {
+            params,
+            body: body.to_string(),
+            span: name_span,
+        });
+        return Ok(());
+    }
+
+    /// Expands the macros used in `line`, which starts at `offset` in the
+    /// file numbered `file`, into `expansion`.
+    fn expand_line(
+        &self,
+        line: &str,
+        offset: usize,
+        file: usize,
+        expansion: &mut Expansion
+    ) -> BfResult<()> {
+        let mut copied = 0;
+        let mut index = 0;
+        while let Some(found) = line[index..].find(['#', '@']) {
+            let at = index + found;
+            if line[at..].starts_with('#') {
+                break;
+            }
+            let call = parse_call(&line[at..]).map_err(|message| {
+                let end = offset + line.trim_end().len();
+                return preprocessing_error(message)
+                    .with_span(Span::new(offset + at, end).in_file(file));
+            })?;
+            let call = match call {
+                Some(call) => call,
+                None => {
+                    index = at + 1;
+                    continue;
+                },
+            };
+            let span = Span::new(offset + at, offset + at + call.end)
+                .in_file(file);
+            expansion.push_exact(
+                &line[copied..at],
+                Span::new(offset + copied, offset + copied).in_file(file)
+            )?;
+            let expanded = self.call(&call, span, &mut Vec::new())?;
+            expansion.push_macro(&expanded, span)?;
+            index = at + call.end;
+            copied = index;
+        }
+        return expansion.push_exact(
+            &line[copied..],
+            Span::new(offset + copied, offset + copied).in_file(file)
+        );
+    }
+
+    /// Expands `call`, which was used at `span` in the expansion of the
+    /// macros in `expanding`.
+    fn call(
+        &self,
+        call: &Call,
+        span: Span,
+        expanding: &mut Vec<String>
+    ) -> BfResult<String> {
+        let definition = match self.macros.get(call.name) {
+            Some(definition) => definition,
+            None => return Err(preprocessing_error(format!(
+                "`@{}` is not defined",
+                call.name
+            )).with_span(span)),
+        };
+        let defined_here = format!("`@{}` is defined here", call.name);
+        if expanding.iter().any(|name| name == call.name) {
+            return Err(preprocessing_error(format!(
+                "`@{}` uses itself",
+                call.name
+            )).with_span(span).with_note(definition.span, defined_here));
+        }
+        if call.args.len() != definition.params.len() {
+            let arguments = |count: usize| match count {
+                1 => "1 argument".to_string(),
+                _ => format!("{} arguments", count),
+            };
+            return Err(preprocessing_error(format!(
+                "`@{}` takes {} but {} given",
+                call.name,
+                arguments(definition.params.len()),
+                match call.args.len() {
+                    1 => "1 was".to_string(),
+                    count => format!("{} were", count),
+                }
+            )).with_span(span).with_note(definition.span, defined_here));
+        }
+        let body = substitute(&definition.body, &definition.params, &call.args)
+            .and_then(|body| repeat(&body))
+            .map_err(|message| {
+                return preprocessing_error(message)
+                    .with_span(span)
+                    .with_note(definition.span, &defined_here);
+            })?;
+
+        // Expand the macros used in the body.
+        let mut text = String::new();
+        let mut index = 0;
+        while let Some(found) = body[index..].find('@') {
+            let at = index + found;
+            let inner = match parse_call(&body[at..]) {
+                Ok(Some(inner)) => inner,
+                Ok(None) => {
+                    text.push_str(&body[index..at + 1]);
+                    index = at + 1;
+                    continue;
+                },
+                Err(message) => return Err(preprocessing_error(message)
+                    .with_span(span)
+                    .with_note(definition.span, defined_here)),
+            };
+            text.push_str(&body[index..at]);
+            expanding.push(call.name.to_string());
+            let expanded = self.call(&inner, span, expanding);
+            expanding.pop();
+            match expanded {
+                Ok(expanded) if text.len() + expanded.len() > MAX_LENGTH => {
+                    return Err(too_long(call.name)
+                        .with_span(span)
+                        .with_note(definition.span, defined_here));
+                },
+                Ok(expanded) => text.push_str(&expanded),
+                Err(error) => return Err(error.with_note(
+                    definition.span,
+                    format!("in the expansion of `@{}`", call.name)
+                )),
+            }
+            index = at + inner.end;
+        }
+        text.push_str(&body[index..]);
+        return Ok(text);
+    }
+}
+
+/// Gets the rest of `line` if it is the directive `name`.
+fn strip_directive<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    return line.strip_prefix(name).filter(|rest| {
+        rest.is_empty()
+            || rest.starts_with(char::is_whitespace)
+            || rest.starts_with('"')
+    });
+}
+
+/// Gets the name of a macro or parameter at the start of `text`, which may
+/// be empty.
+fn identifier(text: &str) -> &str {
+    let end = text
+        .char_indices()
+        .find(|&(index, c)| {
+            !(c == '_' || c.is_ascii_alphabetic()
+                || index > 0 && c.is_ascii_digit())
+        })
+        .map_or(text.len(), |(index, _)| index);
+    return &text[..end];
+}
+
+/// Reads the use of a macro at the start of `text`, which starts with `@`.
+/// If there is no name after the `@`, it is not the use of a macro.
+fn parse_call(text: &str) -> Result<Option<Call<'_>>, String> {
+    let name = identifier(&text[1..]);
+    if name.is_empty() {
+        return Ok(None);
+    }
+    let open = 1 + name.len();
+    if !text[open..].starts_with('(') {
+        return Ok(Some(Call {name, args: Vec::new(), end: open}));
+    }
+    let close = match matching_bracket(&text[open..]) {
+        Some(close) => open + close,
+        None => return Err(format!(
+            "Expected `)` after the arguments of `@{}`",
+            name
+        )),
+    };
+    let inside = &text[open + 1..close];
+    let mut args = Vec::new();
+    if !inside.trim().is_empty() {
+        let mut depth = 0;
+        let mut start = 0;
+        for (index, c) in inside.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    args.push(inside[start..index].trim());
+                    start = index + 1;
+                },
+                _ => (),
+            }
+        }
+        args.push(inside[start..].trim());
+    }
+    return Ok(Some(Call {name, args, end: close + 1}));
+}
+
+/// Finds the `)` which closes the `(` at the start of `text`.
+fn matching_bracket(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 1 => return Some(index),
+            ')' => depth -= 1,
+            _ => (),
+        }
+    }
+    return None;
+}
+
+/// Replaces every `$param` in `body` with its argument in `args`.
+fn substitute(
+    body: &str,
+    params: &[String],
+    args: &[&str]
+) -> Result<String, String> {
+    let mut text = String::new();
+    let mut rest = body;
+    while let Some(found) = rest.find('$') {
+        text.push_str(&rest[..found]);
+        let name = identifier(&rest[found + 1..]);
+        match params.iter().position(|param| param == name) {
+            Some(index) => text.push_str(args[index]),
+            None => return Err(format!("`${}` is not a parameter", name)),
+        }
+        rest = &rest[found + 1 + name.len()..];
+    }
+    text.push_str(rest);
+    return Ok(text);
+}
+
+/// Repeats every character, group in brackets or use of a macro in `text`
+/// which is followed by `*` and a number.
+fn repeat(text: &str) -> Result<String, String> {
+    let mut repeated = String::new();
+    let mut rest = text;
+    while let Some(first) = rest.chars().next() {
+        let length = match first {
+            '(' => matching_bracket(rest).map_or(1, |close| close + 1),
+            '@' => match parse_call(rest) {
+                Ok(Some(call)) => call.end,
+                _ => 1,
+            },
+            _ => first.len_utf8(),
+        };
+        let (unit, after) = rest.split_at(length);
+        rest = after;
+        let group = first == '(' && length > 1;
+        let unit = match group {
+            true => format!("({})", repeat(&unit[1..length - 1])?),
+            false => unit.to_string(),
+        };
+        let count = rest
+            .strip_prefix('*')
+            .map(|count| count.len() - count.trim_start_matches(
+                |c: char| c.is_ascii_digit()
+            ).len())
+            .unwrap_or(0);
+        if count == 0 {
+            repeated.push_str(&unit);
+            continue;
+        }
+        let times: usize = match rest[1..count + 1].parse() {
+            Ok(times) => times,
+            Err(_) => return Err(format!(
+                "`{}` is too many times to repeat something",
+                &rest[1..count + 1]
+            )),
+        };
+        let unit = match group {
+            true => &unit[1..unit.len() - 1],
+            false => &unit,
+        };
+        let fits = unit.len()
+            .checked_mul(times)
+            .and_then(|length| length.checked_add(repeated.len()))
+            .is_some_and(|length| length <= MAX_LENGTH);
+        if !fits {
+            return Err(format!(
+                "`{}` repeated {} times is longer than {} bytes",
+                unit,
+                times,
+                MAX_LENGTH
+            ));
+        }
+        repeated.push_str(&unit.repeat(times));
+        rest = &rest[count + 1..];
+    }
+    return Ok(repeated);
+}
+
+/// Creates the error for a use of the macro `name` whose expansion is longer
+/// than [`MAX_LENGTH`].
+fn too_long(name: &str) -> BfError {
+    return preprocessing_error(format!(
+        "the expansion of `@{}` is longer than {} bytes",
+        name,
+        MAX_LENGTH
+    ));
+}
+
+/// Creates a [`BfError`] with the kind
+/// [`BfErrorKind::PreprocessingError`].
+fn preprocessing_error(description: impl AsRef<str>) -> BfError {
+    return BfError::new(BfErrorKind::PreprocessingError, description);
+}
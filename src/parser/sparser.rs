@@ -4,7 +4,7 @@ use crate::{
     code::{BfProgram, BfCommand, BfToken, Span},
     debug::{Event, OkEvent, Status, ErrEvent, BfError, BfErrorKind}
 };
-use super::BfParser;
+use super::{parser_traits::pest_error, BfParser};
 
 #[derive(Parser)]
 #[grammar = "./parser/simple.pest"]
@@ -57,10 +57,7 @@ impl BfParser for NormalParser {
         let script = script.as_ref();
         let ast = match Self::parse(Rule::FILE, script) {
             Ok(parsed) => parsed,
-            Err(_) => return Err(ErrEvent::Error(BfError::new(
-                BfErrorKind::BadProgram,
-                "Could not parse string."
-            ))),
+            Err(error) => return Err(ErrEvent::Error(pest_error(error))),
         };
         return Self::parse_ast(ast, program);
     }
@@ -0,0 +1,146 @@
+//! [`brainfucklib::parser::stream`]
+//! 
+//! This module reads Brainfuck programs from any [`Read`]er a chunk at a
+//! time, so that programs which are hundreds of megabytes long can be
+//! parsed from a pipe or a file without holding all of their text in
+//! memory. It is used by [`crate::parser::BfParser::parse_reader`].
+//! 
+//! The grammars cannot be run on part of a program, so [`read_commands`]
+//! follows them by hand: it finds the same commands with the same [`Span`]s,
+//! and rejects the same programs. A program which is not valid UTF-8 cannot
+//! be given to a grammar as a string, so it is rejected as well.
+
+use std::{
+    io::{ErrorKind, Read},
+    str,
+};
+
+use crate::{
+    code::{BfCommand, BfProgram, BfToken, Span},
+    debug::{Event, OkEvent, Status, ErrEvent, BfError, BfErrorKind},
+};
+
+/// The number of bytes read from a [`Read`]er at a time.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The grammar [`read_commands`] follows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Grammar {
+    /// The grammar of [`crate::parser::NormalParser`], which skips
+    /// everything that is not a command and does not pair up brackets, so
+    /// unmatched brackets are only found by [`BfProgram::calculate_map`] or
+    /// [`BfProgram::check`].
+    Normal,
+    /// The grammar of [`crate::parser::NewParser`], which also skips
+    /// everything from a `#` up to the end of its line, and stops at the
+    /// first bracket which cannot be paired up.
+    New,
+    /// The commands [`Grammar::New`] would find, without pairing up
+    /// brackets, so that every unmatched bracket can be found afterwards by
+    /// [`BfProgram::check`].
+    Tokens,
+}
+
+/// Reads the commands in `reader` into `program` following `grammar`, a
+/// chunk of [`CHUNK_SIZE`] bytes at a time.
+/// 
+/// An unmatched bracket is reported with the same error
+/// [`BfProgram::check`] gives it, which is the first error
+/// [`crate::parser::BfParser::check_string`] reports for the same program.
+pub fn read_commands<R>(
+    mut reader: R,
+    program: &mut BfProgram,
+    grammar: Grammar
+) -> Event
+where
+    R: Read
+{
+    let mut buffer = vec![0; CHUNK_SIZE];
+    // The bytes which have been read but not scanned yet, which are the
+    // start of a character cut in half at the end of the last chunk.
+    let mut text = Vec::with_capacity(CHUNK_SIZE + 4);
+    let mut offset = 0;
+    let mut in_comment = false;
+    // The indices in `program` of the `[`s which are not closed yet.
+    let mut open = Vec::new();
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(ErrEvent::Error(BfError::new(
+                BfErrorKind::Other,
+                format!(
+                    "Could not read program after {} bytes.\n\
+                    Error by `read`: {}",
+                    offset,
+                    error
+                )
+            ).with_span(Span::new(offset, offset)))),
+        };
+        text.extend_from_slice(&buffer[..read]);
+        let valid = match str::from_utf8(&text) {
+            Ok(_) => text.len(),
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(error) => return Err(not_utf8(offset + error.valid_up_to())),
+        };
+        for (index, &byte) in text[..valid].iter().enumerate() {
+            if in_comment {
+                in_comment = byte != b'\n' && byte != b'\r';
+                continue;
+            }
+            let command = match byte {
+                b'+' => BfCommand::Increment,
+                b'-' => BfCommand::Decrement,
+                b'<' => BfCommand::Previous,
+                b'>' => BfCommand::Next,
+                b'.' => BfCommand::Read,
+                b',' => BfCommand::Write,
+                b'[' => BfCommand::IfZero,
+                b']' => BfCommand::IfNotZero,
+                b'#' if grammar != Grammar::Normal => {
+                    in_comment = true;
+                    continue;
+                },
+                _ => continue,
+            };
+            let start = offset + index;
+            let location = AsRef::<Vec<BfToken>>::as_ref(program).len();
+            program.push(BfToken::new(command, Span::new(start, start + 1)));
+            if grammar != Grammar::New {
+                continue;
+            }
+            match command {
+                BfCommand::IfZero => open.push(location),
+                BfCommand::IfNotZero if open.pop().is_none() => {
+                    return Err(ErrEvent::Error(program.unmatched(
+                        BfErrorKind::UnmatchedRightBracket,
+                        location
+                    )));
+                },
+                _ => (),
+            }
+        }
+        text.drain(..valid);
+        offset += valid;
+    }
+    if !text.is_empty() {
+        return Err(not_utf8(offset));
+    }
+    if let Some(&location) = open.first() {
+        return Err(ErrEvent::Error(program.unmatched(
+            BfErrorKind::UnmatchedLeftBracket,
+            location
+        )));
+    }
+    return Ok(OkEvent::Status(Status::new("Read program: OK")));
+}
+
+/// Creates the error for a program which stops being valid UTF-8 at
+/// `offset`.
+fn not_utf8(offset: usize) -> ErrEvent {
+    return ErrEvent::Error(BfError::new(
+        BfErrorKind::BadProgram,
+        "The program is not valid UTF-8."
+    ).with_span(Span::new(offset, offset + 1)));
+}
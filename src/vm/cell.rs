@@ -1,13 +1,15 @@
 //! [`brainfucklib::vm::cell`]
 //! 
-//! This module defines a struct called [`MemoryCell`] which stores a [`i128`]
-//! value inside it. This represents (as its name implies) a memory cell in
-//! a Brainfuck virtual machine. The struct provides the necessary functions
+//! This module defines a struct called [`MemoryCell`] which stores a value
+//! inside it, which is a [`i128`] unless another [`CellValue`] is chosen.
+//! This represents (as its name implies) a memory cell in a Brainfuck
+//! virtual machine. The struct provides the necessary functions
 //! to check inputs such as incrementing and decrementing in addition to
 //! accepting [`char`]acters as arguments.
 
 use std::fmt;
-use crate::debug::{Event, OkEvent, Status};
+use crate::debug::{Event, OkEvent, Status, ErrEvent, BfError, BfErrorKind};
+use super::{CellMode, CellValue};
 
 /// This is a wrapper around [`i128`], the number that is stored in a
 /// [`MemoryCell`].
@@ -23,20 +25,46 @@ pub fn default_lower() -> CellNumber {
     return 0x00;
 }
 
+/// A [`MemoryCell`] which can hold any integer, for use with
+/// [`CellMode::Unbounded`].
+#[cfg(feature = "bignum")]
+pub type BigCell = MemoryCell<num_bigint::BigInt>;
+
 /// A memory cell in a Brainfuck virtual machine.
 /// 
+/// Arithmetic on the cell is done on [`CellNumber`]s, but the value is
+/// stored as `T`, so that cells which fit in a smaller type take up less
+/// space.
+/// 
 /// # Fields
-/// 1. `number`: [`CellNumber`]
+/// 1. `value`: `T`
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
-pub struct MemoryCell {
-    number: CellNumber,
+#[repr(transparent)]
+pub struct MemoryCell<T = CellNumber>
+where
+    T: CellValue,
+{
+    value: T,
 }
 
-impl MemoryCell {
+impl<T> MemoryCell<T>
+where
+    T: CellValue,
+{
     /// Creates a [`MemoryCell`]. By default, `number` should be the lower
     /// limit (usually [`default_lower`]) of your virtual machine.
     pub fn new(number: CellNumber) -> Self {
-        return Self {number};
+        return Self {value: T::from_number(number)};
+    }
+
+    /// Creates a [`MemoryCell`] which stores `value` as it is.
+    pub fn from_value(value: T) -> Self {
+        return Self {value};
+    }
+
+    /// Gets a reference to the value stored in the [`MemoryCell`].
+    pub fn value(&self) -> &T {
+        return &self.value;
     }
 
     /// Returns true if the cell is equal to the lower bound of the virtual
@@ -67,92 +95,197 @@ impl MemoryCell {
     /// `highest` (inclusive between).
     pub fn wrap(&mut self, lowest: CellNumber, highest: CellNumber) {
         let original = self.number();
-        if self.below_lowest(lowest) || self.at_highest(highest) {
-            *self.number_mut() = original % highest + lowest;
+        if self.below_lowest(lowest) || self.above_highest(highest - 1) {
+            self.set(wrapped(original, lowest, highest));
         }
     }
 
     /// Increments the value of the cell, while checking if it is within the
-    /// lower and upper bounds of the virtual machine.
+    /// lower and upper bounds of the virtual machine. What happens at the
+    /// upper bound depends on `mode`.
     pub fn increment(
         &mut self,
         lowest: CellNumber,
-        highest: CellNumber
+        highest: CellNumber,
+        mode: CellMode
     ) -> Event {
-        if self.number() == CellNumber::MAX {
-            *self.number_mut() = lowest;
-        } else {
-            *self.number_mut() += 1;
-        }
-        self.wrap(lowest, highest);
-
+        self.add(1, lowest, highest, mode)?;
         return Ok(OkEvent::Status(Status::new("Increment memory cell: OK")));
     }
 
     /// Decrements the value of the cell, while checking if it is within the
-    /// lower and upper bounds of the virtual machine.
+    /// lower and upper bounds of the virtual machine. What happens at the
+    /// lower bound depends on `mode`.
     pub fn decrement(
         &mut self,
         lowest: CellNumber,
-        highest: CellNumber
+        highest: CellNumber,
+        mode: CellMode
     ) -> Event {
-        if self.below_lowest(lowest) {
+        self.add(-1, lowest, highest, mode)?;
+        return Ok(OkEvent::Status(Status::new("Decrement memory cell: OK")));
+    }
+
+    /// Adds `amount` to the value of the cell, so that it stays between
+    /// `lowest` and `highest` (exclusive). This has the same effect as
+    /// calling [`MemoryCell::increment`] or [`MemoryCell::decrement`]
+    /// `amount` times.
+    /// 
+    /// If the result would not fit, it is wrapped around, saturated or
+    /// turned into a [`BfErrorKind::CellOverflow`] error depending on
+    /// `mode`. With [`CellMode::Unbounded`], the bounds are ignored.
+    pub fn add(
+        &mut self,
+        amount: CellNumber,
+        lowest: CellNumber,
+        highest: CellNumber,
+        mode: CellMode
+    ) -> Event {
+        if mode == CellMode::Unbounded {
+            self.value.add_number(amount);
+            return Ok(OkEvent::Status(Status::new("Add to memory cell: OK")));
+        }
+        if mode == CellMode::Wrap && T::is_native(lowest, highest) {
+            self.set(self.number().wrapping_add(amount));
+            return Ok(OkEvent::Status(Status::new("Add to memory cell: OK")));
+        }
+        if self.below_lowest(lowest) || self.above_highest(highest - 1) {
             self.wrap(lowest, highest);
-        } else if self.at_lowest(lowest) {
-            *self.number_mut() = highest;
-        } else {
-            *self.number_mut() -= 1;
         }
+        let range = highest - lowest;
+        let offset = self.number() - lowest;
+        // Avoid the (slow) 128-bit remainder unless the cell actually wraps.
+        let offset = match offset.checked_add(amount) {
+            Some(sum) if 0 <= sum && sum < range => sum,
+            _ => match mode {
+                CellMode::Wrap | CellMode::Unbounded => {
+                    (offset + amount.rem_euclid(range)).rem_euclid(range)
+                },
+                CellMode::Saturate if amount < 0 => 0,
+                CellMode::Saturate => range - 1,
+                CellMode::Error => {
+                    return Err(overflow(amount < 0, lowest, highest));
+                },
+            },
+        };
+        self.set(lowest + offset);
+        return Ok(OkEvent::Status(Status::new("Add to memory cell: OK")));
+    }
 
-        return Ok(OkEvent::Status(Status::new("Decrement memory cell: OK")));
+    /// Adds how far `source` is above `zero` multiplied by `factor` to the
+    /// value of the cell, while keeping the value within its bounds like
+    /// [`MemoryCell::add`].
+    pub fn multiply_add(
+        &mut self,
+        source: &Self,
+        factor: CellNumber,
+        zero: CellNumber,
+        lowest: CellNumber,
+        highest: CellNumber,
+        mode: CellMode
+    ) -> Event {
+        if mode == CellMode::Unbounded {
+            self.value.add_product(source.value(), factor);
+            self.value.add_number(zero.wrapping_mul(factor).wrapping_neg());
+            return Ok(OkEvent::Status(Status::new("Add to memory cell: OK")));
+        }
+        let number = source.number().wrapping_sub(zero);
+        if mode == CellMode::Wrap && T::is_native(lowest, highest) {
+            let amount = number.wrapping_mul(factor);
+            return self.add(amount, lowest, highest, mode);
+        }
+        let amount = match number.checked_mul(factor) {
+            Some(amount) => amount,
+            None if mode == CellMode::Wrap => {
+                let range = highest - lowest;
+                mul_mod(
+                    number.rem_euclid(range),
+                    factor.rem_euclid(range),
+                    range
+                )
+            },
+            // Too large for any cell, so this saturates or raises an error.
+            None if (number < 0) != (factor < 0) => CellNumber::MIN,
+            None => CellNumber::MAX,
+        };
+        return self.add(amount, lowest, highest, mode);
+    }
+
+    /// Sets the cell to `zero` (see [`crate::vm::MemoryOptions::zero`]).
+    pub fn flatten(&mut self, zero: CellNumber) {
+        self.set(zero);
+    }
+
+    /// Sets the cell to the largest value below the modulo limit `highest`
+    /// (upper bound).
+    pub fn maximize(&mut self, highest: CellNumber) {
+        self.set(highest - 1);
     }
 
-    /// Sets the cell to `lowest` (lower bound).
-    pub fn flatten(&mut self, lowest: CellNumber) {
-        self.number = lowest;
+    /// Checks whether the value of the memory cell is `zero`, which ends a
+    /// loop.
+    pub fn is_zero(&self, zero: CellNumber) -> bool {
+        return self.value.equals(zero);
     }
 
-    /// Outputs the value of the memory cell as a character via `output`.
-    pub fn to_char(&self, output: &mut char) -> Event {
-        //println!("{}", self.number());
-        let out = std::char::from_u32(self.number() as u32).unwrap_or('�');
+    /// Outputs the value of the memory cell as a character via `output`,
+    /// whose code point is how far the value is above `zero`. Values which
+    /// are not valid characters are output as `�`.
+    pub fn to_char(&self, zero: CellNumber, output: &mut char) -> Event {
+        let out = self.value.to_char(zero).unwrap_or('�');
         *output = out;
         return Ok(OkEvent::Status(Status::new("Output char: OK")));
     }
 
-    /// Get the unicode number of an `input` character and store that value
-    /// as the value of the [`MemoryCell`]. Of course, if the value exceeds
-    /// the bounds of the virtual machine, the value is wrapped.
+    /// Get the unicode number of an `input` character and store that many
+    /// more than `zero` as the value of the [`MemoryCell`]. Of course, if
+    /// the value exceeds the bounds of the virtual machine, the value is
+    /// wrapped, saturated or rejected depending on `mode`, unless the cell
+    /// is [`CellMode::Unbounded`].
     pub fn from_char(
         &mut self,
         input: char,
+        zero: CellNumber,
         lowest: CellNumber,
-        highest: CellNumber
+        highest: CellNumber,
+        mode: CellMode
     ) -> Event {
-        let mut value = input as CellNumber;
-        if lowest <= value && value < highest {
-            *self.number_mut() = value;
+        let mut value = zero.saturating_add(input as CellNumber);
+        let fits = lowest <= value && value < highest;
+        if fits || mode == CellMode::Unbounded {
+            self.set(value);
         } else {
-            value = value % highest + lowest;
-            *self.number_mut() = value;
+            value = match mode {
+                CellMode::Wrap | CellMode::Unbounded => {
+                    wrapped(value, lowest, highest)
+                },
+                CellMode::Saturate if value < lowest => lowest,
+                CellMode::Saturate => highest - 1,
+                CellMode::Error => {
+                    return Err(overflow(value < lowest, lowest, highest));
+                },
+            };
+            self.set(value);
         }
         return Ok(OkEvent::Status(Status::new("Input char: OK")));
     }
 
-    /// Gets a mutable reference to the value inside the [`MemoryCell`].
+    /// Stores `number` in the [`MemoryCell`].
     /// 
     /// This function is private.
-    fn number_mut(&mut self) -> &mut CellNumber {
-        return &mut self.number;
+    fn set(&mut self, number: CellNumber) {
+        self.value = T::from_number(number);
     }
 
     /// Gets the value in the [`MemoryCell`]. This value is only a copy of the
     /// value when the method was called, and is not a reference to the value
     /// inside the cell.
     pub fn number(&self) -> CellNumber {
-        return self.number;
+        return self.value.number();
     }
+}
 
+impl MemoryCell<CellNumber> {
     /// Gets the minimum possible value of a [`MemoryCell`].
     pub fn minimum() -> Self {
         return Self::new(CellNumber::MIN);
@@ -164,29 +297,83 @@ impl MemoryCell {
     }
 }
 
-impl Into<CellNumber> for MemoryCell {
-    /// Converts the [`MemoryCell`] into a [`CellNumber`]. As [`MemoryCell`]
-    /// implements [`Copy`], it means that the original [`MemoryCell`] will
-    /// not be destroyed.
-    fn into(self) -> CellNumber {
-        return self.number();
+/// Creates the [`BfErrorKind::CellOverflow`] error for a cell which would
+/// have gone below `lowest` (if `below` is true) or reached `highest`.
+fn overflow(below: bool, lowest: CellNumber, highest: CellNumber) -> ErrEvent {
+    let description = match below {
+        true => format!("Cell would go below its lower bound of {}.", lowest),
+        false => format!(
+            "Cell would go above its upper bound of {}.",
+            highest - 1
+        ),
+    };
+    return ErrEvent::Error(BfError::new(
+        BfErrorKind::CellOverflow,
+        description
+    ));
+}
+
+/// Wraps `number` around so that it is between `lowest` and `highest`
+/// (exclusive).
+fn wrapped(
+    number: CellNumber,
+    lowest: CellNumber,
+    highest: CellNumber
+) -> CellNumber {
+    let range = highest - lowest;
+    return lowest + number.wrapping_sub(lowest).rem_euclid(range);
+}
+
+/// Multiplies `a` by `b` modulo `modulus` without overflowing, where `a` and
+/// `b` are both between 0 and `modulus` (exclusive).
+fn mul_mod(a: CellNumber, b: CellNumber, modulus: CellNumber) -> CellNumber {
+    let modulus = modulus as u128;
+    let mut a = a as u128;
+    let mut b = b as u128;
+    let mut product: u128 = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            product = (product + a) % modulus;
+        }
+        a = (a + a) % modulus;
+        b >>= 1;
+    }
+    return product as CellNumber;
+}
+
+impl<T> From<MemoryCell<T>> for CellNumber
+where
+    T: CellValue,
+{
+    /// Converts the [`MemoryCell`] into a [`CellNumber`].
+    fn from(cell: MemoryCell<T>) -> Self {
+        return cell.number();
     }
 }
 
-impl From<CellNumber> for MemoryCell {
+impl<T> From<CellNumber> for MemoryCell<T>
+where
+    T: CellValue,
+{
     /// Creates a [`MemoryCell`] from a [`CellNumber`].
     fn from(number: CellNumber) -> Self {
         return Self::new(number);
     }
 }
 
-impl fmt::Display for MemoryCell {
+impl<T> fmt::Display for MemoryCell<T>
+where
+    T: CellValue,
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         return write!(f, "{}", self.number());
     }
 }
 
-impl Default for MemoryCell {
+impl<T> Default for MemoryCell<T>
+where
+    T: CellValue,
+{
     /// Returns a [`MemoryCell`] initialized with a value of [`default_lower`].
     fn default() -> Self {
         return Self::new(default_lower());
@@ -0,0 +1,262 @@
+//! [`brainfucklib::vm::dump`]
+//! 
+//! This module defines [`MemoryDump`], a copy of some of the cells of a
+//! virtual machine which can be formatted as a hex, decimal or ASCII dump
+//! with [`MemoryDump::format`], and [`DumpFormat`], which chooses between
+//! them.
+//! 
+//! Dumps are taken with [`crate::vm::Memory::dump`],
+//! [`crate::vm::Memory::window`] or [`crate::vm::Memory::used`] (or the
+//! methods of the same name on [`crate::vm::SparseMemory`]). Each row of a
+//! dump starts with the position of its first cell, and the cell the pointer
+//! is pointing at is surrounded by square brackets. This is a dump in
+//! [`DumpFormat::Ascii`]:
+//! 
+//! ```text
+//!  0: H  e  l  l  o     w  o  r  l  d [.]
+//! ```
+//! 
+//! In the other formats, each row ends with the same cells as ASCII between
+//! two `|`s, like the output of `hexdump -C`. Also like `hexdump -C`, rows
+//! in which every cell is zero are left out (unless the pointer is in one)
+//! and a `*` is shown instead, so that a dump of two cells which are far
+//! apart stays short.
+
+use std::{fmt, str::FromStr};
+
+use crate::debug::{BfError, BfErrorKind};
+use super::{CellNumber, CellValue, MemoryCell};
+
+/// The number of cells in each row of a [`MemoryDump`].
+pub const DUMP_WIDTH: usize = 16;
+
+/// How the cells in a [`MemoryDump`] are shown.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DumpFormat {
+    /// Show each cell in hexadecimal, followed by the cells as ASCII.
+    Hex,
+    /// Show each cell in decimal, followed by the cells as ASCII.
+    Decimal,
+    /// Only show the cells as ASCII.
+    Ascii,
+}
+
+impl Default for DumpFormat {
+    /// Cells are shown in hexadecimal by default.
+    fn default() -> Self {
+        return DumpFormat::Hex;
+    }
+}
+
+impl FromStr for DumpFormat {
+    type Err = BfError;
+
+    /// Parses "hex", "decimal" or "ascii" into a [`DumpFormat`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "hex" => Ok(DumpFormat::Hex),
+            "decimal" => Ok(DumpFormat::Decimal),
+            "ascii" => Ok(DumpFormat::Ascii),
+            _ => Err(BfError::new(
+                BfErrorKind::Other,
+                format!("{} is not a valid dump format.", s)
+            )),
+        };
+    }
+}
+
+impl fmt::Display for DumpFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", match self {
+            DumpFormat::Hex => "hex",
+            DumpFormat::Decimal => "decimal",
+            DumpFormat::Ascii => "ascii",
+        });
+    }
+}
+
+/// A row of a [`MemoryDump`]: the position of its first cell and up to
+/// [`DUMP_WIDTH`] cells from there.
+type Row<T> = (isize, Vec<MemoryCell<T>>);
+
+/// A copy of the cells of a virtual machine from position `start` up to
+/// position `end`, along with the position of the pointer.
+/// 
+/// Positions are relative to the cell the pointer started at, like
+/// [`crate::vm::Memory::pointer`]. The cells are split into rows of
+/// [`DUMP_WIDTH`] cells, and only the rows which have a cell that is not
+/// zero or the pointer in them are kept.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryDump<T = CellNumber>
+where
+    T: CellValue,
+{
+    start: isize,
+    end: isize,
+    rows: Vec<Row<T>>,
+    pointer: isize,
+    zero: MemoryCell<T>,
+}
+
+impl<T> MemoryDump<T>
+where
+    T: CellValue,
+{
+    /// Creates a [`MemoryDump`] of `cells`, the first of which is at
+    /// position `start`, while the pointer is at position `pointer`.
+    /// 
+    /// Cells are shown as ASCII relative to `zero`, the same way `.` outputs
+    /// them (see [`crate::vm::MemoryOptions::zero`]).
+    pub fn new(
+        start: isize,
+        cells: Vec<MemoryCell<T>>,
+        pointer: isize,
+        zero: CellNumber,
+    ) -> Self {
+        let end = start + cells.len() as isize;
+        let rows = cells.len().div_ceil(DUMP_WIDTH);
+        return Self::collect(
+            start,
+            end,
+            0..rows,
+            |position| cells[(position - start) as usize].clone(),
+            pointer,
+            zero
+        );
+    }
+
+    /// Creates a [`MemoryDump`] of the cells from position `start` up to
+    /// position `end`, where `cell` gets the cell at a position. Only the
+    /// rows numbered in `rows` (counting from 0 at `start`, in order) are
+    /// looked at, so every cell in the other rows has to be zero.
+    pub(crate) fn collect<I, F>(
+        start: isize,
+        end: isize,
+        rows: I,
+        cell: F,
+        pointer: isize,
+        zero: CellNumber,
+    ) -> Self
+    where
+        I: IntoIterator<Item = usize>,
+        F: Fn(isize) -> MemoryCell<T>,
+    {
+        let zero: MemoryCell<T> = MemoryCell::new(zero);
+        let kept = rows.into_iter()
+            .map(|row| start + (row * DUMP_WIDTH) as isize)
+            .filter(|&first| first < end)
+            .map(|first| {
+                let last = end.min(first + DUMP_WIDTH as isize);
+                (first, (first..last).map(&cell).collect::<Vec<_>>())
+            })
+            .filter(|(first, cells)| {
+                let next = first + cells.len() as isize;
+                (*first..next).contains(&pointer)
+                    || cells.iter().any(|cell| *cell != zero)
+            })
+            .collect();
+        return Self {start, end, rows: kept, pointer, zero};
+    }
+
+    /// Gets the position of the first cell in the dump.
+    pub fn start(&self) -> isize {
+        return self.start;
+    }
+
+    /// Gets the position after the last cell in the dump.
+    pub fn end(&self) -> isize {
+        return self.end;
+    }
+
+    /// Gets the rows of the dump which are kept, as the position of their
+    /// first cell and their cells. Every cell in the other rows is zero.
+    pub fn rows(&self) -> impl Iterator<Item = (isize, &[MemoryCell<T>])> {
+        return self.rows.iter().map(|(first, cells)| (*first, &cells[..]));
+    }
+
+    /// Gets the position of the pointer, which may be outside of the dump.
+    pub fn pointer(&self) -> isize {
+        return self.pointer;
+    }
+
+    /// Gets the cell at `position`, if it is in the dump.
+    pub fn get(&self, position: isize) -> Option<&MemoryCell<T>> {
+        if position < self.start || position >= self.end {
+            return None;
+        }
+        let row = self.rows.partition_point(|(first, _)| *first <= position);
+        return match row.checked_sub(1).map(|row| &self.rows[row]) {
+            Some((first, cells)) => Some(
+                cells.get((position - first) as usize).unwrap_or(&self.zero)
+            ),
+            None => Some(&self.zero),
+        };
+    }
+
+    /// Formats the dump as rows of [`DUMP_WIDTH`] cells in `format`, with a
+    /// `*` in place of the rows which are left out.
+    pub fn format(&self, format: DumpFormat) -> String {
+        let token = |cell: &MemoryCell<T>| match format {
+            DumpFormat::Hex => cell.value().hex(),
+            DumpFormat::Decimal => cell.value().to_string(),
+            DumpFormat::Ascii => self.ascii(cell).to_string(),
+        };
+        let width = self.rows
+            .iter()
+            .flat_map(|(_, cells)| cells.iter().map(|cell| token(cell).len()))
+            .max()
+            .unwrap_or(1);
+        let margin = self.start.to_string().len()
+            .max(self.end.to_string().len());
+        let mut dump = String::new();
+        let mut next = self.start;
+        for (first, cells) in self.rows.iter() {
+            if *first != next {
+                dump.push_str("*\n");
+            }
+            next = first + cells.len() as isize;
+            dump.push_str(&format!("{:>1$}:", first, margin));
+            for (column, cell) in cells.iter().enumerate() {
+                let position = first + column as isize;
+                dump.push_str(&match position == self.pointer {
+                    true => format!("[{:>1$}]", token(cell), width),
+                    false => format!(" {:>1$} ", token(cell), width),
+                });
+            }
+            if format != DumpFormat::Ascii {
+                let padding = (DUMP_WIDTH - cells.len()) * (width + 2);
+                let text: String = cells
+                    .iter()
+                    .map(|cell| self.ascii(cell))
+                    .collect();
+                dump.push_str(&format!("{:1$} |{2}|", "", padding, text));
+            }
+            dump.truncate(dump.trim_end().len());
+            dump.push('\n');
+        }
+        if next != self.end {
+            dump.push_str("*\n");
+        }
+        return dump;
+    }
+
+    /// Gets the character `cell` is output as, or `.` if it is not a
+    /// printable ASCII character.
+    fn ascii(&self, cell: &MemoryCell<T>) -> char {
+        let zero = self.zero.value().number();
+        return match cell.value().to_char(zero) {
+            Some(c) if c.is_ascii_graphic() || c == ' ' => c,
+            _ => '.',
+        };
+    }
+}
+
+impl<T> fmt::Display for MemoryDump<T>
+where
+    T: CellValue,
+{
+    /// Formats the dump in [`DumpFormat::Hex`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.format(DumpFormat::Hex));
+    }
+}
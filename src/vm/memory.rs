@@ -6,12 +6,14 @@
 //! with [`MemoryOptions`].
 
 use std::{
-    io::{Error, ErrorKind}
+    io::{Error, ErrorKind},
+    iter,
+    rc::Rc,
 };
 
 use crate::{
     code::CommandRunner,
-    debug::{Event, OkEvent, Status},
+    debug::{Event, OkEvent, Status, ErrEvent, BfError, BfErrorKind},
     unwrapcell,
 };
 use super::{
@@ -19,50 +21,103 @@ use super::{
     default_lower,
     default_range,
     default_upper,
+    CellMode,
+    CellValue,
+    EofPolicy,
     MemoryCell,
+    MemoryDump,
     MemoryRange,
     MemoryPointer,
+    MemorySnapshot,
+    PointerPolicy,
+    Preload,
 };
 
 /// Customization for [`Memory`].
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct MemoryOptions {
-    variable_length: bool,
-    wrap: bool,
+    pointer_policy: PointerPolicy,
+    cell_mode: CellMode,
     lower_bound: CellNumber,
     upper_bound: CellNumber,
     initial_length: MemoryRange,
+    eof_policy: EofPolicy,
+    preload: Preload,
 }
 
 impl MemoryOptions {
     /// Creates a new set of [`MemoryOptions`] with default values.
     pub fn new() -> Self {
         return Self {
-            variable_length: false,
-            wrap: true,
+            pointer_policy: PointerPolicy::default(),
+            cell_mode: CellMode::default(),
             lower_bound: default_lower(),
             upper_bound: default_upper(),
             initial_length: default_range(),
+            eof_policy: EofPolicy::default(),
+            preload: Preload::default(),
         };
     }
 
     /// Sets whether the memory tape should increase in size when necessary.
     /// By default, this field is `false`, which means that the pointer to
     /// the tape wraps back to 0 when it reaches the end of the tape.
+    /// 
+    /// This is a shorthand for setting the [`PointerPolicy`] to
+    /// [`PointerPolicy::Grow`] or [`PointerPolicy::Wrap`].
     pub fn variable_length(&mut self, setting: bool) -> &mut Self {
-        self.variable_length = setting;
+        self.pointer_policy = match setting {
+            true => PointerPolicy::Grow,
+            false => PointerPolicy::Wrap,
+        };
+        return self;
+    }
+
+    /// Sets whether the memory tape should act as if it had no ends, so
+    /// that the pointer can move as far as it likes in either direction.
+    /// 
+    /// This is a shorthand for setting the [`PointerPolicy`] to
+    /// [`PointerPolicy::Infinite`] or [`PointerPolicy::Wrap`].
+    pub fn infinite(&mut self, setting: bool) -> &mut Self {
+        self.pointer_policy = match setting {
+            true => PointerPolicy::Infinite,
+            false => PointerPolicy::Wrap,
+        };
         return self;
     }
 
-    /// Sets whether each memory cell should wrap around if it is not within
-    /// lower_bound and upper_bound. I haven't implemented the functionality
-    /// for when `wrap` is `false` so this method is currently private.
-    #[allow(dead_code)]
-    fn wrap(&mut self, setting: bool) -> &mut Self {
-        self.wrap = setting;
+    /// Checks whether the memory tape increases in size when necessary.
+    pub fn is_variable_length(&self) -> bool {
+        return matches!(
+            self.pointer_policy,
+            PointerPolicy::Grow | PointerPolicy::Infinite
+        );
+    }
+
+    /// Sets what happens when the pointer moves past either end of the
+    /// memory tape. By default, the pointer wraps around.
+    pub fn pointer_policy(&mut self, setting: PointerPolicy) -> &mut Self {
+        self.pointer_policy = setting;
+        return self;
+    }
+
+    /// Gets the [`PointerPolicy`] of the [`Memory`].
+    pub fn on_out_of_bounds(&self) -> PointerPolicy {
+        return self.pointer_policy;
+    }
+
+    /// Sets what happens when a memory cell would go past lower_bound or
+    /// upper_bound. By default, the cell wraps around to the other bound.
+    pub fn cell_mode(&mut self, setting: CellMode) -> &mut Self {
+        self.cell_mode = setting;
         return self;
     }
 
+    /// Gets the [`CellMode`] of the [`Memory`].
+    pub fn on_overflow(&self) -> CellMode {
+        return self.cell_mode;
+    }
+
     /// Sets the lower bound of a [`MemoryCell`].
     pub fn lower_bound(&mut self, setting: CellNumber) -> &mut Self {
         self.lower_bound = setting;
@@ -88,12 +143,61 @@ impl MemoryOptions {
         return self.upper_bound+1;
     }
 
+    /// Gets the value which counts as zero in a [`MemoryCell`]. Every cell
+    /// starts at this value, loops end when the current cell is at this
+    /// value and characters are read and written relative to it.
+    /// 
+    /// This is 0 if it is between the lower and upper bounds (or if cells are
+    /// [`CellMode::Unbounded`]), and the lower bound otherwise. For example,
+    /// with bounds of 1 and 256, cells start at 1, `[-]` sets a cell back to
+    /// 1 and a cell holding 66 is output as `A`.
+    pub fn zero(&self) -> CellNumber {
+        let unbounded = self.cell_mode == CellMode::Unbounded;
+        if unbounded || (self.lower_bound <= 0 && 0 <= self.upper_bound) {
+            return 0;
+        }
+        return self.lower_bound;
+    }
+
     /// Sets the initial length of the [`Memory`] tape.
     pub fn initial_length(&mut self, setting: MemoryRange) -> &mut Self {
         self.initial_length = setting;
         return self;
     }
 
+    /// Gets the initial length of the [`Memory`] tape.
+    pub fn length(&self) -> MemoryRange {
+        return self.initial_length;
+    }
+
+    /// Sets what happens to the current cell when there is no more input.
+    /// By default, the cell is left unchanged.
+    pub fn eof_policy(&mut self, setting: EofPolicy) -> &mut Self {
+        self.eof_policy = setting;
+        return self;
+    }
+
+    /// Gets the [`EofPolicy`] of the [`Memory`].
+    pub fn on_eof(&self) -> EofPolicy {
+        return self.eof_policy;
+    }
+
+    /// Sets the cells which are written to the memory tape before the
+    /// program starts. By default, every cell starts at zero.
+    /// 
+    /// If the [`Preload`] goes past the end of the tape, the tape starts out
+    /// long enough to hold it if it can grow, and the options are invalid
+    /// otherwise.
+    pub fn preload(&mut self, setting: Preload) -> &mut Self {
+        self.preload = setting;
+        return self;
+    }
+
+    /// Gets the [`Preload`] of the [`Memory`].
+    pub fn preloaded(&self) -> &Preload {
+        return &self.preload;
+    }
+
     /// Checks if the set of options here is valid.
     pub fn is_valid(&self) -> bool {
         if ((self.upper_bound - self.lower_bound) <= 0 ||
@@ -102,6 +206,16 @@ impl MemoryOptions {
         {
             return false;
         }
+        if self.preload.end() > self.initial_length
+            && !self.is_variable_length()
+        {
+            return false;
+        }
+        if self.cell_mode != CellMode::Unbounded {
+            return self.preload
+                .values(self.zero())
+                .all(|value| self.lowest() <= value && value < self.highest());
+        }
         return true;
     }
 
@@ -127,8 +241,7 @@ impl MemoryOptions {
     /// Assume that the set of options is valid and panics if it is not.
     pub fn assume_and_generate(&self) -> Memory {
         self.validate();
-        let copy = self.clone();
-        return Memory::new(copy);
+        return Memory::new(self.clone());
     }
 }
 
@@ -139,39 +252,81 @@ impl Default for MemoryOptions {
     }
 }
 
-impl Into<Memory> for MemoryOptions {
+impl<T> From<MemoryOptions> for Memory<T>
+where
+    T: CellValue,
+{
     /// Generates a new [`Memory`].
-    fn into(self) -> Memory {
-        return self.assume_and_generate();
+    fn from(options: MemoryOptions) -> Self {
+        return Memory::new(options);
     }
 }
 
 /// Wrapper type around a vector of [`crate::vm::MemoryCell`]s stored on the
-/// heap.
-type Tape = Box<Vec<MemoryCell>>;
+/// heap. The vector is shared with the [`MemorySnapshot`]s taken of it, and
+/// is only copied once one of them changes it.
+pub(crate) type Tape<T> = Rc<Vec<MemoryCell<T>>>;
 
 /// A virtual machine with a memory [`Tape`], a [`MemoryPointer`] and some
 /// [`MemoryOptions`].
+/// 
+/// The [`MemoryPointer`] stores the index of a cell in the [`Tape`], while
+/// `origin` stores the index of the cell the pointer started at. The two
+/// only differ once the tape has grown to the left, and the difference
+/// between them is the logical position of the pointer returned by
+/// [`Memory::pointer`].
+/// 
+/// The cells are stored as `T`, which has to be able to hold every value
+/// between the bounds in the [`MemoryOptions`].
 #[derive(Clone, Debug)]
-pub struct Memory {
-    tape: Tape,
+pub struct Memory<T = CellNumber>
+where
+    T: CellValue,
+{
+    tape: Tape<T>,
     pointer: MemoryPointer,
+    origin: MemoryRange,
     options: MemoryOptions,
 }
 
-impl Memory {
+impl<T> Memory<T>
+where
+    T: CellValue,
+{
     /// Creates a new instance of [`Memory`].
+    /// 
+    /// This panics if the set of options is invalid or if the cells cannot
+    /// be stored as `T`.
     pub fn new(options: MemoryOptions) -> Self {
         options.validate();
-        let tape: Tape = Box::new(Vec::new());
+        if !T::fits(&options) {
+            panic!(
+                "The cells of this set of MemoryOptions do not fit in {}: \
+                {:?}",
+                std::any::type_name::<T>(),
+                options
+            );
+        }
+        let tape: Tape<T> = Rc::default();
         let pointer = MemoryPointer::default();
-        let mut memory = Self {tape, pointer, options};
+        let mut memory = Self {tape, pointer, origin: 0, options};
         memory.init();
         return memory;
     }
 
     /// Same as new but validates the set of options without panicking.
     pub fn with_validation(options: MemoryOptions) -> Result<Self, Error> {
+        if !T::fits(&options) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "The cells of this set of MemoryOptions do not fit in \
+                    {}: {:?}",
+                    std::any::type_name::<T>(),
+                    options
+                )
+            ));
+        }
         return match options.is_valid() {
             false => Err(Error::new(
                 ErrorKind::InvalidData,
@@ -183,108 +338,358 @@ impl Memory {
 
     /// Initializes the memory tape.
     fn init(&mut self) {
-        self.tape.resize(
-            self.options.initial_length,
-            MemoryCell::new(self.options.lowest())
+        let preload = &self.options.preload;
+        let tape = Rc::make_mut(&mut self.tape);
+        tape.resize(
+            self.options.initial_length.max(preload.end()),
+            MemoryCell::new(self.options.zero())
         );
+        let values = preload.values(self.options.zero());
+        for (cell, value) in tape[preload.offset()..].iter_mut().zip(values) {
+            *cell = MemoryCell::new(value);
+        }
         self.pointer.to_zero();
+        self.origin = 0;
     }
 
-    /// Flattens the value of all cells in the tape to the lower bound defined
-    /// in `options`.
+    /// Flattens the value of all cells in the tape to the zero defined by
+    /// `options` (see [`MemoryOptions::zero`]).
     pub fn flatten(&mut self) {
-        for cell in &mut self.tape.iter_mut() {
-            cell.flatten(self.options.lowest());
+        for cell in Rc::make_mut(&mut self.tape).iter_mut() {
+            cell.flatten(self.options.zero());
         }
     }
 
-    /// Get the index the pointer is pointing at.
-    pub fn pointer(&self) -> MemoryRange {
+    /// Get the position of the cell the pointer is pointing at, relative to
+    /// the cell it started at. This is negative if the pointer has moved to
+    /// the left of where it started.
+    pub fn pointer(&self) -> isize {
+        return self.position(self.index());
+    }
+
+    /// Get the index in [`Memory::cells`] of the cell the pointer is
+    /// pointing at.
+    pub fn index(&self) -> MemoryRange {
         return self.pointer.pointer();
     }
 
-    /// Get a copy of the [`MemoryCell`] indexed by the pointer.
-    pub fn get(&self) -> Option<MemoryCell> {
-        return match self.tape.get(self.pointer()) {
-            Some(mc) => Some(mc.clone()),
-            None => None,
+    /// Get the index in [`Memory::cells`] of the cell the pointer started
+    /// at.
+    pub fn origin(&self) -> MemoryRange {
+        return self.origin;
+    }
+
+    /// Converts the index of a cell in the tape into its position relative
+    /// to the cell the pointer started at.
+    fn position(&self, index: MemoryRange) -> isize {
+        return index as isize - self.origin as isize;
+    }
+
+    /// Works out which cell is `amount` cells away from the cell at `index`
+    /// according to the [`PointerPolicy`], and returns its index.
+    /// 
+    /// If the tape has to grow to the left, the cells already in it move to
+    /// the right and the pointer and origin move with them, so that they
+    /// keep pointing at the same cells. With [`PointerPolicy::Infinite`],
+    /// the tape at least doubles in length each time, so that this does not
+    /// happen every time the pointer moves left.
+    fn target(
+        &mut self,
+        index: MemoryRange,
+        amount: isize
+    ) -> Result<MemoryRange, ErrEvent> {
+        let length = self.tape.len();
+        let target = index as i128 + amount as i128;
+        if 0 <= target && target < length as i128 {
+            return Ok(target as MemoryRange);
+        }
+        let zero = MemoryCell::new(self.options.zero());
+        return match self.options.on_out_of_bounds() {
+            PointerPolicy::Wrap => {
+                Ok(target.rem_euclid(length as i128) as MemoryRange)
+            },
+            PointerPolicy::Grow | PointerPolicy::Infinite if target < 0 => {
+                let needed = target.unsigned_abs() as MemoryRange;
+                let extra = match self.options.on_out_of_bounds() {
+                    PointerPolicy::Infinite => needed.max(length),
+                    _ => needed,
+                };
+                Rc::make_mut(&mut self.tape)
+                    .splice(0..0, iter::repeat_n(zero, extra));
+                self.pointer.to(self.index() + extra);
+                self.origin += extra;
+                Ok(extra - needed)
+            },
+            PointerPolicy::Grow | PointerPolicy::Infinite => {
+                Rc::make_mut(&mut self.tape)
+                    .resize(target as MemoryRange + 1, zero);
+                Ok(target as MemoryRange)
+            },
+            PointerPolicy::Error => Err(ErrEvent::Error(BfError::new(
+                BfErrorKind::OutOfBounds,
+                format!(
+                    "Pointer would move from cell {} to cell {}, outside \
+                    of the memory tape of {} cells.",
+                    self.position(index),
+                    self.position(index) as i128 + amount as i128,
+                    length
+                )
+            ).with_cell(self.position(index)))),
         };
     }
+
+    /// Get a copy of the [`MemoryCell`] indexed by the pointer.
+    pub fn get(&self) -> Option<MemoryCell<T>> {
+        return self.tape.get(self.index()).cloned();
+    }
+
+    /// Gets the [`MemoryOptions`] the [`Memory`] was created with.
+    pub fn options(&self) -> &MemoryOptions {
+        return &self.options;
+    }
+
+    /// Gets the cells in the memory tape.
+    pub fn cells(&self) -> &[MemoryCell<T>] {
+        return &self.tape;
+    }
+
+    /// Takes a [`MemoryDump`] of the cells from position `start` up to (but
+    /// not including) position `end`. Positions outside of the tape are
+    /// left out.
+    pub fn dump(&self, start: isize, end: isize) -> MemoryDump<T> {
+        let origin = self.origin as isize;
+        let length = self.tape.len() as isize;
+        let first = start.saturating_add(origin).clamp(0, length);
+        let last = end.saturating_add(origin).clamp(first, length);
+        return MemoryDump::new(
+            first - origin,
+            self.tape[first as MemoryRange..last as MemoryRange].to_vec(),
+            self.pointer(),
+            self.options.zero()
+        );
+    }
+
+    /// Takes a [`MemoryDump`] of the cells which are at most `radius` cells
+    /// away from the pointer.
+    pub fn window(&self, radius: usize) -> MemoryDump<T> {
+        let pointer = self.pointer();
+        let radius = radius.min(isize::MAX as usize) as isize;
+        return self.dump(
+            pointer.saturating_sub(radius),
+            pointer.saturating_add(radius).saturating_add(1)
+        );
+    }
+
+    /// Takes a [`MemoryDump`] of the cells from the first to the last one
+    /// which is not zero, including the cell the pointer is pointing at.
+    pub fn used(&self) -> MemoryDump<T> {
+        let zero = self.options.zero();
+        let index = self.index();
+        let first = self.tape
+            .iter()
+            .position(|cell| !cell.is_zero(zero))
+            .map_or(index, |first| first.min(index));
+        let last = self.tape
+            .iter()
+            .rposition(|cell| !cell.is_zero(zero))
+            .map_or(index, |last| last.max(index));
+        return self.dump(self.position(first), self.position(last) + 1);
+    }
+
+    /// Takes a [`MemorySnapshot`] of the tape, the pointer and the options,
+    /// which can be restored later with [`Memory::restore`].
+    /// 
+    /// The snapshot shares the tape with the [`Memory`] instead of copying
+    /// it, so the tape is only copied once the program changes a cell.
+    pub fn snapshot(&self) -> MemorySnapshot<T> {
+        return MemorySnapshot::new(
+            Rc::clone(&self.tape),
+            self.index(),
+            self.origin,
+            self.options.clone()
+        );
+    }
+
+    /// Restores the tape, the pointer and the options to what they were
+    /// when `snapshot` was taken. Like [`Memory::snapshot`], this shares
+    /// the tape with the snapshot instead of copying it.
+    pub fn restore(&mut self, snapshot: &MemorySnapshot<T>) {
+        self.tape = Rc::clone(snapshot.tape());
+        self.pointer.to(snapshot.index());
+        self.origin = snapshot.origin();
+        self.options = snapshot.options().clone();
+    }
+
+    /// Gets a mutable reference to the cells in the memory tape, so that
+    /// another engine can write its results back into the tape.
+    #[cfg(feature = "jit")]
+    pub(crate) fn cells_mut(&mut self) -> &mut Vec<MemoryCell<T>> {
+        return Rc::make_mut(&mut self.tape);
+    }
+
+    /// Points the pointer at `index`.
+    #[cfg(feature = "jit")]
+    pub(crate) fn move_to(&mut self, index: MemoryRange) {
+        self.pointer.to(index);
+    }
 }
 
-impl CommandRunner for Memory {
+impl<T> CommandRunner for Memory<T>
+where
+    T: CellValue,
+{
     fn increment(&mut self) -> Event {
-        let pointer = self.pointer();
-        let cell = self.tape
+        let pointer = self.index();
+        let cell = Rc::make_mut(&mut self.tape)
             .get_mut(pointer);
         let cell = unwrapcell!(cell);
-        let _ = cell.increment(self.options.lowest(), self.options.highest());
+        cell.increment(
+            self.options.lowest(),
+            self.options.highest(),
+            self.options.on_overflow()
+        ).map_err(|error| error.with_cell(self.position(pointer)))?;
         return Ok(OkEvent::Status(Status::new("Increment successful")));
     }
 
     fn decrement(&mut self) -> Event {
-        let pointer = self.pointer();
-        let cell = self.tape
+        let pointer = self.index();
+        let cell = Rc::make_mut(&mut self.tape)
             .get_mut(pointer);
         let cell = unwrapcell!(cell);
-        let _ = cell.decrement(self.options.lowest(), self.options.highest());
+        cell.decrement(
+            self.options.lowest(),
+            self.options.highest(),
+            self.options.on_overflow()
+        ).map_err(|error| error.with_cell(self.position(pointer)))?;
         return Ok(OkEvent::Status(Status::new("Decrement successful")));
     }
 
-    fn next(&mut self) -> Event {
-        let resize = self.pointer
-            .increment(self.tape.len(), !self.options.variable_length);
-        if resize {
-            self.tape.push(MemoryCell::new(self.options.lowest()));
+    fn add(&mut self, amount: CellNumber) -> Event {
+        let pointer = self.index();
+        let cell = Rc::make_mut(&mut self.tape)
+            .get_mut(pointer);
+        let cell = unwrapcell!(cell);
+        return cell.add(
+            amount,
+            self.options.lowest(),
+            self.options.highest(),
+            self.options.on_overflow()
+        ).map_err(|error| error.with_cell(self.position(pointer)));
+    }
+
+    fn shift(&mut self, amount: isize) -> Event {
+        let target = self.target(self.index(), amount)?;
+        self.pointer.to(target);
+        return Ok(OkEvent::Status(Status::new("Shift pointer: OK")));
+    }
+
+    fn set_zero(&mut self) -> Event {
+        let pointer = self.index();
+        let cell = Rc::make_mut(&mut self.tape)
+            .get_mut(pointer);
+        let cell = unwrapcell!(cell);
+        cell.flatten(self.options.zero());
+        return Ok(OkEvent::Status(Status::new("Set zero: OK")));
+    }
+
+    fn multiply_add(&mut self, offset: isize, factor: CellNumber) -> Event {
+        let source = unwrapcell!(self.get());
+        if source.is_zero(self.options.zero()) {
+            return Ok(OkEvent::Status(Status::new("Multiply add: OK")));
         }
+        let target = self.target(self.index(), offset)?;
+        let cell = Rc::make_mut(&mut self.tape)
+            .get_mut(target);
+        let cell = unwrapcell!(cell);
+        return cell.multiply_add(
+            &source,
+            factor,
+            self.options.zero(),
+            self.options.lowest(),
+            self.options.highest(),
+            self.options.on_overflow()
+        ).map_err(|error| error.with_cell(self.position(target)));
+    }
+
+    fn scan(&mut self, step: isize) -> Event {
+        let zero = self.options.zero();
+        while !unwrapcell!(self.get()).is_zero(zero) {
+            self.shift(step)?;
+        }
+        return Ok(OkEvent::Status(Status::new("Scan: OK")));
+    }
+
+    fn next(&mut self) -> Event {
+        let target = self.target(self.index(), 1)?;
+        self.pointer.to(target);
         return Ok(OkEvent::Status(Status::new(
             "Successfully moved pointer to the next cell."
         )));
     }
 
     fn previous(&mut self) -> Event {
-        self.pointer
-            .decrement(self.tape.len());
+        let target = self.target(self.index(), -1)?;
+        self.pointer.to(target);
         return Ok(OkEvent::Status(Status::new(
             "Successfully moved pointer to the previous cell."
         )));
     }
 
     fn read_out(&self, output: &mut char) -> Event {
-        let pointer = self.pointer();
+        let pointer = self.index();
         let cell = self.tape
             .get(pointer);
         let cell = unwrapcell!(cell);
-        return cell.to_char(output);
+        return cell.to_char(self.options.zero(), output);
     }
 
     fn write_in(&mut self, input: char) -> Event {
-        let pointer = self.pointer();
-        let cell = self.tape
+        let pointer = self.index();
+        let cell = Rc::make_mut(&mut self.tape)
             .get_mut(pointer);
         let cell = unwrapcell!(cell);
         return cell.from_char(
             input,
+            self.options.zero(),
             self.options.lowest(),
-            self.options.highest()
-        );
+            self.options.highest(),
+            self.options.on_overflow()
+        ).map_err(|error| error.with_cell(self.position(pointer)));
+    }
+
+    fn end_of_input(&mut self) -> Event {
+        let pointer = self.index();
+        let cell = Rc::make_mut(&mut self.tape)
+            .get_mut(pointer);
+        let cell = unwrapcell!(cell);
+        match self.options.on_eof() {
+            EofPolicy::Unchanged => (),
+            EofPolicy::Zero => cell.flatten(self.options.zero()),
+            EofPolicy::Max => cell.maximize(self.options.highest()),
+        }
+        return Ok(OkEvent::Status(Status::new(
+            format!("End of input ({}): OK", self.options.on_eof())
+        )));
     }
 
     fn is_zero(&self, output: &mut bool) -> Event {
-        let pointer = self.pointer();
+        let pointer = self.index();
         let cell = self.tape
             .get(pointer);
         let cell = unwrapcell!(cell);
-        *output = cell.number() == 0;
+        *output = cell.is_zero(self.options.zero());
         return Ok(OkEvent::Status(Status::new(
             "Successfully moved pointer to the previous cell."
         )));
     }
 }
 
-impl From<&MemoryOptions> for Memory {
+impl<T> From<&MemoryOptions> for Memory<T>
+where
+    T: CellValue,
+{
     /// Creates a [`Memory`] instance from [`MemoryOptions`].
     fn from(options: &MemoryOptions) -> Self {
-        return options.assume_and_generate();
+        return Memory::new(options.clone());
     }
 }
\ No newline at end of file
@@ -3,15 +3,32 @@
 //! This module provides definition for a Brainfuck virtual machine. Amongst
 //! the implementations defined here is a [`memory::Memory`], which allows you
 //! to store memory in a Brainfuck program. You can customise it using
-//! [`memory::MemoryOptions`] as well.
+//! [`memory::MemoryOptions`] as well, or use a [`sparse::SparseMemory`] for
+//! tapes which are too long to allocate. The state of a [`memory::Memory`]
+//! can be saved and restored with a [`snapshot::MemorySnapshot`], and its
+//! cells can be inspected with a [`dump::MemoryDump`].
 
 pub mod cell;
+pub mod dump;
 pub mod macros;
 pub mod memory;
 pub mod pointer;
+pub mod policy;
+pub mod preload;
+pub mod snapshot;
+pub mod sparse;
+pub mod value;
 
 pub use self::{
     cell::{CellNumber, default_lower, default_upper, MemoryCell},
+    dump::{DumpFormat, MemoryDump},
     memory::{Memory, MemoryOptions},
-    pointer::{MemoryRange, default_range, MemoryPointer}
-};
\ No newline at end of file
+    pointer::{MemoryRange, default_range, MemoryPointer},
+    policy::{CellMode, EofPolicy, PointerPolicy},
+    preload::Preload,
+    snapshot::MemorySnapshot,
+    sparse::SparseMemory,
+    value::{CellType, CellValue},
+};
+#[cfg(feature = "bignum")]
+pub use self::cell::BigCell;
\ No newline at end of file
@@ -70,21 +70,60 @@ impl MemoryPointer {
     /// the tape when necessary. This is so that the tape does not have to
     /// work with negative indices.
     pub fn decrement(&mut self, highest: MemoryRange) {
-        if highest <= 0 {
+        if highest == 0 {
             panic!("MemoryPointer::decrement: highest cannot be 0 or below.");
         }
         let pointer = self.pointer();
-        if pointer <= 0 || pointer >= highest {
+        if pointer == 0 || pointer >= highest {
             *self.pointer_mut() = highest - 1;
         } else {
             *self.pointer_mut() -= 1;
         }
     }
 
+    /// Moves the pointer by `amount` cells, which has the same effect as
+    /// calling [`MemoryPointer::increment`] or [`MemoryPointer::decrement`]
+    /// `amount` times.
+    /// 
+    /// If the pointer moves past the end of the tape and `wrap` is `false`,
+    /// the new length the tape needs to grow to is returned.
+    pub fn shift(
+        &mut self,
+        amount: isize,
+        highest: MemoryRange,
+        wrap: bool,
+    ) -> Option<MemoryRange> {
+        if highest == 0 {
+            panic!("MemoryPointer::shift: highest cannot be 0.");
+        }
+        let pointer = self.pointer();
+        if amount >= 0 {
+            let target = pointer + amount as MemoryRange;
+            if target < highest {
+                *self.pointer_mut() = target;
+            } else if wrap {
+                *self.pointer_mut() = target % highest;
+            } else {
+                *self.pointer_mut() = target;
+                return Some(target + 1);
+            }
+        } else {
+            let back = amount.unsigned_abs() % highest;
+            let pointer = pointer.min(highest - 1);
+            *self.pointer_mut() = (pointer + highest - back) % highest;
+        }
+        return None;
+    }
+
     /// Resets the pointer to 0.
     pub fn to_zero(&mut self) {
         *self.pointer_mut() = 0;
     }
+
+    /// Points the pointer at `index`.
+    pub fn to(&mut self, index: MemoryRange) {
+        *self.pointer_mut() = index;
+    }
 }
 
 impl Default for MemoryPointer {
@@ -0,0 +1,171 @@
+//! [`brainfucklib::vm::policy`]
+//! 
+//! This module defines policies which decide how a Brainfuck virtual machine
+//! behaves in situations the language itself leaves undefined. These can be
+//! set through [`crate::vm::MemoryOptions`].
+
+use std::{fmt, str::FromStr};
+
+use crate::debug::{BfError, BfErrorKind};
+
+/// What to store in the current cell when `,` is run but there is no more
+/// input left to read.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EofPolicy {
+    /// Leave the cell as it is.
+    Unchanged,
+    /// Set the cell to zero, or to the lower bound of the virtual machine if
+    /// zero is not between its bounds (see
+    /// [`crate::vm::MemoryOptions::zero`]).
+    Zero,
+    /// Set the cell to the upper bound of the virtual machine, which is the
+    /// same as -1 for wrapping 8-bit cells.
+    Max,
+}
+
+impl Default for EofPolicy {
+    /// Leaves the cell unchanged by default.
+    fn default() -> Self {
+        return EofPolicy::Unchanged;
+    }
+}
+
+impl FromStr for EofPolicy {
+    type Err = BfError;
+
+    /// Parses "unchanged", "zero" or "max" (or "-1") into an [`EofPolicy`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Ok(match s {
+            "unchanged" => EofPolicy::Unchanged,
+            "zero" | "0" => EofPolicy::Zero,
+            "max" | "-1" => EofPolicy::Max,
+            _ => return Err(BfError::new(
+                BfErrorKind::Other,
+                format!("{} is not a valid end of input policy.", s)
+            )),
+        });
+    }
+}
+
+impl fmt::Display for EofPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", match self {
+            EofPolicy::Unchanged => "unchanged",
+            EofPolicy::Zero => "zero",
+            EofPolicy::Max => "max",
+        });
+    }
+}
+
+/// What happens when arithmetic would take a cell past its lower or upper
+/// bound.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CellMode {
+    /// Wrap around to the other bound, like an unsigned integer.
+    Wrap,
+    /// Stay at the bound which would have been crossed.
+    Saturate,
+    /// Leave the cell as it is and raise a
+    /// [`BfErrorKind::CellOverflow`] error.
+    Error,
+    /// Ignore the bounds and let the cell hold any integer at all. Cells
+    /// always start at 0, and only a cell type which can hold arbitrarily
+    /// large numbers (such as the one enabled by the `bignum` feature) can
+    /// be used.
+    Unbounded,
+}
+
+impl Default for CellMode {
+    /// Cells wrap around by default.
+    fn default() -> Self {
+        return CellMode::Wrap;
+    }
+}
+
+impl FromStr for CellMode {
+    type Err = BfError;
+
+    /// Parses "wrap", "saturate", "error" or "unbounded" into a
+    /// [`CellMode`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Ok(match s {
+            "wrap" => CellMode::Wrap,
+            "saturate" => CellMode::Saturate,
+            "error" => CellMode::Error,
+            "unbounded" => CellMode::Unbounded,
+            _ => return Err(BfError::new(
+                BfErrorKind::Other,
+                format!("{} is not a valid cell mode.", s)
+            )),
+        });
+    }
+}
+
+impl fmt::Display for CellMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", match self {
+            CellMode::Wrap => "wrap",
+            CellMode::Saturate => "saturate",
+            CellMode::Error => "error",
+            CellMode::Unbounded => "unbounded",
+        });
+    }
+}
+
+/// What happens when the pointer is moved past either end of the memory
+/// tape.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PointerPolicy {
+    /// Wrap around to the other end of the tape.
+    Wrap,
+    /// Add cells to that end of the tape, so that the pointer can keep
+    /// moving in either direction.
+    Grow,
+    /// Treat the tape as if it had no ends. Like [`PointerPolicy::Grow`],
+    /// cells are added to whichever end the pointer moves past, but the
+    /// tape grows to the left in larger and larger steps so that programs
+    /// which keep moving left stay fast. Cells are then addressed by their
+    /// position relative to the cell the pointer started at, which can be
+    /// negative.
+    Infinite,
+    /// Leave the pointer where it is and raise a
+    /// [`BfErrorKind::OutOfBounds`] error.
+    Error,
+}
+
+impl Default for PointerPolicy {
+    /// The pointer wraps around by default.
+    fn default() -> Self {
+        return PointerPolicy::Wrap;
+    }
+}
+
+impl FromStr for PointerPolicy {
+    type Err = BfError;
+
+    /// Parses "wrap", "grow", "infinite" or "error" into a
+    /// [`PointerPolicy`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Ok(match s {
+            "wrap" => PointerPolicy::Wrap,
+            "grow" => PointerPolicy::Grow,
+            "infinite" => PointerPolicy::Infinite,
+            "error" => PointerPolicy::Error,
+            _ => return Err(BfError::new(
+                BfErrorKind::Other,
+                format!("{} is not a valid pointer policy.", s)
+            )),
+        });
+    }
+}
+
+impl fmt::Display for PointerPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", match self {
+            PointerPolicy::Wrap => "wrap",
+            PointerPolicy::Grow => "grow",
+            PointerPolicy::Infinite => "infinite",
+            PointerPolicy::Error => "error",
+        });
+    }
+}
@@ -0,0 +1,156 @@
+//! [`brainfucklib::vm::preload`]
+//! 
+//! This module defines [`Preload`], a list of cells which are written to the
+//! memory tape before a program starts, so that the program can start with
+//! data already on the tape instead of reading it with `,`.
+//! 
+//! A [`Preload`] is given to a virtual machine with
+//! [`crate::vm::MemoryOptions::preload`].
+
+use std::{fs, io, path::Path};
+
+use crate::debug::{BfError, BfErrorKind};
+use super::{CellNumber, MemoryRange};
+
+/// Cells which are written to the memory tape before a program starts,
+/// from the cell at `offset` onwards.
+/// 
+/// Cells made from numbers are stored as they are, while cells made from
+/// bytes or text are stored relative to [`crate::vm::MemoryOptions::zero`],
+/// like `,` stores them. Unlike `,`, cells which do not fit between the
+/// bounds of a cell make the [`crate::vm::MemoryOptions`] invalid instead of
+/// wrapping around.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Preload {
+    offset: MemoryRange,
+    cells: Vec<CellNumber>,
+    relative: bool,
+}
+
+impl Preload {
+    /// Creates a [`Preload`] which stores each of `numbers` as it is.
+    pub fn numbers<I>(numbers: I) -> Self
+    where
+        I: IntoIterator<Item = CellNumber>
+    {
+        return Self {
+            offset: 0,
+            cells: numbers.into_iter().collect(),
+            relative: false,
+        };
+    }
+
+    /// Creates a [`Preload`] which stores each of `bytes` relative to zero.
+    pub fn bytes<B>(bytes: B) -> Self
+    where
+        B: AsRef<[u8]>
+    {
+        return Self {
+            offset: 0,
+            cells: bytes.as_ref().iter().map(|&b| b as CellNumber).collect(),
+            relative: true,
+        };
+    }
+
+    /// Creates a [`Preload`] which stores the code point of each character
+    /// in `text` relative to zero.
+    pub fn text<S>(text: S) -> Self
+    where
+        S: AsRef<str>
+    {
+        return Self {
+            offset: 0,
+            cells: text.as_ref().chars().map(|c| c as CellNumber).collect(),
+            relative: true,
+        };
+    }
+
+    /// Creates a [`Preload`] which stores every byte of the file at `path`
+    /// relative to zero.
+    pub fn from_file<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>
+    {
+        return Ok(Self::bytes(fs::read(path)?));
+    }
+
+    /// Parses a list of numbers separated by commas or whitespace (such as
+    /// `72, 105, -1`) into a [`Preload`] which stores each of them as it is.
+    pub fn parse_numbers<S>(numbers: S) -> Result<Self, BfError>
+    where
+        S: AsRef<str>
+    {
+        let mut cells = Vec::new();
+        let tokens = numbers.as_ref()
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty());
+        for token in tokens {
+            match token.parse() {
+                Ok(number) => cells.push(number),
+                Err(_) => return Err(BfError::new(
+                    BfErrorKind::Other,
+                    format!("{} is not a valid cell to preload.", token)
+                )),
+            }
+        }
+        return Ok(Self::numbers(cells));
+    }
+
+    /// Moves the cells so that the first one is written to the cell at
+    /// `offset` instead of the cell the pointer starts at.
+    pub fn at(mut self, offset: MemoryRange) -> Self {
+        self.offset = offset;
+        return self;
+    }
+
+    /// Gets the index of the cell the first cell is written to.
+    pub fn offset(&self) -> MemoryRange {
+        return self.offset;
+    }
+
+    /// Gets the index after the cell the last cell is written to.
+    pub fn end(&self) -> MemoryRange {
+        return self.offset + self.cells.len();
+    }
+
+    /// Gets the number of cells.
+    pub fn len(&self) -> usize {
+        return self.cells.len();
+    }
+
+    /// Checks whether there are no cells to write.
+    pub fn is_empty(&self) -> bool {
+        return self.cells.is_empty();
+    }
+
+    /// Gets the cells as they were given, before bytes and text are moved
+    /// relative to zero.
+    pub(crate) fn cells(&self) -> &[CellNumber] {
+        return &self.cells;
+    }
+
+    /// Checks whether the cells are stored relative to zero, like bytes and
+    /// text.
+    pub(crate) fn is_relative(&self) -> bool {
+        return self.relative;
+    }
+
+    /// Creates a [`Preload`] of `cells`, which are stored relative to zero if
+    /// `relative` is true.
+    pub(crate) fn from_cells(cells: Vec<CellNumber>, relative: bool) -> Self {
+        return Self {offset: 0, cells, relative};
+    }
+
+    /// Gets the values of the cells, where bytes and text are stored
+    /// relative to `zero`.
+    pub fn values(
+        &self,
+        zero: CellNumber
+    ) -> impl Iterator<Item = CellNumber> + '_ {
+        let base = match self.relative {
+            true => zero,
+            false => 0,
+        };
+        return self.cells.iter().map(move |&cell| base.saturating_add(cell));
+    }
+}
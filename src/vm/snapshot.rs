@@ -0,0 +1,372 @@
+//! [`brainfucklib::vm::snapshot`]
+//! 
+//! This module defines [`MemorySnapshot`], a copy of the state of a
+//! [`crate::vm::Memory`] which can be restored later or saved to a file.
+//! 
+//! Snapshots are saved as plain text. The first line is a version header,
+//! followed by one line for each option and for the position of the
+//! pointer, and then every cell in the tape:
+//! 
+//! ```text
+//! brainfuck-snapshot 1
+//! pointer-policy wrap
+//! cell-mode wrap
+//! lower-bound 0
+//! upper-bound 255
+//! length 30000
+//! eof-policy unchanged
+//! index 2
+//! origin 0
+//! program-pointer 17
+//! cells 30000
+//! 72 105 0*29998
+//! ```
+//! 
+//! A cell written as `value*count` stands for `count` cells in a row which
+//! hold `value`, which keeps the files of mostly empty tapes small. The
+//! `program-pointer` line is only there for snapshots taken from a
+//! [`crate::code::BfRunner`].
+//! 
+//! If the memory was preloaded (see [`crate::vm::Preload`]), there is also
+//! a `preload` line before the `index` line. It holds the offset of the
+//! first preloaded cell, `numbers` or `text` (for cells which are stored
+//! relative to zero, like bytes and text) and the preloaded cells, such as
+//! `preload 0 text 72 105`.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Error, ErrorKind, Read, Write},
+    path::Path,
+    rc::Rc,
+    str::FromStr,
+};
+
+use super::{
+    memory::Tape,
+    CellNumber,
+    CellValue,
+    MemoryCell,
+    MemoryOptions,
+    MemoryRange,
+    Preload,
+};
+
+/// The header on the first line of every snapshot file.
+const HEADER: &str = "brainfuck-snapshot 1";
+
+/// A copy of the tape, pointer and options of a [`crate::vm::Memory`] at
+/// some point in time, taken with [`crate::vm::Memory::snapshot`].
+/// 
+/// A snapshot shares its tape with the [`crate::vm::Memory`] it was taken
+/// of, so taking one and restoring it with [`crate::vm::Memory::restore`]
+/// do not copy any cells. The tape is copied the first time the memory
+/// changes a cell while a snapshot still shares it, which leaves the
+/// snapshot as it was, so the same snapshot can be restored as many times
+/// as you like.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemorySnapshot<T = CellNumber>
+where
+    T: CellValue,
+{
+    cells: Tape<T>,
+    index: MemoryRange,
+    origin: MemoryRange,
+    options: MemoryOptions,
+    program_pointer: Option<usize>,
+}
+
+impl<T> MemorySnapshot<T>
+where
+    T: CellValue,
+{
+    /// Creates a [`MemorySnapshot`] of a tape whose pointer is at `index`
+    /// and started at `origin`.
+    pub(crate) fn new(
+        cells: Tape<T>,
+        index: MemoryRange,
+        origin: MemoryRange,
+        options: MemoryOptions,
+    ) -> Self {
+        return Self {cells, index, origin, options, program_pointer: None};
+    }
+
+    /// Gets the cells in the memory tape.
+    pub fn cells(&self) -> &[MemoryCell<T>] {
+        return &self.cells;
+    }
+
+    /// Gets the tape, so that a [`crate::vm::Memory`] can share it.
+    pub(crate) fn tape(&self) -> &Tape<T> {
+        return &self.cells;
+    }
+
+    /// Gets the index of the cell the pointer was pointing at.
+    pub fn index(&self) -> MemoryRange {
+        return self.index;
+    }
+
+    /// Gets the index of the cell the pointer started at.
+    pub fn origin(&self) -> MemoryRange {
+        return self.origin;
+    }
+
+    /// Get the position of the cell the pointer was pointing at, relative
+    /// to the cell it started at.
+    pub fn pointer(&self) -> isize {
+        return self.index as isize - self.origin as isize;
+    }
+
+    /// Gets the [`MemoryOptions`] of the memory the snapshot was taken of.
+    pub fn options(&self) -> &MemoryOptions {
+        return &self.options;
+    }
+
+    /// Gets the position of the next command to run, if the snapshot was
+    /// taken from a [`crate::code::BfRunner`].
+    pub fn program_pointer(&self) -> Option<usize> {
+        return self.program_pointer;
+    }
+
+    /// Sets the position of the next command to run.
+    pub fn with_program_pointer(mut self, program_pointer: usize) -> Self {
+        self.program_pointer = Some(program_pointer);
+        return self;
+    }
+
+    /// Writes the snapshot into `output` in the format described in
+    /// [`crate::vm::snapshot`].
+    pub fn write_to<W>(&self, output: &mut W) -> io::Result<()>
+    where
+        W: Write
+    {
+        let options = &self.options;
+        writeln!(output, "{}", HEADER)?;
+        writeln!(output, "pointer-policy {}", options.on_out_of_bounds())?;
+        writeln!(output, "cell-mode {}", options.on_overflow())?;
+        writeln!(output, "lower-bound {}", options.lowest())?;
+        writeln!(output, "upper-bound {}", options.highest() - 1)?;
+        writeln!(output, "length {}", options.length())?;
+        writeln!(output, "eof-policy {}", options.on_eof())?;
+        let preload = options.preloaded();
+        if !preload.is_empty() {
+            let kind = match preload.is_relative() {
+                true => "text",
+                false => "numbers",
+            };
+            write!(output, "preload {} {}", preload.offset(), kind)?;
+            for cell in preload.cells() {
+                write!(output, " {}", cell)?;
+            }
+            writeln!(output)?;
+        }
+        writeln!(output, "index {}", self.index)?;
+        writeln!(output, "origin {}", self.origin)?;
+        if let Some(program_pointer) = self.program_pointer {
+            writeln!(output, "program-pointer {}", program_pointer)?;
+        }
+        writeln!(output, "cells {}", self.cells.len())?;
+        let mut runs = self.cells.iter().peekable();
+        let mut first = true;
+        while let Some(cell) = runs.next() {
+            let mut count = 1;
+            while runs.peek() == Some(&cell) {
+                runs.next();
+                count += 1;
+            }
+            if !first {
+                write!(output, " ")?;
+            }
+            first = false;
+            match count {
+                1 => write!(output, "{}", cell.value())?,
+                _ => write!(output, "{}*{}", cell.value(), count)?,
+            }
+        }
+        writeln!(output)?;
+        return output.flush();
+    }
+
+    /// Saves the snapshot to the file at `path`, which is created if it
+    /// does not exist and truncated if it does.
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>
+    {
+        let mut file = BufWriter::new(File::create(path)?);
+        return self.write_to(&mut file);
+    }
+
+    /// Reads a snapshot written by [`MemorySnapshot::write_to`] from
+    /// `input`.
+    /// 
+    /// An error of kind [`ErrorKind::InvalidData`] is returned if the
+    /// snapshot is malformed, if its options are invalid or if its cells
+    /// cannot be stored as `T`.
+    pub fn read_from<R>(input: &mut R) -> io::Result<Self>
+    where
+        R: Read
+    {
+        let mut text = String::new();
+        input.read_to_string(&mut text)?;
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some(HEADER) {
+            return Err(invalid(format!(
+                "A snapshot has to start with `{}`.",
+                HEADER
+            )));
+        }
+        let mut options = MemoryOptions::new();
+        let mut index = 0;
+        let mut origin = 0;
+        let mut program_pointer = None;
+        let mut length = None;
+        for line in &mut lines {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = match line.split_once(' ') {
+                Some((key, value)) => (key, value.trim()),
+                None => return Err(invalid(format!(
+                    "`{}` is not a `key value` pair.",
+                    line
+                ))),
+            };
+            match key {
+                "pointer-policy" => {
+                    options.pointer_policy(parse(key, value)?);
+                },
+                "cell-mode" => {
+                    options.cell_mode(parse(key, value)?);
+                },
+                "lower-bound" => {
+                    options.lower_bound(parse(key, value)?);
+                },
+                "upper-bound" => {
+                    options.upper_bound(parse(key, value)?);
+                },
+                "length" => {
+                    options.initial_length(parse(key, value)?);
+                },
+                "eof-policy" => {
+                    options.eof_policy(parse(key, value)?);
+                },
+                "preload" => {
+                    options.preload(parse_preload(value)?);
+                },
+                "index" => index = parse(key, value)?,
+                "origin" => origin = parse(key, value)?,
+                "program-pointer" => {
+                    program_pointer = Some(parse(key, value)?);
+                },
+                "cells" => {
+                    length = Some(parse::<usize>(key, value)?);
+                    break;
+                },
+                _ => return Err(invalid(format!(
+                    "`{}` is not a field of a snapshot.",
+                    key
+                ))),
+            }
+        }
+        let length = match length {
+            Some(length) => length,
+            None => return Err(invalid("A snapshot has no `cells` line.")),
+        };
+        if !options.is_valid() || !T::fits(&options) {
+            return Err(invalid(format!(
+                "The cells of a snapshot with these options cannot be \
+                stored as {}: {:?}",
+                std::any::type_name::<T>(),
+                options
+            )));
+        }
+        // The header is not trusted to reserve memory, as it could ask for
+        // far more cells than the file holds.
+        let mut cells = Vec::new();
+        for token in lines.flat_map(str::split_whitespace) {
+            let (value, count) = match token.split_once('*') {
+                Some((value, count)) => (value, parse("count", count)?),
+                None => (token, 1),
+            };
+            let value: T = parse("cell", value)?;
+            if count > length - cells.len() {
+                return Err(invalid(format!(
+                    "A snapshot has more than the {} cells it should have.",
+                    length
+                )));
+            }
+            cells.extend(
+                std::iter::repeat_n(MemoryCell::from_value(value), count)
+            );
+        }
+        if cells.len() != length {
+            return Err(invalid(format!(
+                "A snapshot has {} cells instead of {}.",
+                cells.len(),
+                length
+            )));
+        }
+        if index >= length || origin > length {
+            return Err(invalid(format!(
+                "The pointer of a snapshot is outside of its {} cells.",
+                length
+            )));
+        }
+        return Ok(Self {
+            cells: Rc::new(cells),
+            index,
+            origin,
+            options,
+            program_pointer,
+        });
+    }
+
+    /// Loads a snapshot saved with [`MemorySnapshot::save`] from the file
+    /// at `path`.
+    pub fn load<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>
+    {
+        let mut file = File::open(path)?;
+        return Self::read_from(&mut file);
+    }
+}
+
+/// Creates an [`Error`] of kind [`ErrorKind::InvalidData`].
+fn invalid<S>(message: S) -> Error
+where
+    S: Into<String>
+{
+    return Error::new(ErrorKind::InvalidData, message.into());
+}
+
+/// Parses the offset, kind and cells of the `preload` field of a snapshot.
+fn parse_preload(value: &str) -> io::Result<Preload> {
+    let mut tokens = value.split_whitespace();
+    let offset = parse("preload", tokens.next().unwrap_or_default())?;
+    let relative = match tokens.next() {
+        Some("numbers") => false,
+        Some("text") => true,
+        _ => return Err(invalid(format!(
+            "`{}` is not a valid value for `preload` in a snapshot.",
+            value
+        ))),
+    };
+    let cells = tokens
+        .map(|token| parse("preload", token))
+        .collect::<io::Result<Vec<CellNumber>>>()?;
+    return Ok(Preload::from_cells(cells, relative).at(offset));
+}
+
+/// Parses the `value` of the field `key` of a snapshot.
+fn parse<F>(key: &str, value: &str) -> io::Result<F>
+where
+    F: FromStr
+{
+    return value.parse().map_err(|_| invalid(format!(
+        "`{}` is not a valid value for `{}` in a snapshot.",
+        value,
+        key
+    )));
+}
@@ -0,0 +1,366 @@
+//! [`brainfucklib::vm::sparse`]
+//! 
+//! This module defines [`SparseMemory`], a virtual machine which behaves
+//! like [`crate::vm::Memory`] but only stores the cells a program has
+//! written to. This lets programs use tapes which are gigabytes long, or
+//! which jump between cells that are far apart, without allocating every
+//! cell in between.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    code::CommandRunner,
+    debug::{Event, OkEvent, Status, ErrEvent, BfError, BfErrorKind},
+};
+use super::{
+    CellNumber,
+    CellValue,
+    EofPolicy,
+    MemoryCell,
+    MemoryDump,
+    MemoryOptions,
+    PointerPolicy,
+    dump::DUMP_WIDTH,
+};
+
+/// The number of cells in each page of a [`SparseMemory`].
+pub const PAGE_SIZE: usize = 4096;
+
+/// A page of cells in a [`SparseMemory`].
+type Page<T> = Box<[MemoryCell<T>]>;
+
+/// A virtual machine whose tape is split into pages of [`PAGE_SIZE`] cells,
+/// which are only allocated once a cell in them is written to. Cells which
+/// have never been written to are at [`MemoryOptions::zero`].
+/// 
+/// The length of the tape in [`MemoryOptions`] is only used to decide where
+/// the pointer wraps around or raises an error, so it costs nothing to make
+/// it very large. With [`PointerPolicy::Grow`] or
+/// [`PointerPolicy::Infinite`], the tape has no ends at all.
+/// 
+/// Like [`crate::vm::Memory`], the cells are stored as `T`.
+#[derive(Clone, Debug)]
+pub struct SparseMemory<T = CellNumber>
+where
+    T: CellValue,
+{
+    pages: HashMap<isize, Page<T>>,
+    pointer: isize,
+    options: MemoryOptions,
+}
+
+impl<T> SparseMemory<T>
+where
+    T: CellValue,
+{
+    /// Creates a new instance of [`SparseMemory`].
+    /// 
+    /// This panics if the set of options is invalid or if the cells cannot
+    /// be stored as `T`.
+    pub fn new(options: MemoryOptions) -> Self {
+        options.validate();
+        if !T::fits(&options) {
+            panic!(
+                "The cells of this set of MemoryOptions do not fit in {}: \
+                {:?}",
+                std::any::type_name::<T>(),
+                options
+            );
+        }
+        let mut memory = Self {pages: HashMap::new(), pointer: 0, options};
+        let preload = memory.options.preloaded().clone();
+        let values = preload.values(memory.options.zero());
+        for (position, value) in (preload.offset()..).zip(values) {
+            *memory.cell_mut(position as isize) = MemoryCell::new(value);
+        }
+        return memory;
+    }
+
+    /// Get the position of the cell the pointer is pointing at, relative to
+    /// the cell it started at.
+    pub fn pointer(&self) -> isize {
+        return self.pointer;
+    }
+
+    /// Gets the [`MemoryOptions`] the [`SparseMemory`] was created with.
+    pub fn options(&self) -> &MemoryOptions {
+        return &self.options;
+    }
+
+    /// Gets the number of pages which have been allocated.
+    pub fn pages(&self) -> usize {
+        return self.pages.len();
+    }
+
+    /// Get a copy of the [`MemoryCell`] at `position`.
+    pub fn cell(&self, position: isize) -> MemoryCell<T> {
+        let (page, offset) = Self::locate(position);
+        return match self.pages.get(&page) {
+            Some(cells) => cells[offset].clone(),
+            None => MemoryCell::new(self.options.zero()),
+        };
+    }
+
+    /// Get a copy of the [`MemoryCell`] the pointer is pointing at.
+    pub fn get(&self) -> MemoryCell<T> {
+        return self.cell(self.pointer);
+    }
+
+    /// Takes a [`MemoryDump`] of the cells from position `start` up to (but
+    /// not including) position `end`. Positions outside of the tape are
+    /// left out, unless the tape has no ends.
+    /// 
+    /// Only the rows of the dump which overlap a page or hold the pointer
+    /// are looked at, so this takes as long as the number of pages rather
+    /// than the number of cells between `start` and `end`.
+    pub fn dump(&self, start: isize, end: isize) -> MemoryDump<T> {
+        let (first, last) = match self.options.on_out_of_bounds() {
+            PointerPolicy::Grow | PointerPolicy::Infinite => {
+                (start, end.max(start))
+            },
+            _ => {
+                let length = self.options.length().min(isize::MAX as usize);
+                let first = start.clamp(0, length as isize);
+                (first, end.clamp(first, length as isize))
+            },
+        };
+        let row = |position: isize| {
+            return (position - first) as usize / DUMP_WIDTH;
+        };
+        let mut rows = BTreeSet::new();
+        if (first..last).contains(&self.pointer) {
+            rows.insert(row(self.pointer));
+        }
+        for page in self.pages.keys() {
+            let size = PAGE_SIZE as isize;
+            let from = (page * size).max(first);
+            let to = page.saturating_add(1).saturating_mul(size).min(last);
+            if from < to {
+                rows.extend(row(from)..=row(to - 1));
+            }
+        }
+        return MemoryDump::collect(
+            first,
+            last,
+            rows,
+            |position| self.cell(position),
+            self.pointer,
+            self.options.zero()
+        );
+    }
+
+    /// Takes a [`MemoryDump`] of the cells which are at most `radius` cells
+    /// away from the pointer.
+    pub fn window(&self, radius: usize) -> MemoryDump<T> {
+        let radius = radius.min(isize::MAX as usize) as isize;
+        return self.dump(
+            self.pointer.saturating_sub(radius),
+            self.pointer.saturating_add(radius).saturating_add(1)
+        );
+    }
+
+    /// Takes a [`MemoryDump`] of the cells from the first to the last one
+    /// which is not zero, including the cell the pointer is pointing at.
+    pub fn used(&self) -> MemoryDump<T> {
+        let zero = self.options.zero();
+        let mut first = self.pointer;
+        let mut last = self.pointer;
+        for (page, cells) in self.pages.iter() {
+            for (offset, cell) in cells.iter().enumerate() {
+                if !cell.is_zero(zero) {
+                    let position = page * PAGE_SIZE as isize + offset as isize;
+                    first = first.min(position);
+                    last = last.max(position);
+                }
+            }
+        }
+        return self.dump(first, last + 1);
+    }
+
+    /// Flattens the value of all cells in the tape to the zero defined by
+    /// `options`, which frees every page.
+    pub fn flatten(&mut self) {
+        self.pages.clear();
+    }
+
+    /// Splits `position` into the number of its page and its offset in that
+    /// page.
+    fn locate(position: isize) -> (isize, usize) {
+        let size = PAGE_SIZE as isize;
+        return (
+            position.div_euclid(size),
+            position.rem_euclid(size) as usize,
+        );
+    }
+
+    /// Get a mutable reference to the [`MemoryCell`] at `position`,
+    /// allocating its page if it has not been written to before.
+    fn cell_mut(&mut self, position: isize) -> &mut MemoryCell<T> {
+        let (page, offset) = Self::locate(position);
+        let zero = MemoryCell::new(self.options.zero());
+        let cells = self.pages
+            .entry(page)
+            .or_insert_with(|| vec![zero; PAGE_SIZE].into_boxed_slice());
+        return &mut cells[offset];
+    }
+
+    /// Works out which cell is `amount` cells away from the cell at
+    /// `position` according to the [`PointerPolicy`], and returns its
+    /// position.
+    fn target(
+        &self,
+        position: isize,
+        amount: isize
+    ) -> Result<isize, ErrEvent> {
+        let length = self.options.length() as i128;
+        let target = position as i128 + amount as i128;
+        return match self.options.on_out_of_bounds() {
+            PointerPolicy::Grow | PointerPolicy::Infinite => {
+                Ok(target as isize)
+            },
+            _ if 0 <= target && target < length => Ok(target as isize),
+            PointerPolicy::Wrap => Ok(target.rem_euclid(length) as isize),
+            PointerPolicy::Error => Err(ErrEvent::Error(BfError::new(
+                BfErrorKind::OutOfBounds,
+                format!(
+                    "Pointer would move from cell {} to cell {}, outside \
+                    of the memory tape of {} cells.",
+                    position,
+                    target,
+                    length
+                )
+            ).with_cell(position))),
+        };
+    }
+
+    /// Adds `amount` to the cell at `position`.
+    fn add_at(&mut self, position: isize, amount: CellNumber) -> Event {
+        let lowest = self.options.lowest();
+        let highest = self.options.highest();
+        let mode = self.options.on_overflow();
+        return self.cell_mut(position)
+            .add(amount, lowest, highest, mode)
+            .map_err(|error| error.with_cell(position));
+    }
+}
+
+impl<T> CommandRunner for SparseMemory<T>
+where
+    T: CellValue,
+{
+    fn increment(&mut self) -> Event {
+        self.add_at(self.pointer, 1)?;
+        return Ok(OkEvent::Status(Status::new("Increment successful")));
+    }
+
+    fn decrement(&mut self) -> Event {
+        self.add_at(self.pointer, -1)?;
+        return Ok(OkEvent::Status(Status::new("Decrement successful")));
+    }
+
+    fn add(&mut self, amount: CellNumber) -> Event {
+        return self.add_at(self.pointer, amount);
+    }
+
+    fn shift(&mut self, amount: isize) -> Event {
+        self.pointer = self.target(self.pointer, amount)?;
+        return Ok(OkEvent::Status(Status::new("Shift pointer: OK")));
+    }
+
+    fn set_zero(&mut self) -> Event {
+        let zero = self.options.zero();
+        self.cell_mut(self.pointer).flatten(zero);
+        return Ok(OkEvent::Status(Status::new("Set zero: OK")));
+    }
+
+    fn multiply_add(&mut self, offset: isize, factor: CellNumber) -> Event {
+        let source = self.get();
+        if source.is_zero(self.options.zero()) {
+            return Ok(OkEvent::Status(Status::new("Multiply add: OK")));
+        }
+        let target = self.target(self.pointer, offset)?;
+        let zero = self.options.zero();
+        let lowest = self.options.lowest();
+        let highest = self.options.highest();
+        let mode = self.options.on_overflow();
+        return self.cell_mut(target)
+            .multiply_add(&source, factor, zero, lowest, highest, mode)
+            .map_err(|error| error.with_cell(target));
+    }
+
+    fn scan(&mut self, step: isize) -> Event {
+        let zero = self.options.zero();
+        while !self.get().is_zero(zero) {
+            self.shift(step)?;
+        }
+        return Ok(OkEvent::Status(Status::new("Scan: OK")));
+    }
+
+    fn next(&mut self) -> Event {
+        self.pointer = self.target(self.pointer, 1)?;
+        return Ok(OkEvent::Status(Status::new(
+            "Successfully moved pointer to the next cell."
+        )));
+    }
+
+    fn previous(&mut self) -> Event {
+        self.pointer = self.target(self.pointer, -1)?;
+        return Ok(OkEvent::Status(Status::new(
+            "Successfully moved pointer to the previous cell."
+        )));
+    }
+
+    fn read_out(&self, output: &mut char) -> Event {
+        return self.get().to_char(self.options.zero(), output);
+    }
+
+    fn write_in(&mut self, input: char) -> Event {
+        let zero = self.options.zero();
+        let lowest = self.options.lowest();
+        let highest = self.options.highest();
+        let mode = self.options.on_overflow();
+        let pointer = self.pointer;
+        return self.cell_mut(pointer)
+            .from_char(input, zero, lowest, highest, mode)
+            .map_err(|error| error.with_cell(pointer));
+    }
+
+    fn end_of_input(&mut self) -> Event {
+        let policy = self.options.on_eof();
+        let zero = self.options.zero();
+        let highest = self.options.highest();
+        match policy {
+            EofPolicy::Unchanged => (),
+            EofPolicy::Zero => self.cell_mut(self.pointer).flatten(zero),
+            EofPolicy::Max => self.cell_mut(self.pointer).maximize(highest),
+        }
+        return Ok(OkEvent::Status(Status::new(
+            format!("End of input ({}): OK", policy)
+        )));
+    }
+
+    fn is_zero(&self, output: &mut bool) -> Event {
+        *output = self.get().is_zero(self.options.zero());
+        return Ok(OkEvent::Status(Status::new("Is zero: OK")));
+    }
+}
+
+impl<T> From<MemoryOptions> for SparseMemory<T>
+where
+    T: CellValue,
+{
+    /// Creates a [`SparseMemory`] instance from [`MemoryOptions`].
+    fn from(options: MemoryOptions) -> Self {
+        return Self::new(options);
+    }
+}
+
+impl<T> From<&MemoryOptions> for SparseMemory<T>
+where
+    T: CellValue,
+{
+    /// Creates a [`SparseMemory`] instance from [`MemoryOptions`].
+    fn from(options: &MemoryOptions) -> Self {
+        return Self::new(options.clone());
+    }
+}
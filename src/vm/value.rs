@@ -0,0 +1,280 @@
+//! [`brainfucklib::vm::value`]
+//! 
+//! This module defines [`CellValue`], which is implemented by the types a
+//! [`crate::vm::MemoryCell`] can store its value as, and [`CellType`], which
+//! lets you choose one of them at runtime.
+//! 
+//! Smaller types make the memory tape take up less space. If the bounds of
+//! a cell match the range of the type exactly (such as 0 to 255 for [`u8`])
+//! and cells wrap around, arithmetic on the cell also wraps around natively
+//! instead of going through the slower 128-bit arithmetic needed for custom
+//! bounds.
+//! 
+//! With the `bignum` feature, cells can also be stored as a
+//! [`num_bigint::BigInt`], which is the only type that can be used with
+//! [`CellMode::Unbounded`].
+
+use std::{fmt, str::FromStr};
+
+#[cfg(feature = "bignum")]
+use std::convert::TryFrom;
+
+#[cfg(feature = "bignum")]
+use num_bigint::{BigInt, Sign};
+
+use crate::debug::{BfError, BfErrorKind};
+use super::{CellMode, CellNumber, MemoryOptions};
+
+/// A type which a [`crate::vm::MemoryCell`] can store its value as.
+/// 
+/// Values are converted to and from [`CellNumber`]s for arithmetic, so every
+/// value the type can hold must also fit in a [`CellNumber`], unless the type
+/// is [`CellValue::UNBOUNDED`]. They are converted to and from strings to
+/// save and load [`crate::vm::MemorySnapshot`]s.
+pub trait CellValue:
+    Clone + fmt::Debug + fmt::Display + FromStr + PartialEq + 'static
+{
+    /// The lowest value and one more than the highest value of the type, if
+    /// it wraps around natively.
+    const NATIVE: Option<(CellNumber, CellNumber)>;
+
+    /// Whether the type can hold any integer, so that it can be used with
+    /// [`CellMode::Unbounded`].
+    const UNBOUNDED: bool = false;
+
+    /// Converts a [`CellNumber`] into a value of this type. Numbers which do
+    /// not fit keep only the bits which do, like the `as` operator.
+    fn from_number(number: CellNumber) -> Self;
+
+    /// Converts the value into a [`CellNumber`]. Values of
+    /// [`CellValue::UNBOUNDED`] types which do not fit are clamped to
+    /// [`CellNumber::MIN`] or [`CellNumber::MAX`].
+    fn number(&self) -> CellNumber;
+
+    /// Adds `amount` to the value without any bounds. This is only used with
+    /// [`CellMode::Unbounded`].
+    fn add_number(&mut self, amount: CellNumber) {
+        *self = Self::from_number(self.number().wrapping_add(amount));
+    }
+
+    /// Adds `value` multiplied by `factor` to the value without any bounds.
+    /// This is only used with [`CellMode::Unbounded`].
+    fn add_product(&mut self, value: &Self, factor: CellNumber) {
+        self.add_number(value.number().wrapping_mul(factor));
+    }
+
+    /// Checks whether the value is equal to `number`.
+    fn equals(&self, number: CellNumber) -> bool {
+        return self.number() == number;
+    }
+
+    /// Converts the value into the character whose code point is how far
+    /// the value is above `zero`, if there is one.
+    fn to_char(&self, zero: CellNumber) -> Option<char> {
+        return std::char::from_u32(self.number().wrapping_sub(zero) as u32);
+    }
+
+    /// Formats the value in lowercase hexadecimal, with a `-` in front of
+    /// negative values.
+    fn hex(&self) -> String {
+        let number = self.number();
+        return match number < 0 {
+            true => format!("-{:x}", number.unsigned_abs()),
+            false => format!("{:x}", number),
+        };
+    }
+
+    /// Checks whether every value between `lowest` and `highest`
+    /// (exclusive) can be stored as this type.
+    fn holds(lowest: CellNumber, highest: CellNumber) -> bool {
+        return match Self::NATIVE {
+            Some((minimum, limit)) => minimum <= lowest && highest <= limit,
+            None => true,
+        };
+    }
+
+    /// Checks whether the cells of a virtual machine with `options` can be
+    /// stored as this type.
+    fn fits(options: &MemoryOptions) -> bool {
+        if options.on_overflow() == CellMode::Unbounded {
+            return Self::UNBOUNDED;
+        }
+        return Self::holds(options.lowest(), options.highest());
+    }
+
+    /// Checks whether a cell ranging from `lowest` to `highest` (exclusive)
+    /// can wrap around natively when stored as this type.
+    fn is_native(lowest: CellNumber, highest: CellNumber) -> bool {
+        return Self::NATIVE == Some((lowest, highest));
+    }
+}
+
+macro_rules! unsignedvalue {
+    ($type: ty) => {
+        impl CellValue for $type {
+            const NATIVE: Option<(CellNumber, CellNumber)> = Some((
+                0,
+                <$type>::MAX as CellNumber + 1
+            ));
+
+            fn from_number(number: CellNumber) -> Self {
+                return number as Self;
+            }
+
+            fn number(&self) -> CellNumber {
+                return *self as CellNumber;
+            }
+        }
+    };
+}
+
+unsignedvalue!(u8);
+unsignedvalue!(u16);
+unsignedvalue!(u32);
+unsignedvalue!(u64);
+
+impl CellValue for CellNumber {
+    const NATIVE: Option<(CellNumber, CellNumber)> = None;
+
+    fn from_number(number: CellNumber) -> Self {
+        return number;
+    }
+
+    fn number(&self) -> CellNumber {
+        return *self;
+    }
+}
+
+#[cfg(feature = "bignum")]
+impl CellValue for BigInt {
+    const NATIVE: Option<(CellNumber, CellNumber)> = None;
+    const UNBOUNDED: bool = true;
+
+    fn from_number(number: CellNumber) -> Self {
+        return BigInt::from(number);
+    }
+
+    fn number(&self) -> CellNumber {
+        return match CellNumber::try_from(self) {
+            Ok(number) => number,
+            Err(_) if self.sign() == Sign::Minus => CellNumber::MIN,
+            Err(_) => CellNumber::MAX,
+        };
+    }
+
+    fn add_number(&mut self, amount: CellNumber) {
+        *self += amount;
+    }
+
+    fn add_product(&mut self, value: &Self, factor: CellNumber) {
+        *self += value * factor;
+    }
+
+    fn equals(&self, number: CellNumber) -> bool {
+        return *self == BigInt::from(number);
+    }
+
+    fn hex(&self) -> String {
+        return format!("{:x}", self);
+    }
+
+    /// Converts the value into a character, if it is a valid code point once
+    /// `zero` is taken away. Unlike the other types, a value which is too
+    /// large is not cut down to 32 bits first.
+    fn to_char(&self, zero: CellNumber) -> Option<char> {
+        return u32::try_from(self - zero)
+            .ok()
+            .and_then(std::char::from_u32);
+    }
+}
+
+/// The type the cells of a virtual machine are stored as.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CellType {
+    /// [`u8`], for cells from 0 to 255.
+    U8,
+    /// [`u16`], for cells from 0 to 65535.
+    U16,
+    /// [`u32`], for cells from 0 to 2^32 - 1.
+    U32,
+    /// [`u64`], for cells from 0 to 2^64 - 1.
+    U64,
+    /// [`CellNumber`], which can hold any bounds.
+    I128,
+    /// [`num_bigint::BigInt`], which can hold any integer.
+    #[cfg(feature = "bignum")]
+    Big,
+}
+
+impl CellType {
+    /// Finds the smallest type which can hold every value of a cell with
+    /// `options`.
+    pub fn fitting(options: &MemoryOptions) -> Self {
+        let lowest = options.lowest();
+        let highest = options.highest();
+        #[cfg(feature = "bignum")]
+        if options.on_overflow() == CellMode::Unbounded {
+            return CellType::Big;
+        }
+        if u8::holds(lowest, highest) {
+            return CellType::U8;
+        } else if u16::holds(lowest, highest) {
+            return CellType::U16;
+        } else if u32::holds(lowest, highest) {
+            return CellType::U32;
+        } else if u64::holds(lowest, highest) {
+            return CellType::U64;
+        }
+        return CellType::I128;
+    }
+
+    /// Checks whether every value of a cell with `options` can be stored as
+    /// this type.
+    pub fn holds(&self, options: &MemoryOptions) -> bool {
+        return match self {
+            CellType::U8 => u8::fits(options),
+            CellType::U16 => u16::fits(options),
+            CellType::U32 => u32::fits(options),
+            CellType::U64 => u64::fits(options),
+            CellType::I128 => CellNumber::fits(options),
+            #[cfg(feature = "bignum")]
+            CellType::Big => BigInt::fits(options),
+        };
+    }
+}
+
+impl FromStr for CellType {
+    type Err = BfError;
+
+    /// Parses "u8", "u16", "u32", "u64", "i128" or (with the `bignum`
+    /// feature) "big" into a [`CellType`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Ok(match s {
+            "u8" => CellType::U8,
+            "u16" => CellType::U16,
+            "u32" => CellType::U32,
+            "u64" => CellType::U64,
+            "i128" => CellType::I128,
+            #[cfg(feature = "bignum")]
+            "big" => CellType::Big,
+            _ => return Err(BfError::new(
+                BfErrorKind::Other,
+                format!("{} is not a valid cell type.", s)
+            )),
+        });
+    }
+}
+
+impl fmt::Display for CellType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", match self {
+            CellType::U8 => "u8",
+            CellType::U16 => "u16",
+            CellType::U32 => "u32",
+            CellType::U64 => "u64",
+            CellType::I128 => "i128",
+            #[cfg(feature = "bignum")]
+            CellType::Big => "big",
+        });
+    }
+}
@@ -0,0 +1,251 @@
+//! Checks the C files made by [`CCompiler`]: they only carry the parts of
+//! the runtime a program uses, and when a C compiler can be found, they
+//! compile without warnings and write the same output as [`BfRunner`] given
+//! the same input.
+
+#![allow(clippy::needless_return)]
+
+use std::{
+    cell::RefCell,
+    env,
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    rc::Rc,
+};
+
+use brainfucklib::{
+    code::{BfProgram, BfRunner},
+    compile::{BfCompiler, CCompiler},
+    debug::BfDebugger,
+    parser::{BfParser, NormalParser},
+    vm::{EofPolicy, Memory, MemoryOptions, Preload},
+};
+
+/// The most commands the interpreter may run before the program is assumed
+/// to be stuck.
+const LIMIT: usize = 1_000_000;
+
+fn parse(script: &str) -> BfProgram {
+    let mut program = BfProgram::new();
+    NormalParser::parse_string(script, &mut program).unwrap();
+    program.populate_map().unwrap();
+    program.calculate_map().unwrap();
+    return program;
+}
+
+fn compile(script: &str, options: &MemoryOptions) -> String {
+    let mut source = Vec::new();
+    CCompiler::compile(&parse(script), options, &mut source).unwrap();
+    return String::from_utf8(source).unwrap();
+}
+
+fn interpret(script: &str, options: &MemoryOptions, input: &[u8]) -> Vec<u8> {
+    let memory: Memory = Memory::from(options);
+    let mut runner = BfRunner::with_buffers(
+        Box::new(parse(script)),
+        Box::new(memory),
+        None::<Rc<RefCell<BfDebugger>>>,
+        input,
+    );
+    for _i in 0..LIMIT {
+        if runner.run_once().is_none() {
+            return runner.output().clone();
+        }
+    }
+    panic!("{:?} did not finish after {} commands", script, LIMIT);
+}
+
+/// Gets the C compiler named by `CC`, or `cc` if it is not set, unless it
+/// cannot be run.
+fn c_compiler() -> Option<String> {
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let found = Command::new(&compiler)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    return match found {
+        true => Some(compiler),
+        false => None,
+    };
+}
+
+/// Compiles `script` with the C compiler and runs it on `input`, unless
+/// there is no C compiler.
+fn execute(
+    name: &str,
+    script: &str,
+    options: &MemoryOptions,
+    input: &[u8]
+) -> Option<Vec<u8>> {
+    let compiler = c_compiler()?;
+    let folder = env::temp_dir()
+        .join(format!("brainfuck-c-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&folder).unwrap();
+    let source = folder.join("program.c");
+    let binary: PathBuf = folder.join("program");
+    fs::write(&source, compile(script, options)).unwrap();
+    let built = Command::new(compiler)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-pedantic", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .unwrap();
+    assert!(
+        built.status.success(),
+        "{:?} did not compile:\n{}",
+        script,
+        String::from_utf8_lossy(&built.stderr)
+    );
+    let mut child = Command::new(&binary)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_dir_all(&folder).unwrap();
+    return Some(output.stdout);
+}
+
+/// Checks that `script` writes the same output when it is compiled as when
+/// it is interpreted.
+fn same(name: &str, script: &str, options: &MemoryOptions, input: &[u8]) {
+    let expected = interpret(script, options, input);
+    if let Some(output) = execute(name, script, options, input) {
+        assert_eq!(output, expected, "{:?}", script);
+    }
+}
+
+const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>\
+    ---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+#[test]
+fn only_the_runtime_a_program_uses() {
+    let cat = compile(",[.,]", &MemoryOptions::new());
+    for line in [
+        "#define USES_ADD 0",
+        "#define USES_MOVE 0",
+        "#define USES_OUTPUT 1",
+        "#define USES_INPUT 1",
+        "#define USES_MUL_ADD 0",
+    ] {
+        assert!(cat.contains(line), "{} is not in\n{}", line, cat);
+    }
+    let copy = compile("+[->+<]>[>]", &MemoryOptions::new());
+    for line in [
+        "#define USES_ADD 1",
+        "#define USES_MOVE 1",
+        "#define USES_OUTPUT 0",
+        "#define USES_INPUT 0",
+        "#define USES_MUL_ADD 1",
+        "bf_mul_add(1LL, 1ULL);",
+        "while (tape[ptr] != ZERO) bf_move(1LL);",
+    ] {
+        assert!(copy.contains(line), "{} is not in\n{}", line, copy);
+    }
+}
+
+#[test]
+fn cells_are_stored_natively_when_they_can_be() {
+    let source = compile("+.", &MemoryOptions::new());
+    assert!(source.contains("typedef uint8_t cell_t;"));
+    assert!(source.contains("#define NATIVE 1"));
+    let mut options = MemoryOptions::new();
+    options.upper_bound(100);
+    let source = compile("+.", &options);
+    assert!(source.contains("typedef int64_t cell_t;"));
+    assert!(source.contains("#define RANGE ((cell_t)101LL)"));
+}
+
+#[test]
+fn cells_too_wide_for_c() {
+    let mut options = MemoryOptions::new();
+    options.upper_bound(1 << 40);
+    let mut source = Vec::new();
+    let compiled = CCompiler::compile(&parse("+."), &options, &mut source);
+    assert!(compiled.is_err());
+}
+
+#[test]
+fn hello_world() {
+    same("hello", HELLO, &MemoryOptions::new(), b"");
+}
+
+#[test]
+fn cat() {
+    let mut options = MemoryOptions::new();
+    options.eof_policy(EofPolicy::Zero);
+    same("cat", ",[.,]", &options, b"h\xc3\xa9\x7f\n");
+    same("empty", "", &options, b"");
+}
+
+#[test]
+fn end_of_input() {
+    for (name, policy) in [
+        ("unchanged", EofPolicy::Unchanged),
+        ("zero", EofPolicy::Zero),
+    ] {
+        let mut options = MemoryOptions::new();
+        options.eof_policy(policy);
+        same(name, "+++,>,,.<.", &options, b"a");
+    }
+}
+
+/// Moves off the left end of the tape one cell at a time, and then several
+/// cells at once.
+const GROW_LEFT: &str = "+>+>+>+[<]<<++.>>>.<<<<<<<<<<<<+++.>>>>>>>>>>>>>>>.";
+
+#[test]
+fn tape() {
+    let mut options = MemoryOptions::new();
+    options.initial_length(4);
+    same("wrap", "+>++>+++>++++>+++++.<<<<.", &options, b"");
+    options.variable_length(true);
+    same("grow", "+>++>+++>++++>+++++.<<<<.", &options, b"");
+    same("grow-hello", HELLO, &options, b"");
+    same("grow-left", GROW_LEFT, &options, b"");
+}
+
+#[test]
+fn loops() {
+    let options = MemoryOptions::new();
+    same("multiply", "+++++[>+++++++++++++<-]>.", &options, b"");
+    let scan = "+>+>+>+<<<[>]+++++++++++++++++++++++++++++++++.";
+    same("scan", scan, &options, b"");
+}
+
+/// Goes below the lower bound and back, clears a cell and reads a character,
+/// all of which count from the lower bound when 0 is out of range.
+const FROM_THE_LOWER_BOUND: &str = "-.+.++[-]+.>,.[-],.";
+
+#[test]
+fn cells_count_from_the_lower_bound() {
+    for (name, lower, upper) in [("one", 1, 256), ("negative", -300, -45)] {
+        let mut options = MemoryOptions::new();
+        options.lower_bound(lower).upper_bound(upper);
+        same(name, FROM_THE_LOWER_BOUND, &options, b"A");
+    }
+}
+
+/// Prints the cells from the second one up to the first cell which is zero.
+const PRINT_PRELOAD: &str = ">[.>]";
+
+#[test]
+fn preloaded_cells() {
+    let mut options = MemoryOptions::new();
+    options.preload(Preload::text("Hi!").at(1));
+    same("text", PRINT_PRELOAD, &options, b"");
+    options.preload(Preload::numbers([1, 2, 3]).at(1));
+    same("numbers", PRINT_PRELOAD, &options, b"");
+    options.lower_bound(1).upper_bound(256);
+    options.preload(Preload::bytes(b"\x01ok").at(1));
+    same("relative", PRINT_PRELOAD, &options, b"");
+    let mut options = MemoryOptions::new();
+    options.initial_length(2).variable_length(true);
+    options.preload(Preload::text("longer").at(1));
+    same("longer", PRINT_PRELOAD, &options, b"");
+}
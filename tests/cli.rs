@@ -0,0 +1,168 @@
+//! Runs the `brainfuck` binary to check where it reads programs and their
+//! input from.
+
+#![allow(clippy::needless_return)]
+
+use std::{
+    env,
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+/// The output of a run of the binary.
+struct Run {
+    code: i32,
+    stdout: String,
+    stderr: String,
+}
+
+/// Runs the binary with `args`, giving it `stdin`.
+fn run(args: &[&str], stdin: &str) -> Run {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_brainfuck"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The binary may exit before it reads stdin, which closes the pipe.
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    let output = child.wait_with_output().unwrap();
+    return Run {
+        code: output.status.code().unwrap(),
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+    };
+}
+
+/// Writes `contents` to a file in a temporary directory, which is named
+/// after `name` so that tests running at once do not share files.
+fn file(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!(
+        "brainfuck-cli-{}-{}",
+        std::process::id(),
+        name
+    ));
+    fs::write(&path, contents).unwrap();
+    return path;
+}
+
+#[test]
+fn programs_from_stdin() {
+    let ran = run(&["-", "--input-string", "hi"], ",.,.");
+    assert_eq!((ran.code, ran.stdout.as_str()), (0, "hi\n"));
+}
+
+#[test]
+fn input_from_stdin() {
+    let ran = run(&["-r", ",.+."], "a");
+    assert_eq!((ran.code, ran.stdout.as_str()), (0, "ab\n"));
+}
+
+#[test]
+fn input_from_a_string() {
+    let ran = run(&["-r", ",.,.,.", "--input-string", "xy"], "z");
+    assert_eq!(ran.code, 0);
+    assert!(ran.stdout.starts_with("xy"), "{:?}", ran.stdout);
+    assert!(!ran.stdout.contains('z'), "{:?}", ran.stdout);
+}
+
+#[test]
+fn input_from_a_file() {
+    let program = file("input-program.b", ",.,.");
+    let input = file("input.txt", "ab");
+    let ran = run(&[
+        program.to_str().unwrap(),
+        "--input-file",
+        input.to_str().unwrap(),
+    ], "z");
+    assert_eq!((ran.code, ran.stdout.as_str()), (0, "ab\n"));
+}
+
+#[test]
+fn missing_input_files() {
+    let input = env::temp_dir().join("brainfuck-cli-no-such-file");
+    let ran = run(&["-r", ",.", "--input-file", input.to_str().unwrap()], "");
+    assert_eq!(ran.code, 1);
+    assert!(
+        ran.stdout.starts_with("Could not open input file"),
+        "{:?}",
+        ran.stdout
+    );
+}
+
+#[test]
+fn input_files_and_strings_conflict() {
+    let ran = run(&[
+        "-r",
+        ",.",
+        "--input-file",
+        "input.txt",
+        "--input-string",
+        "x",
+    ], "");
+    assert_eq!(ran.code, 1);
+    assert!(ran.stderr.contains("cannot be used with"), "{:?}", ran.stderr);
+}
+
+#[test]
+fn errors_in_programs_from_stdin() {
+    let ran = run(&["-", "-N"], "]+[");
+    assert_eq!(ran.code, 4);
+    assert_eq!(ran.stdout, concat!(
+        "error[UnmatchedRightBracket]: Unmatched ]\n",
+        "Position: 0 to 1\n",
+        "error[UnmatchedLeftBracket]: Unmatched [\n",
+        "Position: 2 to 3\n",
+        "Note (3 to 3): the program ends before this loop is closed\n",
+        "2 errors and 0 warnings found\n",
+        "Exited with code: 4\n",
+    ));
+}
+
+#[test]
+fn input_split_off_the_program() {
+    let program = file("split.b", ",.,.!ab");
+    let program = program.to_str().unwrap();
+    let ran = run(&[program, "--split-input"], "z");
+    assert_eq!((ran.code, ran.stdout.as_str()), (0, "ab\n"));
+    let ran = run(&[program, "--split-input", "--input-string", "xy"], "z");
+    assert_eq!((ran.code, ran.stdout.as_str()), (0, "xy\n"));
+    let ran = run(&["-", "-N", "--split-input"], "# no!\n,.!q");
+    assert_eq!((ran.code, ran.stdout.as_str()), (0, "q\n"));
+}
+
+#[test]
+fn programs_are_not_split_unless_asked_to() {
+    let ran = run(&["-r", ",.!ab"], "z");
+    assert_eq!((ran.code, ran.stdout.as_str()), (0, "z\n"));
+}
+
+#[test]
+fn errors_in_split_programs() {
+    let ran = run(&["-", "--split-input"], "+[!]");
+    assert_eq!(ran.code, 4);
+    assert_eq!(ran.stdout, concat!(
+        "error[UnmatchedLeftBracket]: Unmatched [\n",
+        " --> <stdin>:1:2\n",
+        "  |\n",
+        "1 | +[!]\n",
+        "  |  ^\n",
+        "  |   ^ the program ends before this loop is closed\n",
+        "\n",
+        "1 error and 0 warnings found\n",
+        "Exited with code: 4\n",
+    ));
+}
+
+#[test]
+fn preprocessed_programs() {
+    let lib = file("preprocess-lib.b", "#define out(c) +*$c.\n");
+    let lib = lib.file_name().unwrap().to_str().unwrap();
+    let code = format!("#include \"{}\"\n@out(65)", lib);
+    let program = file("preprocess.b", &code);
+    let ran = run(&[program.to_str().unwrap(), "--preprocess"], "");
+    assert_eq!((ran.code, ran.stdout.as_str()), (0, "A\n"));
+}
@@ -0,0 +1,122 @@
+//! Checks that [`BfParser::check_string`] reports every unmatched bracket and
+//! empty loop in a program at once, with either parser.
+
+#![allow(clippy::needless_return)]
+
+use brainfucklib::{
+    code::{BfProgram, SourceMap, Span},
+    debug::{Diagnostic, Diagnostics, Severity},
+    parser::{BfParser, NewParser, NormalParser},
+};
+
+/// Checks `script` with both parsers, making sure they find the same
+/// diagnostics.
+fn check(script: &str) -> Diagnostics {
+    let normal = NormalParser::check_string(script, &mut BfProgram::new());
+    let new = NewParser::check_string(script, &mut BfProgram::new());
+    let source = SourceMap::new("test.b", script);
+    assert_eq!(normal.render(&source), new.render(&source), "{:?}", script);
+    return normal;
+}
+
+/// Gets the name and the start of what each diagnostic points at.
+fn found(diagnostics: &Diagnostics) -> Vec<(&str, Option<usize>)> {
+    return diagnostics
+        .iter()
+        .map(|d| (d.name(), d.span().map(|span| span.start())))
+        .collect();
+}
+
+#[test]
+fn every_unmatched_bracket() {
+    let diagnostics = check("]+[[]-]]>[");
+    assert_eq!(found(&diagnostics), [
+        ("UnmatchedRightBracket", Some(0)),
+        ("EmptyLoop", Some(3)),
+        ("UnmatchedRightBracket", Some(7)),
+        ("UnmatchedLeftBracket", Some(9)),
+    ]);
+    assert_eq!(diagnostics.errors(), 3);
+    assert_eq!(diagnostics.warnings(), 1);
+    assert!(diagnostics.has_errors());
+    assert_eq!(diagnostics.summary(), "3 errors and 1 warning found");
+}
+
+#[test]
+fn empty_loops_are_warnings() {
+    let script = "+[]>[ ]";
+    let mut program = BfProgram::new();
+    let diagnostics = NormalParser::check_string(script, &mut program);
+    assert_eq!(found(&diagnostics), [
+        ("EmptyLoop", Some(1)),
+        ("EmptyLoop", Some(4)),
+    ]);
+    assert!(!diagnostics.has_errors());
+    assert_eq!(
+        diagnostics.iter().map(Diagnostic::severity).collect::<Vec<_>>(),
+        [Severity::Warning, Severity::Warning]
+    );
+    let second = diagnostics.iter().nth(1).unwrap();
+    assert_eq!(second.span(), Some(Span::new(4, 7)));
+    assert_eq!(program.get_counterpart(1), Some(2));
+    assert_eq!(program.get_counterpart(4), Some(5));
+}
+
+#[test]
+fn programs_without_problems() {
+    let mut program = BfProgram::new();
+    let diagnostics = NormalParser::check_string("+[->+<]", &mut program);
+    assert!(diagnostics.is_empty());
+    assert_eq!(diagnostics.summary(), "0 errors and 0 warnings found");
+    assert_eq!(program.get_counterpart(1), Some(6));
+    assert_eq!(program.get_counterpart(6), Some(1));
+}
+
+#[test]
+fn rendered_together() {
+    let script = "]+[[]-]]";
+    let diagnostics = check(script);
+    assert_eq!(diagnostics.render(&SourceMap::new("test.b", script)), concat!(
+        "error[UnmatchedRightBracket]: Unmatched ]\n",
+        " --> test.b:1:1\n",
+        "  |\n",
+        "1 | ]+[[]-]]\n",
+        "  | ^\n",
+        "\n",
+        "warning[EmptyLoop]: Empty loop never ends if the cell is not zero\n",
+        " --> test.b:1:4\n",
+        "  |\n",
+        "1 | ]+[[]-]]\n",
+        "  |    ^^\n",
+        "\n",
+        "error[UnmatchedRightBracket]: Unmatched ]\n",
+        " --> test.b:1:8\n",
+        "  |\n",
+        "1 | ]+[[]-]]\n",
+        "  |    ^ the last `[` before it is already closed\n",
+        "  |        ^\n",
+        "\n",
+        "2 errors and 1 warning found",
+    ));
+}
+
+#[test]
+fn comments_are_skipped() {
+    let script = "# ] [\n[+\n]]\n";
+    let diagnostics = NewParser::check_string(script, &mut BfProgram::new());
+    assert_eq!(found(&diagnostics), [("UnmatchedRightBracket", Some(10))]);
+}
+
+#[test]
+fn diagnostics_are_sorted() {
+    let mut diagnostics = Diagnostics::new();
+    diagnostics.push(Diagnostic::warning("B", "b").with_span(Span::new(4, 5)));
+    diagnostics.push(Diagnostic::warning("A", "a").with_span(Span::new(1, 2)));
+    diagnostics.push(Diagnostic::warning("C", "c"));
+    diagnostics.sort();
+    assert_eq!(found(&diagnostics), [
+        ("C", None),
+        ("A", Some(1)),
+        ("B", Some(4)),
+    ]);
+}
@@ -0,0 +1,142 @@
+//! Checks how a [`MemoryDump`] shows the cells of a [`Memory`] or a
+//! [`SparseMemory`].
+
+#![allow(clippy::needless_return)]
+
+use brainfucklib::{
+    code::CommandRunner,
+    vm::{DumpFormat, Memory, MemoryOptions, SparseMemory},
+};
+
+/// Writes `text` into the cells of `memory`, one character to a cell, and
+/// leaves the pointer on the cell after it.
+fn write<M>(memory: &mut M, text: &str)
+where
+    M: CommandRunner
+{
+    for character in text.chars() {
+        memory.write_in(character).unwrap();
+        memory.next().unwrap();
+    }
+}
+
+fn tape(length: usize) -> MemoryOptions {
+    let mut options = MemoryOptions::new();
+    options.initial_length(length);
+    return options;
+}
+
+#[test]
+fn formats() {
+    let mut memory: Memory = Memory::from(&tape(20));
+    write(&mut memory, "Hello world");
+    let dump = memory.used();
+    assert_eq!((dump.start(), dump.end()), (0, 12));
+    assert_eq!(
+        dump.format(DumpFormat::Ascii),
+        " 0: H  e  l  l  o     w  o  r  l  d [.]\n"
+    );
+    assert_eq!(
+        dump.format(DumpFormat::Hex),
+        format!(
+            " 0: 48  65  6c  6c  6f  20  77  6f  72  6c  64 [ 0]{:16} \
+            |Hello world.|\n",
+            ""
+        )
+    );
+    assert_eq!(dump.format(DumpFormat::Hex), dump.to_string());
+    let dump = memory.window(1);
+    assert_eq!(
+        dump.format(DumpFormat::Decimal),
+        format!("10: 100 [  0]   0 {:65} |d..|\n", "")
+    );
+}
+
+#[test]
+fn format_names() {
+    for format in [DumpFormat::Hex, DumpFormat::Decimal, DumpFormat::Ascii] {
+        assert_eq!(format.to_string().parse::<DumpFormat>().unwrap(), format);
+    }
+    assert_eq!(DumpFormat::default(), DumpFormat::Hex);
+    assert!("binary".parse::<DumpFormat>().is_err());
+}
+
+#[test]
+fn rows_of_zeros_are_left_out() {
+    let mut memory: Memory = Memory::from(&tape(100));
+    write(&mut memory, "a");
+    memory.shift(60).unwrap();
+    write(&mut memory, "b");
+    memory.shift(-30).unwrap();
+    let dump = memory.dump(0, 100);
+    let firsts: Vec<isize> = dump.rows().map(|(first, _)| first).collect();
+    assert_eq!(firsts, [0, 32, 48]);
+    assert_eq!(dump.get(20).unwrap().number(), 0);
+    assert_eq!(dump.get(61).unwrap().number(), 98);
+    assert!(dump.get(100).is_none());
+    assert_eq!(
+        dump.format(DumpFormat::Ascii),
+        concat!(
+            "  0: a  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+            "*\n",
+            " 32:[.] .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+            " 48: .  .  .  .  .  .  .  .  .  .  .  .  .  b  .  .\n",
+            "*\n",
+        )
+    );
+}
+
+#[test]
+fn far_apart_cells_of_a_sparse_memory() {
+    let mut memory: SparseMemory = SparseMemory::from(&tape(4_000_000_000));
+    write(&mut memory, "H");
+    memory.shift(2_999_999_999).unwrap();
+    write(&mut memory, "i");
+    memory.shift(-20).unwrap();
+    let dump = memory.used();
+    assert_eq!((dump.start(), dump.end()), (0, 3_000_000_001));
+    let rows: Vec<(isize, usize)> = dump.rows()
+        .map(|(first, cells)| (first, cells.len()))
+        .collect();
+    assert_eq!(rows, [(0, 16), (2_999_999_968, 16), (3_000_000_000, 1)]);
+    assert_eq!(
+        dump.format(DumpFormat::Ascii),
+        concat!(
+            "         0: H  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+            "*\n",
+            "2999999968: .  .  .  .  .  .  .  .  .  .  .  .  . [.] .  .\n",
+            "*\n",
+            "3000000000: i\n",
+        )
+    );
+    let dense: Memory = Memory::from(&tape(20));
+    assert_eq!(
+        SparseMemory::<i128>::from(&tape(20)).used(),
+        dense.used()
+    );
+}
+
+/// Writes two words which are on different rows, and moves the pointer
+/// between them.
+fn fill<M>(memory: &mut M)
+where
+    M: CommandRunner
+{
+    write(memory, "left");
+    memory.shift(200).unwrap();
+    write(memory, "right");
+    memory.shift(-100).unwrap();
+}
+
+#[test]
+fn sparse_and_dense_dumps_match() {
+    let options = tape(300);
+    let mut sparse: SparseMemory = SparseMemory::from(&options);
+    let mut dense: Memory = Memory::from(&options);
+    fill(&mut sparse);
+    fill(&mut dense);
+    assert_eq!(sparse.used(), dense.used());
+    assert_eq!(sparse.dump(-10, 400), dense.dump(-10, 400));
+    assert_eq!(sparse.dump(150, 210), dense.dump(150, 210));
+    assert_eq!(sparse.window(5), dense.window(5));
+}
@@ -0,0 +1,292 @@
+//! Checks that a program lowered into a [`BfIr`], with and without
+//! [`recognize_idioms`], runs exactly like the program itself: it reads the
+//! same input, writes the same output, leaves the same cells behind and
+//! stops with the same error.
+
+#![allow(clippy::needless_return)]
+
+use std::{cell::RefCell, rc::Rc};
+
+use brainfucklib::{
+    code::{
+        ir::{recognize_idioms, BfOp},
+        BfIr,
+        BfProgram,
+        BfRunner,
+        IrRunner,
+    },
+    debug::{BfDebugger, Debugger, ErrEvent},
+    parser::{BfParser, NormalParser},
+    vm::{EofPolicy, Memory, MemoryDump, MemoryOptions, Preload},
+};
+
+/// The most commands a program may run before it is assumed to be stuck.
+const LIMIT: usize = 1_000_000;
+
+/// What running a program left behind.
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    output: Vec<u8>,
+    error: Option<String>,
+    tape: MemoryDump,
+}
+
+fn parse(script: &str) -> BfProgram {
+    let mut program = BfProgram::new();
+    NormalParser::parse_string(script, &mut program).unwrap();
+    program.populate_map().unwrap();
+    program.calculate_map().unwrap();
+    return program;
+}
+
+fn lower(script: &str, idioms: bool) -> BfIr {
+    let mut ir = BfIr::lower(&parse(script)).unwrap();
+    if idioms {
+        recognize_idioms(&mut ir);
+    }
+    return ir;
+}
+
+fn ops(ir: &BfIr) -> Vec<BfOp> {
+    return (0..ir.len())
+        .map(|index| ir.instruction(index).unwrap().op())
+        .collect();
+}
+
+/// Gets the error the program stopped with, if it did.
+fn last_error(debugger: &Rc<RefCell<BfDebugger>>) -> Option<String> {
+    return match debugger.borrow().last_event() {
+        Some(Err(ErrEvent::Error(error))) => Some(error.to_string()),
+        Some(Err(ErrEvent::Warning(warning))) => {
+            Some(format!("{:?}", warning))
+        },
+        _ => None,
+    };
+}
+
+/// Runs every step of a runner, making sure that the program finishes.
+fn finish(mut step: impl FnMut() -> Option<()>) {
+    for _i in 0..LIMIT {
+        if step().is_none() {
+            return;
+        }
+    }
+    panic!("the program did not finish after {} commands", LIMIT);
+}
+
+fn interpret(script: &str, options: &MemoryOptions, input: &[u8]) -> Outcome {
+    let debugger = Rc::new(RefCell::new(BfDebugger::new()));
+    let memory: Memory = Memory::from(options);
+    let mut runner = BfRunner::with_buffers(
+        Box::new(parse(script)),
+        Box::new(memory),
+        Some(Rc::clone(&debugger)),
+        input,
+    );
+    finish(|| runner.run_once());
+    return Outcome {
+        output: runner.output().clone(),
+        error: last_error(&debugger),
+        tape: runner.memory().used(),
+    };
+}
+
+fn optimize(
+    script: &str,
+    options: &MemoryOptions,
+    input: &[u8],
+    idioms: bool
+) -> Outcome {
+    let debugger = Rc::new(RefCell::new(BfDebugger::new()));
+    let memory: Memory = Memory::from(options);
+    let mut runner = IrRunner::with_buffers(
+        Box::new(lower(script, idioms)),
+        Box::new(memory),
+        Some(Rc::clone(&debugger)),
+        input,
+    );
+    finish(|| runner.run_once());
+    return Outcome {
+        output: runner.output().clone(),
+        error: last_error(&debugger),
+        tape: runner.memory().used(),
+    };
+}
+
+/// Checks that `script` runs the same on both runners, and returns what it
+/// did so that the test can check that as well.
+fn same(script: &str, options: &MemoryOptions, input: &[u8]) -> Outcome {
+    let expected = interpret(script, options, input);
+    for idioms in [false, true] {
+        assert_eq!(
+            optimize(script, options, input, idioms),
+            expected,
+            "{:?} with idioms: {}",
+            script,
+            idioms
+        );
+    }
+    return expected;
+}
+
+fn tape(length: usize, variable: bool) -> MemoryOptions {
+    let mut options = MemoryOptions::new();
+    options.initial_length(length).variable_length(variable);
+    return options;
+}
+
+const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>\
+    ---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+#[test]
+fn runs_are_folded() {
+    assert_eq!(ops(&lower("+++--->><<<,[-]", false)), [
+        BfOp::Move(-1),
+        BfOp::Input,
+        BfOp::JumpIfZero(4),
+        BfOp::Add(-1),
+        BfOp::JumpIfNotZero(2),
+    ]);
+}
+
+#[test]
+fn idioms_are_recognized() {
+    assert_eq!(ops(&lower("+[-]>[+]", true)), [
+        BfOp::Add(1),
+        BfOp::SetZero,
+        BfOp::Move(1),
+        BfOp::SetZero,
+    ]);
+    assert_eq!(ops(&lower("[->++<<+++>]", true)), [
+        BfOp::MulAdd(-1, 3),
+        BfOp::MulAdd(1, 2),
+        BfOp::SetZero,
+    ]);
+    assert_eq!(ops(&lower("[>>][<]", true)), [
+        BfOp::Scan(2),
+        BfOp::Scan(-1),
+    ]);
+    // Loops which do anything else are left alone.
+    assert_eq!(ops(&lower("[-.]", true)), ops(&lower("[-.]", false)));
+    assert_eq!(ops(&lower("[->+]", true)), ops(&lower("[->+]", false)));
+}
+
+#[test]
+fn hello_world() {
+    let outcome = same(HELLO, &MemoryOptions::new(), b"");
+    assert_eq!(outcome.output, b"Hello World!\n");
+    assert_eq!(outcome.error, None);
+}
+
+#[test]
+fn cat_keeps_every_byte() {
+    let mut options = MemoryOptions::new();
+    options.eof_policy(EofPolicy::Zero);
+    let input = b"h\xc3\xa9\n";
+    assert_eq!(same(",[.,]", &options, input).output, input);
+}
+
+#[test]
+fn end_of_input() {
+    for policy in [EofPolicy::Unchanged, EofPolicy::Zero, EofPolicy::Max] {
+        let mut options = MemoryOptions::new();
+        options.eof_policy(policy);
+        same("+++,>,,.<.", &options, b"a");
+    }
+}
+
+#[test]
+fn pointer_wraps_around() {
+    for length in [4, 7] {
+        let options = tape(length, false);
+        same("<+.>>>>>>>>+.", &options, b"");
+        same("+>+>+<<[<]+.", &options, b"");
+    }
+}
+
+/// Moves off the left end of the tape one cell at a time, and then several
+/// cells at once.
+const GROW_LEFT: &str = "+>+>+>+[<]<<++.>>>.<<<<<<<<<<<<+++.>>>>>>>>>>>>>>>.";
+
+#[test]
+fn tape_grows() {
+    let options = tape(2, true);
+    same(GROW_LEFT, &options, b"");
+    same(">>>>>+.<<<<+.", &options, b"");
+    same(HELLO, &options, b"");
+}
+
+#[test]
+fn tape_is_infinite() {
+    let mut options = tape(2, false);
+    options.infinite(true);
+    same(GROW_LEFT, &options, b"");
+    same("<<<<<+.>>>>>>>>>>+.", &options, b"");
+    same(HELLO, &options, b"");
+}
+
+#[test]
+fn clear_loops() {
+    let options = MemoryOptions::new();
+    assert_eq!(same("+++++[-]+.", &options, b"").output, [1]);
+    assert_eq!(same("+++++[+]+.", &options, b"").output, [1]);
+}
+
+#[test]
+fn multiplication() {
+    let options = MemoryOptions::new();
+    let script = "++++++++[>+++++++++<-]>.";
+    assert_eq!(same(script, &options, b"").output, b"H");
+    // Several cells on both sides of the counter.
+    same("+++++[<++>>+++>-<<-]>>.>.", &tape(16, false), b"");
+}
+
+#[test]
+fn scans() {
+    let options = tape(16, false);
+    same("+>+>+>+<<<[>]+.<[<]>.", &options, b"");
+    same(">>+<+<+[>>]+.", &options, b"");
+}
+
+#[test]
+fn cells_wrap_around() {
+    let options = MemoryOptions::new();
+    assert_eq!(same("-.+.", &options, b"").output, [255, 0]);
+    let script = "++++++++[>++++++++++++++++++++++++++++++++++++++++<-]>.";
+    assert_eq!(same(script, &options, b"").output, [64]);
+    assert_eq!(same("-[--->+<]>.", &options, b"").output, [85]);
+}
+
+/// Goes below the lower bound and back, clears a cell and reads a character,
+/// all of which count from the lower bound when 0 is out of range.
+const FROM_THE_LOWER_BOUND: &str = "-.+.++[-]+.>,.[-],.";
+
+#[test]
+fn cells_count_from_the_lower_bound() {
+    for (lower, upper) in [(1, 256), (-300, -45)] {
+        let mut options = MemoryOptions::new();
+        options.lower_bound(lower).upper_bound(upper);
+        let outcome = same(FROM_THE_LOWER_BOUND, &options, b"A");
+        assert_eq!(outcome.output, [255, 0, 1, 65, 0]);
+        same("+++[-]>+[>++<-]>.", &options, b"");
+    }
+}
+
+/// Prints the cells from the second one up to the first cell which is zero.
+const PRINT_PRELOAD: &str = ">[.>]";
+
+#[test]
+fn preloaded_cells() {
+    let mut options = MemoryOptions::new();
+    options.preload(Preload::text("Hi!").at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b"").output, b"Hi!");
+    options.preload(Preload::numbers([1, 2, 3]).at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b"").output, [1, 2, 3]);
+    options.lower_bound(1).upper_bound(256);
+    options.preload(Preload::bytes(b"\x01ok").at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b"").output, b"\x01ok");
+    let mut options = MemoryOptions::new();
+    options.initial_length(2).variable_length(true);
+    options.preload(Preload::text("longer").at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b"").output, b"longer");
+}
@@ -0,0 +1,238 @@
+//! Checks that [`JitRunner`] writes the same output as [`BfRunner`] given
+//! the same program and input, whether it compiles the program into machine
+//! code or falls back to an [`brainfucklib::code::IrRunner`].
+
+#![cfg(feature = "jit")]
+#![allow(clippy::needless_return)]
+
+use std::{cell::RefCell, rc::Rc};
+
+use brainfucklib::{
+    code::{ir::recognize_idioms, BfIr, BfProgram, BfRunner, JitRunner},
+    debug::BfDebugger,
+    parser::{BfParser, NormalParser},
+    vm::{
+        CellNumber,
+        CellValue,
+        EofPolicy,
+        Memory,
+        MemoryOptions,
+        Preload,
+    },
+};
+
+/// The most commands the interpreter may run before the program is assumed
+/// to be stuck.
+const LIMIT: usize = 1_000_000;
+
+fn parse(script: &str) -> BfProgram {
+    let mut program = BfProgram::new();
+    NormalParser::parse_string(script, &mut program).unwrap();
+    program.populate_map().unwrap();
+    program.calculate_map().unwrap();
+    return program;
+}
+
+fn interpret(script: &str, options: &MemoryOptions, input: &[u8]) -> Vec<u8> {
+    let memory: Memory = Memory::from(options);
+    let mut runner = BfRunner::with_buffers(
+        Box::new(parse(script)),
+        Box::new(memory),
+        None::<Rc<RefCell<BfDebugger>>>,
+        input,
+    );
+    for _i in 0..LIMIT {
+        if runner.run_once().is_none() {
+            return runner.output().clone();
+        }
+    }
+    panic!("{:?} did not finish after {} commands", script, LIMIT);
+}
+
+fn jit(script: &str, options: &MemoryOptions, input: &[u8]) -> JitRunner<
+    BfDebugger,
+    CellNumber,
+    std::io::Cursor<Vec<u8>>,
+    Vec<u8>
+> {
+    return jit_on(script, Memory::from(options), input);
+}
+
+fn jit_on<T>(script: &str, memory: Memory<T>, input: &[u8]) -> JitRunner<
+    BfDebugger,
+    T,
+    std::io::Cursor<Vec<u8>>,
+    Vec<u8>
+>
+where
+    T: CellValue,
+{
+    let mut ir = BfIr::lower(&parse(script)).unwrap();
+    recognize_idioms(&mut ir);
+    return JitRunner::with_buffers(
+        Box::new(ir),
+        Box::new(memory),
+        None::<Rc<RefCell<BfDebugger>>>,
+        input,
+    );
+}
+
+/// Checks that `script` writes the same output on both runners, and returns
+/// that output.
+fn same(script: &str, options: &MemoryOptions, input: &[u8]) -> Vec<u8> {
+    let expected = interpret(script, options, input);
+    let mut runner = jit(script, options, input);
+    for _i in 0..LIMIT {
+        if runner.run_once().is_none() {
+            assert_eq!(*runner.output(), expected, "{:?}", script);
+            return expected;
+        }
+    }
+    panic!("{:?} did not finish after {} instructions", script, LIMIT);
+}
+
+fn tape(length: usize) -> MemoryOptions {
+    let mut options = MemoryOptions::new();
+    options.initial_length(length);
+    return options;
+}
+
+const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>\
+    ---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+#[test]
+fn only_native_widths_are_compiled() {
+    let native = cfg!(all(target_arch = "x86_64", unix));
+    assert_eq!(jit("", &MemoryOptions::new(), b"").is_native(), native);
+    let mut options = MemoryOptions::new();
+    options.upper_bound(65535);
+    assert_eq!(jit("", &options, b"").is_native(), native);
+    options.upper_bound(5);
+    assert!(!jit("", &options, b"").is_native());
+    let mut options = MemoryOptions::new();
+    options.variable_length(true);
+    assert!(!jit("", &options, b"").is_native());
+}
+
+#[test]
+fn hello_world() {
+    let output = same(HELLO, &MemoryOptions::new(), b"");
+    assert_eq!(output, b"Hello World!\n");
+}
+
+#[test]
+fn cells_wrap_around() {
+    let options = MemoryOptions::new();
+    let script = "-.>++++++++++++++++[<++++++++++++++++>-]<+.";
+    assert_eq!(same(script, &options, b""), [255, 0]);
+    let mut options = MemoryOptions::new();
+    options.upper_bound(65535);
+    same("-[>+<-----]>.", &options, b"");
+    options.upper_bound(5);
+    assert_eq!(same("-.+++++++.", &options, b""), [5, 0]);
+}
+
+/// Checks that `script` writes the same output when the JIT runs on a tape
+/// of `T`s as it does on the interpreter.
+fn same_on<T>(script: &str, options: &MemoryOptions)
+where
+    T: CellValue,
+{
+    let expected = interpret(script, options, b"");
+    let mut runner = jit_on::<T>(script, Memory::from(options), b"");
+    assert_eq!(runner.is_native(), cfg!(all(target_arch = "x86_64", unix)));
+    while runner.run_once().is_some() {}
+    assert_eq!(*runner.output(), expected, "{:?}", script);
+}
+
+#[test]
+fn cells_of_each_width_run_in_place() {
+    let script = "-.>+.<+.>>-.<<<-.";
+    let mut options = tape(4);
+    same_on::<u8>(script, &options);
+    options.upper_bound(CellNumber::from(u16::MAX));
+    same_on::<u16>(script, &options);
+    options.upper_bound(CellNumber::from(u32::MAX));
+    same_on::<u32>(script, &options);
+    options.upper_bound(CellNumber::from(u64::MAX));
+    same_on::<u64>(script, &options);
+}
+
+#[test]
+fn pointer_wraps_around() {
+    let options = tape(5);
+    assert_eq!(same("<+.>>>>>+.", &options, b""), [1, 2]);
+    same("+>+>+<<[<]+.", &options, b"");
+    same("+++[<+>-]<.", &options, b"");
+}
+
+#[test]
+fn scans() {
+    let options = tape(16);
+    same("+>+>+>+<<<[>]+++++++++++++++++++++++++++++++++.", &options, b"");
+    same(">>>>>>+[<<]+.>>.", &options, b"");
+    same("+>>+>>+>>[<<]+.", &options, b"");
+}
+
+#[test]
+fn multiplication() {
+    let options = MemoryOptions::new();
+    same("+++++[>+++++++++++++<-]>.", &options, b"");
+    same("++++[->+++>>-----<<<]>.>>.", &options, b"");
+    same("+++[>>+++<<-]>>[<<+++++++++>>-]<<.", &options, b"");
+}
+
+/// Adds 47 to what `,` leaves in a cell which was 2 before, and prints it.
+const AFTER_EOF: &str = "++,+++++++++++++++++++++++++++++++++++++++++++++++.";
+
+#[test]
+fn end_of_input() {
+    for policy in [EofPolicy::Unchanged, EofPolicy::Zero, EofPolicy::Max] {
+        let mut options = MemoryOptions::new();
+        options.eof_policy(policy);
+        same("+++,>,,.<.", &options, b"a");
+        same(AFTER_EOF, &options, b"");
+    }
+    let mut options = MemoryOptions::new();
+    options.eof_policy(EofPolicy::Zero);
+    let input = b"h\xc3\xa9\xff\n";
+    assert_eq!(same(",[.,]", &options, input), input);
+}
+
+/// Goes below the lower bound and back, clears a cell and reads a character,
+/// all of which count from the lower bound when 0 is out of range.
+const FROM_THE_LOWER_BOUND: &str = "-.+.++[-]+.>,.[-],.";
+
+#[test]
+fn cells_count_from_the_lower_bound() {
+    for (lower, upper) in [(1, 256), (-300, -45)] {
+        let mut options = MemoryOptions::new();
+        options.lower_bound(lower).upper_bound(upper);
+        assert!(!jit("", &options, b"").is_native());
+        let output = same(FROM_THE_LOWER_BOUND, &options, b"A");
+        assert_eq!(output, [255, 0, 1, 65, 0]);
+    }
+}
+
+/// Prints the cells from the second one up to the first cell which is zero.
+const PRINT_PRELOAD: &str = ">[.>]";
+
+#[test]
+fn preloaded_cells() {
+    let mut options = MemoryOptions::new();
+    options.preload(Preload::text("Hi!").at(1));
+    assert_eq!(jit("", &options, b"").is_native(), cfg!(all(
+        target_arch = "x86_64",
+        unix
+    )));
+    assert_eq!(same(PRINT_PRELOAD, &options, b""), b"Hi!");
+    options.preload(Preload::numbers([1, 2, 3]).at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b""), [1, 2, 3]);
+    options.lower_bound(1).upper_bound(256);
+    options.preload(Preload::bytes(b"\x01ok").at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b""), b"\x01ok");
+    let mut options = MemoryOptions::new();
+    options.initial_length(2).variable_length(true);
+    options.preload(Preload::text("longer").at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b""), b"longer");
+}
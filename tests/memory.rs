@@ -0,0 +1,441 @@
+//! Checks how a [`Memory`] stores its tape: where the pointer is, how far
+//! the tape grows and what is in its cells.
+
+#![allow(clippy::needless_return)]
+
+use std::{cell::RefCell, rc::Rc};
+
+#[cfg(feature = "bignum")]
+use num_bigint::BigInt;
+
+use brainfucklib::{
+    code::{BfProgram, BfRunner, CommandRunner},
+    debug::{BfDebugger, BfErrorKind, Debugger, ErrEvent},
+    parser::{BfParser, NormalParser},
+    vm::{
+        CellMode,
+        CellNumber,
+        CellType,
+        Memory,
+        MemoryOptions,
+        PointerPolicy,
+        Preload,
+        SparseMemory,
+    },
+};
+
+/// The most commands a program may run before it is stopped.
+const LIMIT: usize = 100_000;
+
+fn parse(script: &str) -> Box<BfProgram> {
+    let mut program = Box::new(BfProgram::new());
+    NormalParser::parse_string(script, &mut program).unwrap();
+    program.populate_map().unwrap();
+    program.calculate_map().unwrap();
+    return program;
+}
+
+/// Runs `script` on `memory`, and gets its output and the error it stopped
+/// with.
+fn run<M>(script: &str, memory: M) -> (Vec<u8>, Option<String>)
+where
+    M: CommandRunner
+{
+    let debugger = Rc::new(RefCell::new(BfDebugger::new()));
+    let mut runner = BfRunner::with_buffers(
+        parse(script),
+        Box::new(memory),
+        Some(Rc::clone(&debugger)),
+        "",
+    );
+    for _i in 0..LIMIT {
+        if runner.run_once().is_none() {
+            break;
+        }
+    }
+    let error = match debugger.borrow().last_event() {
+        Some(Err(error)) => Some(error.to_string()),
+        _ => None,
+    };
+    return (runner.output().clone(), error);
+}
+
+fn sparse(length: usize, policy: PointerPolicy) -> SparseMemory {
+    let mut options = MemoryOptions::new();
+    options.initial_length(length).pointer_policy(policy);
+    return SparseMemory::from(&options);
+}
+
+fn infinite(length: usize) -> Memory {
+    let mut options = MemoryOptions::new();
+    options.initial_length(length).infinite(true);
+    return Memory::from(&options);
+}
+
+/// Gets the position of the pointer, its index in the tape and the index of
+/// the cell it started at.
+fn place(memory: &Memory) -> (isize, usize, usize) {
+    return (memory.pointer(), memory.index(), memory.origin());
+}
+
+#[test]
+fn pointer_positions_are_relative_to_the_start() {
+    let mut memory = infinite(4);
+    assert_eq!(place(&memory), (0, 0, 0));
+    memory.shift(2).unwrap();
+    assert_eq!(place(&memory), (2, 2, 0));
+    memory.shift(-3).unwrap();
+    let (pointer, index, origin) = place(&memory);
+    assert_eq!(pointer, -1);
+    assert_eq!(index as isize - origin as isize, -1);
+}
+
+#[test]
+fn infinite_tapes_double_to_the_left() {
+    let mut memory = infinite(4);
+    memory.previous().unwrap();
+    assert_eq!(memory.cells().len(), 8);
+    assert_eq!((memory.index(), memory.origin()), (3, 4));
+    // Moving within the cells already added does not grow the tape.
+    memory.shift(-3).unwrap();
+    assert_eq!(memory.cells().len(), 8);
+    assert_eq!(memory.pointer(), -4);
+    memory.shift(-5).unwrap();
+    assert_eq!(memory.cells().len(), 16);
+    assert_eq!(place(&memory), (-9, 3, 12));
+}
+
+#[test]
+fn growing_tapes_only_add_what_they_need() {
+    let mut options = MemoryOptions::new();
+    options.initial_length(4).pointer_policy(PointerPolicy::Grow);
+    let mut memory: Memory = Memory::from(&options);
+    memory.shift(-2).unwrap();
+    assert_eq!(memory.cells().len(), 6);
+    assert_eq!(place(&memory), (-2, 0, 2));
+    memory.shift(7).unwrap();
+    assert_eq!(memory.cells().len(), 8);
+    assert_eq!(memory.pointer(), 5);
+}
+
+#[test]
+fn cells_keep_their_values_when_the_tape_grows() {
+    let mut memory = infinite(2);
+    memory.add(3).unwrap();
+    memory.next().unwrap();
+    memory.add(5).unwrap();
+    memory.shift(-10).unwrap();
+    memory.add(7).unwrap();
+    memory.shift(10).unwrap();
+    assert_eq!(memory.get().unwrap().number(), 5);
+    memory.previous().unwrap();
+    assert_eq!(memory.get().unwrap().number(), 3);
+    memory.shift(-9).unwrap();
+    assert_eq!(memory.get().unwrap().number(), 7);
+    assert_eq!(memory.pointer(), -9);
+}
+
+#[test]
+fn errors_say_where_the_cell_is() {
+    let mut options = MemoryOptions::new();
+    options.initial_length(2).infinite(true).cell_mode(CellMode::Error);
+    let mut memory: Memory = Memory::from(&options);
+    memory.shift(-3).unwrap();
+    let error = match memory.decrement() {
+        Err(ErrEvent::Error(error)) => error,
+        other => panic!("{:?}", other),
+    };
+    assert!(matches!(error.kind(), BfErrorKind::CellOverflow));
+    assert_eq!(error.cell(), Some(-3));
+}
+
+#[test]
+fn infinite_tapes_from_options() {
+    let mut options = MemoryOptions::new();
+    options.infinite(true);
+    assert_eq!(options.on_out_of_bounds(), PointerPolicy::Infinite);
+    assert!(options.is_variable_length());
+    options.infinite(false);
+    assert_eq!(options.on_out_of_bounds(), PointerPolicy::Wrap);
+    let policy = "infinite".parse::<PointerPolicy>().unwrap();
+    assert_eq!(policy, PointerPolicy::Infinite);
+    assert_eq!(policy.to_string(), "infinite");
+}
+
+#[test]
+fn sparse_pages_are_only_allocated_when_written_to() {
+    let mut memory = sparse(4_000_000_000, PointerPolicy::Wrap);
+    memory.shift(3_000_000_000).unwrap();
+    let mut is_zero = false;
+    memory.is_zero(&mut is_zero).unwrap();
+    assert!(is_zero);
+    assert_eq!(memory.pages(), 0);
+    memory.increment().unwrap();
+    assert_eq!(memory.pages(), 1);
+    assert_eq!(memory.cell(3_000_000_000).number(), 1);
+    // Moving off the left end wraps around to the far end of the tape.
+    memory.shift(-3_000_000_001).unwrap();
+    assert_eq!(memory.pointer(), 3_999_999_999);
+    memory.add(2).unwrap();
+    assert_eq!(memory.pages(), 2);
+    assert_eq!(memory.get().number(), 2);
+    memory.flatten();
+    assert_eq!(memory.pages(), 0);
+    assert_eq!(memory.cell(3_000_000_000).number(), 0);
+}
+
+#[test]
+fn sparse_pointer_policies() {
+    let mut memory = sparse(4, PointerPolicy::Infinite);
+    memory.shift(-10_000).unwrap();
+    assert_eq!(memory.pointer(), -10_000);
+    memory.increment().unwrap();
+    assert_eq!(memory.cell(-10_000).number(), 1);
+    let mut memory = sparse(4, PointerPolicy::Grow);
+    memory.shift(10_000).unwrap();
+    assert_eq!(memory.pointer(), 10_000);
+    let mut memory = sparse(4, PointerPolicy::Error);
+    memory.shift(3).unwrap();
+    let error = match memory.next() {
+        Err(ErrEvent::Error(error)) => error,
+        other => panic!("{:?}", other),
+    };
+    assert!(matches!(error.kind(), BfErrorKind::OutOfBounds));
+    assert_eq!(error.cell(), Some(3));
+}
+
+#[test]
+fn sparse_memory_runs_like_memory() {
+    let scripts = [
+        "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>\
+        ---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.",
+        "+<++.>.>>>>.",
+        "+>+>+>+[<]<<++.>>>.<<<<<<<<<<<<+++.>>>>>>>>>>>>>>>.",
+        "+.<.",
+        "+[>+]",
+    ];
+    // A program which runs forever is stopped at the same place on both.
+    let policies = [
+        PointerPolicy::Wrap,
+        PointerPolicy::Grow,
+        PointerPolicy::Infinite,
+        PointerPolicy::Error,
+    ];
+    for policy in policies {
+        let mut options = MemoryOptions::new();
+        options.initial_length(4).pointer_policy(policy);
+        for script in scripts {
+            let sparse: SparseMemory = SparseMemory::from(&options);
+            let dense: Memory = Memory::from(&options);
+            assert_eq!(
+                run(script, sparse),
+                run(script, dense),
+                "{:?} with {}",
+                script,
+                policy
+            );
+        }
+    }
+}
+
+fn bounds(lower: CellNumber, upper: CellNumber) -> MemoryOptions {
+    let mut options = MemoryOptions::new();
+    options.lower_bound(lower).upper_bound(upper);
+    return options;
+}
+
+#[test]
+fn cell_types_fit_the_bounds() {
+    let widths = [
+        (bounds(0, 255), CellType::U8),
+        (bounds(10, 20), CellType::U8),
+        (bounds(0, 256), CellType::U16),
+        (bounds(0, CellNumber::from(u16::MAX)), CellType::U16),
+        (bounds(0, CellNumber::from(u32::MAX)), CellType::U32),
+        (bounds(0, CellNumber::from(u64::MAX)), CellType::U64),
+        (bounds(-1, 255), CellType::I128),
+        (bounds(0, CellNumber::from(u64::MAX) + 1), CellType::I128),
+    ];
+    for (options, expected) in widths {
+        assert_eq!(CellType::fitting(&options), expected);
+        assert!(expected.holds(&options));
+        assert!(CellType::I128.holds(&options));
+    }
+    assert!(!CellType::U8.holds(&bounds(0, 256)));
+    assert!(!CellType::U64.holds(&bounds(-1, 0)));
+}
+
+#[test]
+fn cell_types_from_text() {
+    let types = [
+        CellType::U8,
+        CellType::U16,
+        CellType::U32,
+        CellType::U64,
+        CellType::I128,
+    ];
+    for cell_type in types {
+        let text = cell_type.to_string();
+        assert_eq!(text.parse::<CellType>().unwrap(), cell_type);
+    }
+    assert!("i8".parse::<CellType>().is_err());
+    assert!("U8".parse::<CellType>().is_err());
+}
+
+#[test]
+fn cells_of_each_width_run_alike() {
+    let scripts = [
+        "-.+.-->+.",
+        "-[>+<-----]>.",
+        "+++[>-------<-]>.",
+    ];
+    let modes = [CellMode::Wrap, CellMode::Saturate, CellMode::Error];
+    for mode in modes {
+        for script in scripts {
+            let mut options = bounds(0, 255);
+            options.cell_mode(mode);
+            let expected = run(script, Memory::<CellNumber>::from(&options));
+            assert_eq!(run(script, Memory::<u8>::from(&options)), expected);
+            options.upper_bound(CellNumber::from(u16::MAX));
+            let expected = run(script, Memory::<CellNumber>::from(&options));
+            assert_eq!(run(script, Memory::<u16>::from(&options)), expected);
+            options.upper_bound(CellNumber::from(u64::MAX));
+            let expected = run(script, Memory::<CellNumber>::from(&options));
+            assert_eq!(run(script, Memory::<u64>::from(&options)), expected);
+        }
+    }
+}
+
+#[test]
+fn narrow_cells_keep_custom_bounds() {
+    let mut options = bounds(10, 20);
+    let memory: Memory<u8> = Memory::from(&options);
+    // Characters count from the lower bound, since 0 is out of range.
+    assert_eq!(run("-.+.", memory).0, [10, 0]);
+    options.cell_mode(CellMode::Saturate);
+    let memory: Memory<u8> = Memory::from(&options);
+    assert_eq!(run("-.+++++++++++++.", memory).0, [0, 10]);
+}
+
+#[cfg(feature = "bignum")]
+fn unbounded() -> MemoryOptions {
+    let mut options = MemoryOptions::new();
+    options.cell_mode(CellMode::Unbounded);
+    return options;
+}
+
+#[cfg(feature = "bignum")]
+#[test]
+fn unbounded_cells_go_past_every_bound() {
+    let mut memory: Memory<BigInt> = Memory::from(&unbounded());
+    memory.add(CellNumber::MAX).unwrap();
+    memory.add(CellNumber::MAX).unwrap();
+    memory.increment().unwrap();
+    let expected = BigInt::from(CellNumber::MAX) * 2 + 1;
+    assert_eq!(*memory.cells()[0].value(), expected);
+    memory.next().unwrap();
+    memory.decrement().unwrap();
+    memory.add(CellNumber::MIN).unwrap();
+    let expected = BigInt::from(CellNumber::MIN) - 1;
+    assert_eq!(*memory.cells()[1].value(), expected);
+}
+
+#[cfg(feature = "bignum")]
+#[test]
+fn unbounded_cells_do_not_wrap() {
+    // Prints 1 if 16 * 16 is not zero.
+    let script = "++++++++++++++++[>++++++++++++++++<-]>[[-]>+<]>.";
+    let memory: Memory<BigInt> = Memory::from(&unbounded());
+    assert_eq!(run(script, memory), (vec![1], None));
+    let memory: Memory = Memory::from(&MemoryOptions::new());
+    assert_eq!(run(script, memory), (vec![0], None));
+}
+
+#[cfg(feature = "bignum")]
+#[test]
+fn only_big_cells_are_unbounded() {
+    let options = unbounded();
+    assert_eq!(CellType::fitting(&options), CellType::Big);
+    assert!(CellType::Big.holds(&options));
+    assert!(!CellType::I128.holds(&options));
+    assert!(!CellType::U8.holds(&options));
+    assert!(CellType::Big.holds(&MemoryOptions::new()));
+    assert_eq!("big".parse::<CellType>().unwrap(), CellType::Big);
+    assert_eq!(CellType::Big.to_string(), "big");
+}
+
+#[test]
+fn zero_is_the_lower_bound_when_0_is_out_of_range() {
+    assert_eq!(bounds(0, 255).zero(), 0);
+    assert_eq!(bounds(-10, 10).zero(), 0);
+    assert_eq!(bounds(-10, 0).zero(), 0);
+    assert_eq!(bounds(1, 256).zero(), 1);
+    assert_eq!(bounds(-300, -45).zero(), -300);
+    let mut options = bounds(1, 256);
+    options.cell_mode(CellMode::Unbounded);
+    assert_eq!(options.zero(), 0);
+    let memory: Memory = Memory::from(&bounds(1, 256));
+    assert_eq!(memory.cells()[0].number(), 1);
+}
+
+/// Gets the numbers in the cells of `memory`.
+fn numbers(memory: &Memory) -> Vec<CellNumber> {
+    return memory.cells().iter().map(|cell| cell.number()).collect();
+}
+
+#[test]
+fn preloaded_cells_are_written_before_the_program_starts() {
+    let mut options = MemoryOptions::new();
+    options.initial_length(6).preload(Preload::numbers([7, 0, 255]).at(2));
+    assert_eq!(numbers(&Memory::from(&options)), [0, 0, 7, 0, 255, 0]);
+    options.preload(Preload::text("Hé"));
+    assert_eq!(numbers(&Memory::from(&options)), [72, 233, 0, 0, 0, 0]);
+    options.lower_bound(1).upper_bound(256);
+    options.preload(Preload::bytes(b"\x00A"));
+    assert_eq!(numbers(&Memory::from(&options)), [1, 66, 1, 1, 1, 1]);
+    let sparse: SparseMemory = SparseMemory::from(&options);
+    assert_eq!(sparse.cell(1).number(), 66);
+    assert_eq!(run(">.", Memory::<CellNumber>::from(&options)).0, [65]);
+}
+
+#[test]
+fn preloaded_cells_have_to_fit() {
+    let mut options = MemoryOptions::new();
+    options.initial_length(2).preload(Preload::text("abc"));
+    assert!(!options.is_valid());
+    options.variable_length(true);
+    assert!(options.is_valid());
+    assert_eq!(numbers(&Memory::from(&options)), [97, 98, 99]);
+    let mut options = MemoryOptions::new();
+    options.preload(Preload::numbers([256]));
+    assert!(!options.is_valid());
+    options.preload(Preload::text("é"));
+    assert!(options.is_valid());
+    options.preload(Preload::text("\u{100}"));
+    assert!(!options.is_valid());
+    options.lower_bound(1).upper_bound(256);
+    options.preload(Preload::text("\u{ff}"));
+    assert!(options.is_valid());
+    options.preload(Preload::numbers([0]));
+    assert!(!options.is_valid());
+}
+
+#[test]
+fn preloaded_numbers_from_text() {
+    let preload = Preload::parse_numbers("72, 105,-1\n 0").unwrap();
+    assert_eq!(preload.values(0).collect::<Vec<_>>(), [72, 105, -1, 0]);
+    assert_eq!((preload.offset(), preload.end()), (0, 4));
+    assert!(Preload::parse_numbers("").unwrap().is_empty());
+    assert!(Preload::parse_numbers("1, two").is_err());
+    assert!(Preload::parse_numbers("1.5").is_err());
+}
+
+#[test]
+fn preloaded_files() {
+    let path = std::env::temp_dir().join("brainfuck-preload-test.bin");
+    std::fs::write(&path, b"\xff\x00\n").unwrap();
+    let preload = Preload::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(preload, Preload::bytes([255, 0, 10]));
+    assert!(Preload::from_file(&path).is_err());
+}
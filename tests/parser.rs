@@ -0,0 +1,192 @@
+//! Checks that [`BfParser::parse_reader`] reads programs exactly like
+//! [`BfParser::parse_string`]: it finds the same commands in the programs
+//! the grammar accepts, and rejects the others with the first error
+//! [`BfParser::check_string`] finds in them, however the program is split
+//! into chunks. [`BfParser::check_reader`] then reports every problem
+//! [`BfParser::check_string`] does.
+
+#![allow(clippy::needless_return)]
+
+use std::io::{self, Read};
+
+use brainfucklib::{
+    code::{BfProgram, BfToken},
+    debug::{Diagnostic, Diagnostics, ErrEvent},
+    parser::{BfParser, NewParser, NormalParser},
+};
+
+/// A [`Read`]er which gives out one byte at a time, so that comments and
+/// characters are split between reads.
+struct OneByte<'a>(&'a [u8]);
+
+impl Read for OneByte<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        return match (self.0.split_first(), buffer.first_mut()) {
+            (Some((&byte, rest)), Some(first)) => {
+                *first = byte;
+                self.0 = rest;
+                Ok(1)
+            },
+            _ => Ok(0),
+        };
+    }
+}
+
+fn commands(program: &BfProgram) -> Vec<(String, usize, usize)> {
+    let tokens: &Vec<BfToken> = program.as_ref();
+    return tokens
+        .iter()
+        .map(|token| {
+            let span = token.span();
+            (token.command().to_string(), span.start(), span.end())
+        })
+        .collect();
+}
+
+fn shown(diagnostics: &Diagnostics) -> Vec<String> {
+    return diagnostics.iter().map(Diagnostic::to_string).collect();
+}
+
+/// Checks that `script` is read the same way from a string and from a
+/// [`Read`]er, whether it is read all at once or a byte at a time.
+fn same<P>(script: &str)
+where
+    P: BfParser
+{
+    let mut expected = BfProgram::new();
+    let parsed = P::parse_string(script, &mut expected);
+    let checked = P::check_string(script, &mut BfProgram::new());
+    for chunked in [false, true] {
+        let mut program = BfProgram::new();
+        let read = match chunked {
+            false => P::parse_reader(script.as_bytes(), &mut program),
+            true => P::parse_reader(OneByte(script.as_bytes()), &mut program),
+        };
+        match (&parsed, read) {
+            (Ok(_), Ok(_)) => {
+                assert_eq!(commands(&program), commands(&expected));
+            },
+            (Err(_), Err(ErrEvent::Error(error))) => {
+                let first = checked.iter().find(|d| d.is_error()).unwrap();
+                assert_eq!(
+                    Diagnostic::from(error).to_string(),
+                    first.to_string(),
+                    "{:?}",
+                    script
+                );
+            },
+            (parsed, read) => panic!(
+                "{:?} was parsed as {:?} but read as {:?}",
+                script,
+                parsed,
+                read
+            ),
+        }
+        let mut program = BfProgram::new();
+        let diagnostics = match chunked {
+            false => P::check_reader(script.as_bytes(), &mut program),
+            true => P::check_reader(OneByte(script.as_bytes()), &mut program),
+        };
+        assert_eq!(shown(&diagnostics), shown(&checked), "{:?}", script);
+    }
+}
+
+/// Calls `check` with every string of up to `length` characters taken
+/// from `alphabet`.
+fn every(alphabet: &[char], length: usize, check: &dyn Fn(&str)) {
+    let mut scripts = vec![String::new()];
+    for _i in 0..length {
+        let mut longer = Vec::new();
+        for script in scripts.iter() {
+            check(script);
+            for &character in alphabet {
+                let mut next = script.clone();
+                next.push(character);
+                longer.push(next);
+            }
+        }
+        scripts = longer;
+    }
+    scripts.iter().for_each(|script| check(script));
+}
+
+const ALPHABET: [char; 6] = ['[', ']', '#', '\n', '+', '\r'];
+
+#[test]
+fn new_parser_reads_every_short_program_alike() {
+    every(&ALPHABET, 5, &same::<NewParser>);
+}
+
+#[test]
+fn normal_parser_reads_every_short_program_alike() {
+    every(&ALPHABET, 5, &same::<NormalParser>);
+}
+
+#[test]
+fn longer_programs() {
+    let scripts = [
+        "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>\
+        ---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.",
+        "# A comment with [ and ] in it.\n+[->+<] # [\r\n-[]",
+        "[\u{e9}+\u{1F600}] # \u{e9}]\n]",
+        "+[[-]\n# ]\n",
+        "[]]] [[",
+    ];
+    for script in scripts.iter() {
+        same::<NewParser>(script);
+        same::<NormalParser>(script);
+    }
+}
+
+#[test]
+fn every_unmatched_bracket_is_streamed() {
+    let script = "]+[[]-]] # ]\n>[";
+    for chunked in [false, true] {
+        let mut program = BfProgram::new();
+        let diagnostics = match chunked {
+            false => NewParser::check_reader(script.as_bytes(), &mut program),
+            true => NewParser::check_reader(
+                OneByte(script.as_bytes()),
+                &mut program
+            ),
+        };
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.name(), d.span().unwrap().start()))
+            .collect();
+        assert_eq!(found, [
+            ("UnmatchedRightBracket", 0),
+            ("EmptyLoop", 3),
+            ("UnmatchedRightBracket", 7),
+            ("UnmatchedLeftBracket", 14),
+        ]);
+    }
+}
+
+#[test]
+fn input_is_split_off_at_the_first_bang() {
+    assert_eq!(NormalParser::split_input(",.!ab!c"), (",.", Some("ab!c")));
+    assert_eq!(NormalParser::split_input("+#!\n-"), ("+#", Some("\n-")));
+    assert_eq!(NormalParser::split_input(",."), (",.", None));
+    assert_eq!(NewParser::split_input("+#!\n-!x"), ("+#!\n-", Some("x")));
+    assert_eq!(NewParser::split_input("# !\r!"), ("# !\r", Some("")));
+}
+
+#[test]
+fn invalid_utf8_is_rejected() {
+    for bytes in [&b"+\xff+"[..], b"+\xc3", b"\xc3+", b"#\xed\xa0\x80\n"] {
+        for chunked in [false, true] {
+            let mut program = BfProgram::new();
+            let read = match chunked {
+                false => NewParser::parse_reader(bytes, &mut program),
+                true => NewParser::parse_reader(OneByte(bytes), &mut program),
+            };
+            match read {
+                Err(ErrEvent::Error(error)) => {
+                    assert_eq!(error.kind().to_string(), "BadProgram");
+                },
+                other => panic!("{:?} was read as {:?}", bytes, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Checks that [`Preprocessor`] expands includes and macros, that the
+//! [`Span`]s of the expanded program lead back to the files they came from,
+//! and that it refuses to expand a program past [`MAX_LENGTH`] bytes,
+//! pointing at what made it too long, instead of running out of memory.
+
+#![allow(clippy::needless_return)]
+
+use std::{
+    env,
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use brainfucklib::{
+    code::{BfProgram, BfToken, SourceMap, Span},
+    debug::BfError,
+    parser::{preprocess::MAX_LENGTH, BfParser, NewParser, Preprocessor},
+};
+
+fn process(code: &str) -> Result<String, BfError> {
+    let mut source = SourceMap::new("<raw>", code);
+    return Preprocessor::new()
+        .process(code, &mut source)
+        .map(|expansion| expansion.text().to_string());
+}
+
+/// Creates an empty folder named after `name`, so that tests running at once
+/// do not share files.
+fn folder(name: &str) -> PathBuf {
+    let folder = env::temp_dir().join(format!(
+        "brainfuck-preprocess-{}-{}",
+        process::id(),
+        name
+    ));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    return folder;
+}
+
+/// Writes every file in `files` to `folder`.
+fn write(folder: &Path, files: &[(&str, &str)]) {
+    for (name, text) in files {
+        fs::write(folder.join(name), text).unwrap();
+    }
+}
+
+/// Preprocesses the file at `path`, giving back its [`SourceMap`] as well.
+fn process_file(path: &Path) -> (SourceMap, Result<String, BfError>) {
+    let text = fs::read_to_string(path).unwrap();
+    let mut source = SourceMap::new(path.to_str().unwrap(), &text);
+    let processed = Preprocessor::new()
+        .process(&text, &mut source)
+        .map(|expansion| expansion.text().to_string());
+    return (source, processed);
+}
+
+/// Renders `error`, leaving out the path of `folder`.
+fn render(error: &BfError, source: &SourceMap, folder: &Path) -> String {
+    let folder = format!("{}/", folder.display());
+    return error.render(source).replace(&folder, "");
+}
+
+/// Gets the file, start and end of `span`.
+fn place(span: Span) -> (usize, usize, usize) {
+    return (span.file(), span.start(), span.end());
+}
+
+/// Checks that `code` is rejected because of the use of a macro which
+/// starts at `start` and ends at `end`.
+fn too_long(code: &str, start: usize, end: usize) {
+    let error = process(code).unwrap_err();
+    assert_eq!(error.kind().to_string(), "PreprocessingError");
+    let span = error.span().unwrap();
+    assert_eq!((span.start(), span.end()), (start, end), "{:?}", code);
+}
+
+#[test]
+fn repeats_below_the_limit() {
+    let code = format!("#define fill >*{}\n@fill", MAX_LENGTH);
+    assert_eq!(process(&code).unwrap().matches('>').count(), MAX_LENGTH);
+}
+
+#[test]
+fn repeating_past_the_limit() {
+    let code = format!("#define fill >*{}\n+@fill", MAX_LENGTH + 1);
+    too_long(&code, code.len() - 5, code.len());
+    let code = format!("#define fill (+>)*{}\n@fill", MAX_LENGTH / 2 + 1);
+    too_long(&code, code.len() - 5, code.len());
+}
+
+#[test]
+fn repeating_past_the_size_of_memory() {
+    let code = "#define fill (+>)*18446744073709551615\n@fill";
+    too_long(code, code.len() - 5, code.len());
+    let code = "#define fill(n) >*$n\n@fill(9223372036854775807)";
+    too_long(code, 21, code.len());
+}
+
+#[test]
+fn nested_repeats_past_the_limit() {
+    let code = "#define a +*4096\n\
+        #define b @a*4096\n\
+        #define c @b*4096\n\
+        >@c";
+    too_long(code, code.len() - 2, code.len());
+}
+
+#[test]
+fn uses_past_the_limit_together() {
+    let code = format!(
+        "#define fill >*{}\n@fill\n@fill",
+        MAX_LENGTH / 2 + 1
+    );
+    too_long(&code, code.len() - 5, code.len());
+}
+
+#[test]
+fn includes_past_the_limit_together() {
+    let folder = folder("too-long");
+    let big = "+".repeat(MAX_LENGTH / 4 + 1);
+    write(&folder, &[
+        ("main.b", "#include \"big.b\"\n".repeat(4).as_str()),
+        ("big.b", &big),
+    ]);
+    let (_source, processed) = process_file(&folder.join("main.b"));
+    let error = processed.unwrap_err();
+    assert_eq!(error.kind().to_string(), "PreprocessingError");
+    assert_eq!(place(error.span().unwrap()), (4, 0, big.len()));
+}
+
+#[test]
+fn includes_and_macros() {
+    let folder = folder("includes");
+    write(&folder, &[
+        ("main.b", "+\n#include \"lib.b\"\n@twice(>)\n"),
+        ("lib.b", "# lib\n#define twice(x) $x*2\n-[]\n"),
+    ]);
+    let path = folder.join("main.b");
+    let text = fs::read_to_string(&path).unwrap();
+    let mut source = SourceMap::new(path.to_str().unwrap(), &text);
+    let expansion = Preprocessor::new().process(&text, &mut source).unwrap();
+    assert_eq!(expansion.text(), "+\n# lib\n-[]\n>>\n");
+    assert_eq!(source.file_count(), 2);
+    let mut program = BfProgram::new();
+    NewParser::parse_string(expansion.text(), &mut program).unwrap();
+    expansion.map_program(&mut program);
+    let tokens: &Vec<BfToken> = program.as_ref();
+    let places: Vec<_> = tokens.iter().map(|t| place(t.span())).collect();
+    assert_eq!(places, [
+        (0, 0, 1),
+        (1, 28, 29),
+        (1, 29, 30),
+        (1, 30, 31),
+        (0, 19, 28),
+        (0, 19, 28),
+    ]);
+}
+
+#[test]
+fn spans_in_the_middle_of_the_text() {
+    let code = "#define m +-\n>@m<\n";
+    let mut source = SourceMap::new("<raw>", code);
+    let expansion = Preprocessor::new().process(code, &mut source).unwrap();
+    assert_eq!(expansion.text(), ">+-<\n");
+    let map = |start, end| place(expansion.map_span(Span::new(start, end)));
+    assert_eq!(map(0, 1), (0, 13, 14));
+    assert_eq!(map(1, 2), (0, 14, 16));
+    assert_eq!(map(2, 3), (0, 14, 16));
+    assert_eq!(map(3, 5), (0, 16, 18));
+    // A span which goes past the end of what was copied is cut short.
+    assert_eq!(map(0, 4), (0, 13, 14));
+}
+
+#[test]
+fn errors_in_included_files() {
+    let folder = folder("errors");
+    write(&folder, &[
+        ("main.b", "+\n#include \"lib.b\"\n"),
+        ("lib.b", "#define f(a, b) $a$b\n@f(+)\n"),
+    ]);
+    let (source, processed) = process_file(&folder.join("main.b"));
+    assert_eq!(render(&processed.unwrap_err(), &source, &folder), concat!(
+        "error[PreprocessingError]: `@f` takes 2 arguments but 1 was given\n",
+        " --> lib.b:2:1\n",
+        "  |\n",
+        "1 | #define f(a, b) $a$b\n",
+        "  |         ^ `@f` is defined here\n",
+        "2 | @f(+)\n",
+        "  | ^^^^^\n",
+    ));
+}
+
+#[test]
+fn cyclic_includes() {
+    let folder = folder("cycles");
+    write(&folder, &[
+        ("a.b", "#include \"b.b\"\n"),
+        ("b.b", "+\n#include \"a.b\"\n"),
+        ("self.b", "#include \"self.b\""),
+    ]);
+    let (source, processed) = process_file(&folder.join("a.b"));
+    let error = processed.unwrap_err();
+    assert_eq!(render(&error, &source, &folder), concat!(
+        "error[PreprocessingError]: \"a.b\" includes itself\n",
+        " --> b.b:2:10\n",
+        "  |\n",
+        "2 | #include \"a.b\"\n",
+        "  |          ^^^^^\n",
+    ));
+    let (_source, processed) = process_file(&folder.join("self.b"));
+    let error = processed.unwrap_err();
+    assert!(error.description().ends_with("includes itself"));
+    assert_eq!(place(error.span().unwrap()), (0, 9, 17));
+}
+
+#[test]
+fn missing_includes() {
+    let folder = folder("missing");
+    write(&folder, &[("main.b", "#include \"nope.b\"")]);
+    let (_source, processed) = process_file(&folder.join("main.b"));
+    let error = processed.unwrap_err();
+    assert!(error.description().starts_with("Could not open file"));
+    assert_eq!(place(error.span().unwrap()), (0, 9, 17));
+    let error = process("#include nope.b").unwrap_err();
+    assert_eq!(
+        error.description(),
+        "Expected the name of a file in quotes after `#include`"
+    );
+}
+
+#[test]
+fn argument_counts() {
+    let messages = [
+        ("#define f(a, b) $a$b\n@f(+)", "takes 2 arguments but 1 was given"),
+        ("#define f(a) $a\n@f", "takes 1 argument but 0 were given"),
+        ("#define f +\n@f(+, -)", "takes 0 arguments but 2 were given"),
+    ];
+    for (code, message) in messages.iter() {
+        let error = process(code).unwrap_err();
+        assert_eq!(error.description(), format!("`@f` {}", message));
+        let span = error.span().unwrap();
+        assert_eq!(span.end(), code.len(), "{:?}", code);
+        assert_eq!(error.notes()[0].0.start(), 8, "{:?}", code);
+    }
+    assert_eq!(process("#define f(a, b) $b$a\n@f(+, -)").unwrap(), "-+");
+}
+
+#[test]
+fn macros_which_cannot_be_expanded() {
+    let error = process("@g").unwrap_err();
+    assert_eq!(error.description(), "`@g` is not defined");
+    let error = process("#define f @f\n@f").unwrap_err();
+    assert_eq!(error.description(), "`@f` uses itself");
+    let error = process("#define f $x\n@f").unwrap_err();
+    assert_eq!(error.description(), "`$x` is not a parameter");
+}
@@ -0,0 +1,148 @@
+//! Checks how a [`SourceMap`] finds the lines and columns of a program, and
+//! how [`BfError::render`] shows an error with the lines it points at.
+
+#![allow(clippy::needless_return)]
+
+use brainfucklib::{
+    code::{BfProgram, SourceMap, Span},
+    debug::{BfError, BfErrorKind, ErrEvent},
+    parser::{BfParser, NormalParser},
+};
+
+/// Gets the error for the first unmatched bracket in `script`.
+fn unmatched(script: &str) -> BfError {
+    let mut program = BfProgram::new();
+    NormalParser::parse_string(script, &mut program).unwrap();
+    program.populate_map().unwrap();
+    return match program.calculate_map() {
+        Err(ErrEvent::Error(error)) => error,
+        other => panic!("{:?} has no unmatched bracket: {:?}", script, other),
+    };
+}
+
+/// Renders the error for the first unmatched bracket in `script`.
+fn render(script: &str) -> String {
+    return unmatched(script).render(&SourceMap::new("test.b", script));
+}
+
+#[test]
+fn lines_and_columns() {
+    let source = SourceMap::new("test.b", "+é\n\t[x");
+    assert_eq!(source.name(), "test.b");
+    assert_eq!(source.line_count(), 2);
+    let columns: Vec<(usize, usize)> = (0..=8)
+        .map(|offset| source.line_column(Span::new(offset, offset)))
+        .collect();
+    assert_eq!(columns, [
+        (1, 1),
+        (1, 2),
+        (1, 2),
+        (1, 3),
+        (2, 1),
+        (2, 2),
+        (2, 3),
+        (2, 4),
+        (2, 4),
+    ]);
+    assert_eq!(source.line(0, 0), None);
+    assert_eq!(source.line(0, 1), Some("+é"));
+    assert_eq!(source.line(0, 2), Some("\t[x"));
+    assert_eq!(source.line(0, 3), None);
+    assert_eq!(source.location(Span::new(5, 6)), "test.b:2:2");
+}
+
+#[test]
+fn line_breaks_are_left_out() {
+    let source = SourceMap::new("test.b", "+\r\n-\r\n");
+    assert_eq!(source.line_count(), 3);
+    assert_eq!(source.line(0, 1), Some("+"));
+    assert_eq!(source.line(0, 2), Some("-"));
+    assert_eq!(source.line(0, 3), Some(""));
+    assert_eq!(source.line_column(Span::new(3, 4)), (2, 1));
+}
+
+#[test]
+fn unclosed_loops() {
+    assert_eq!(render("+[>+<-"), concat!(
+        "error[UnmatchedLeftBracket]: Unmatched [\n",
+        " --> test.b:1:2\n",
+        "  |\n",
+        "1 | +[>+<-\n",
+        "  |  ^\n",
+        "  |       ^ the program ends before this loop is closed\n",
+    ));
+}
+
+#[test]
+fn loops_closed_too_often() {
+    assert_eq!(render("# a\r\n+[\r\n]]"), concat!(
+        "error[UnmatchedRightBracket]: Unmatched ]\n",
+        " --> test.b:3:2\n",
+        "  |\n",
+        "2 | +[\n",
+        "  |  ^ the last `[` before it is already closed\n",
+        "3 | ]]\n",
+        "  |  ^\n",
+    ));
+}
+
+#[test]
+fn carets_line_up_with_tabs_and_wide_characters() {
+    assert_eq!(render("\t+]"), concat!(
+        "error[UnmatchedRightBracket]: Unmatched ]\n",
+        " --> test.b:1:3\n",
+        "  |\n",
+        "1 | \t+]\n",
+        "  | \t ^\n",
+    ));
+    assert_eq!(render("é→[")
+        .lines()
+        .nth(4), Some("  |   ^"));
+}
+
+#[test]
+fn lines_far_apart() {
+    let script = format!("[{}[]", "\n".repeat(11));
+    assert_eq!(render(&script), concat!(
+        "error[UnmatchedLeftBracket]: Unmatched [\n",
+        "  --> test.b:1:1\n",
+        "   |\n",
+        " 1 | [\n",
+        "   | ^\n",
+        "  ...\n",
+        "12 | []\n",
+        "   |  ^ the last `]` closes a loop inside this one\n",
+    ));
+}
+
+#[test]
+fn spans_over_several_lines() {
+    let source = SourceMap::new("test.b", "+++\n[->\n+<]\n");
+    let error = BfError::new(BfErrorKind::CellOverflow, "Cell overflow")
+        .with_span(Span::new(1, 6))
+        .with_cell(-3);
+    assert_eq!(error.render(&source), concat!(
+        "error[CellOverflow]: Cell overflow\n",
+        " --> test.b:1:2\n",
+        "  |\n",
+        "1 | +++\n",
+        "  |  ^^\n",
+        "  |\n",
+        "  = cell: -3\n",
+    ));
+}
+
+#[test]
+fn errors_without_spans() {
+    let error = BfError::new(BfErrorKind::Other, "Something went wrong");
+    let source = SourceMap::new("test.b", "+");
+    assert_eq!(
+        error.render(&source),
+        "error[Other]: Something went wrong\n"
+    );
+    let error = unmatched("+[");
+    assert!(error.to_string().contains("\nPosition: 1 to 2"));
+    assert!(error.to_string().contains(
+        "\nNote (2 to 2): the program ends before this loop is closed"
+    ));
+}
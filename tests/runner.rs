@@ -0,0 +1,411 @@
+//! Checks that [`BfRunner`] reads the input of `,` from and writes the output
+//! of `.` to whatever it is given instead of the terminal.
+
+#![allow(clippy::needless_return)]
+
+use std::{cell::RefCell, env, fs, rc::Rc};
+
+use brainfucklib::{
+    code::{input::write_char, BfInput, BfProgram, BfRunner, InputMode},
+    debug::{BfDebugger, BfError, BfErrorKind, Debugger, ErrEvent},
+    parser::{BfParser, NormalParser},
+    vm::{CellMode, EofPolicy, Memory, MemoryOptions, PointerPolicy},
+};
+
+fn parse(script: &str) -> Box<BfProgram> {
+    let mut program = Box::new(BfProgram::new());
+    NormalParser::parse_string(script, &mut program).unwrap();
+    program.populate_map().unwrap();
+    program.calculate_map().unwrap();
+    return program;
+}
+
+fn memory() -> Box<Memory> {
+    return Box::new(MemoryOptions::new().into());
+}
+
+/// Runs `script` on a tape made with `options`, reading `input` in `mode`,
+/// and gets its output.
+fn run_in(
+    mode: InputMode,
+    script: &str,
+    options: &MemoryOptions,
+    input: &[u8]
+) -> Vec<u8> {
+    let memory: Memory = Memory::from(options);
+    let mut runner = BfRunner::with_buffers(
+        parse(script),
+        Box::new(memory),
+        None::<Rc<RefCell<BfDebugger>>>,
+        input,
+    ).with_input_mode(mode);
+    while runner.run_once().is_some() {}
+    return runner.output().clone();
+}
+
+fn run(script: &str, options: &MemoryOptions, input: &[u8]) -> Vec<u8> {
+    return run_in(InputMode::default(), script, options, input);
+}
+
+/// Runs `script` on a tape made with `options`, and gets its output and the
+/// error it stopped with.
+fn run_with(
+    options: &MemoryOptions,
+    script: &str
+) -> (Vec<u8>, Option<BfError>) {
+    let debugger = Rc::new(RefCell::new(BfDebugger::new()));
+    let memory: Memory = Memory::from(options);
+    let mut runner = BfRunner::with_buffers(
+        parse(script),
+        Box::new(memory),
+        Some(Rc::clone(&debugger)),
+        "",
+    );
+    while runner.run_once().is_some() {}
+    let error = match debugger.borrow().last_event() {
+        Some(Err(ErrEvent::Error(error))) => Some(error.clone()),
+        _ => None,
+    };
+    return (runner.output().clone(), error);
+}
+
+/// Runs `script` on cells which go past their bounds in `mode`.
+fn run_cells(mode: CellMode, script: &str) -> (Vec<u8>, Option<BfError>) {
+    let mut options = MemoryOptions::new();
+    options.cell_mode(mode);
+    return run_with(&options, script);
+}
+
+/// Runs `script` on a tape of `length` cells which the pointer moves past
+/// according to `policy`.
+fn run_tape(
+    policy: PointerPolicy,
+    length: usize,
+    script: &str
+) -> (Vec<u8>, Option<BfError>) {
+    let mut options = MemoryOptions::new();
+    options.pointer_policy(policy).initial_length(length);
+    return run_with(&options, script);
+}
+
+/// Adds 256 to the second cell and prints it.
+const PAST_THE_TOP: &str = "++++++++++++++++[>++++++++++++++++<-]>.";
+
+const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>\
+    ---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+#[test]
+fn output_is_kept_in_a_buffer() {
+    let mut runner = BfRunner::with_buffers(
+        parse(HELLO),
+        memory(),
+        None::<Rc<RefCell<BfDebugger>>>,
+        "",
+    );
+    while runner.run_once().is_some() {}
+    assert_eq!(runner.output(), b"Hello World!\n");
+}
+
+#[test]
+fn input_comes_from_a_buffer() {
+    let mut runner = BfRunner::with_buffers(
+        parse(",+."),
+        memory(),
+        None::<Rc<RefCell<BfDebugger>>>,
+        "a\n",
+    );
+    while runner.run_once().is_some() {}
+    assert_eq!(runner.output(), b"b");
+}
+
+#[test]
+fn any_reader_and_writer() {
+    let mut output = Vec::new();
+    let mut runner = BfRunner::with_io(
+        parse(",.,."),
+        memory(),
+        None::<Rc<RefCell<BfDebugger>>>,
+        &b"xy"[..],
+        &mut output,
+    );
+    while runner.run_once().is_some() {}
+    drop(runner);
+    assert_eq!(output, b"xy");
+}
+
+#[test]
+fn files() {
+    let folder = env::temp_dir()
+        .join(format!("brainfuck-runner-{}", std::process::id()));
+    fs::create_dir_all(&folder).unwrap();
+    let input = folder.join("input.txt");
+    let output = folder.join("output.txt");
+    fs::write(&input, "q\n").unwrap();
+    fs::write(&output, "this is truncated").unwrap();
+    let mut runner = BfRunner::with_files(
+        parse(",-.>++++++++++."),
+        memory(),
+        None::<Rc<RefCell<BfDebugger>>>,
+        &input,
+        &output,
+    ).unwrap();
+    while runner.run_once().is_some() {}
+    drop(runner);
+    assert_eq!(fs::read(&output).unwrap(), b"p\n");
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[test]
+fn missing_input_file() {
+    let missing = env::temp_dir().join("brainfuck-runner-missing/input.txt");
+    let runner = BfRunner::with_files(
+        parse(","),
+        memory(),
+        None::<Rc<RefCell<BfDebugger>>>,
+        &missing,
+        env::temp_dir().join("brainfuck-runner-missing-output.txt"),
+    );
+    assert!(runner.is_err());
+}
+
+/// Adds 47 to what `,` leaves in a cell which was 2 before, and prints it.
+const AFTER_EOF: &str = "++,+++++++++++++++++++++++++++++++++++++++++++++++.";
+
+#[test]
+fn end_of_input() {
+    let expected = [
+        (EofPolicy::Unchanged, b"1"),
+        (EofPolicy::Zero, b"/"),
+        (EofPolicy::Max, b"."),
+    ];
+    for (policy, output) in expected.iter() {
+        let mut options = MemoryOptions::new();
+        options.eof_policy(*policy);
+        assert_eq!(run(AFTER_EOF, &options, b""), *output, "{}", policy);
+    }
+    assert_eq!(MemoryOptions::new().on_eof(), EofPolicy::Unchanged);
+}
+
+#[test]
+fn cat_stops_at_the_end_of_input() {
+    let mut options = MemoryOptions::new();
+    options.eof_policy(EofPolicy::Zero);
+    assert_eq!(run(",[.,]", &options, b"a\nb\n"), b"a\nb\n");
+    options.eof_policy(EofPolicy::Max);
+    assert_eq!(run(",+[-.,+]", &options, b"a\nb\n"), b"a\nb\n");
+}
+
+#[test]
+fn end_of_input_policies_from_text() {
+    let policies = [
+        ("unchanged", EofPolicy::Unchanged),
+        ("zero", EofPolicy::Zero),
+        ("0", EofPolicy::Zero),
+        ("max", EofPolicy::Max),
+        ("-1", EofPolicy::Max),
+    ];
+    for (text, policy) in policies.iter() {
+        assert_eq!(text.parse::<EofPolicy>().unwrap(), *policy);
+    }
+    assert!("none".parse::<EofPolicy>().is_err());
+}
+
+#[test]
+fn bytes_are_read_one_at_a_time() {
+    let options = MemoryOptions::new();
+    assert_eq!(run(",.,.,.", &options, b"ab\ncd"), b"ab\n");
+    // A newline is read as 10.
+    assert_eq!(run(",----------[+.],.", &options, b"\nx"), b"x");
+}
+
+#[test]
+fn bytes_are_written_back_as_they_were_read() {
+    let mut options = MemoryOptions::new();
+    options.eof_policy(EofPolicy::Zero);
+    let input = b"h\xc3\xa9\xff\n";
+    assert_eq!(run(",[.,]", &options, input), input);
+}
+
+#[test]
+fn lines_keep_their_first_character() {
+    let options = MemoryOptions::new();
+    let output = run_in(InputMode::Line, ",.,.", &options, b"ab\ncd\n");
+    assert_eq!(output, b"ac");
+}
+
+#[test]
+fn characters_are_read_whole() {
+    let options = MemoryOptions::new();
+    let input = "\u{e9}a".as_bytes();
+    assert_eq!(run_in(InputMode::Char, ",.,.", &options, input), input);
+    let bytes = &b"\xff\xc3\xa9\xe2\x82"[..];
+    let mut input = BfInput::new(bytes, InputMode::Char);
+    assert_eq!(input.read_char().unwrap(), Some(char::REPLACEMENT_CHARACTER));
+    assert_eq!(input.read_char().unwrap(), Some('\u{e9}'));
+    assert_eq!(input.read_char().unwrap(), Some(char::REPLACEMENT_CHARACTER));
+    assert_eq!(input.read_char().unwrap(), None);
+}
+
+#[test]
+fn output_follows_the_input_mode() {
+    let expected = [
+        (InputMode::Byte, &[0xff][..]),
+        (InputMode::Char, "\u{ff}".as_bytes()),
+        (InputMode::Line, "\u{ff}".as_bytes()),
+    ];
+    for (mode, bytes) in expected.iter() {
+        let mut output = Vec::new();
+        write_char(&mut output, '\u{ff}', *mode).unwrap();
+        assert_eq!(output, *bytes, "{}", mode);
+    }
+}
+
+#[test]
+fn input_modes_from_text() {
+    for mode in [InputMode::Line, InputMode::Byte, InputMode::Char].iter() {
+        assert_eq!(mode.to_string().parse::<InputMode>().unwrap(), *mode);
+    }
+    assert!("word".parse::<InputMode>().is_err());
+    assert_eq!(InputMode::default(), InputMode::Byte);
+}
+
+/// Runs `script` like [`run_cells`], checking that it does not stop with an
+/// error.
+fn run_cells_ok(mode: CellMode, script: &str) -> Vec<u8> {
+    let (output, error) = run_cells(mode, script);
+    assert!(error.is_none(), "{:?}", error);
+    return output;
+}
+
+#[test]
+fn cells_wrap_around() {
+    assert_eq!(run_cells_ok(CellMode::Wrap, "-.+."), [255, 0]);
+    assert_eq!(run_cells_ok(CellMode::Wrap, PAST_THE_TOP), [0]);
+    assert_eq!(MemoryOptions::new().on_overflow(), CellMode::Wrap);
+}
+
+#[test]
+fn cells_saturate() {
+    assert_eq!(run_cells_ok(CellMode::Saturate, "-.+."), [0, 1]);
+    let script = format!("{}-.", PAST_THE_TOP);
+    assert_eq!(run_cells_ok(CellMode::Saturate, &script), [255, 254]);
+}
+
+#[test]
+fn cells_raise_errors() {
+    let (output, error) = run_cells(CellMode::Error, "+.>-.");
+    assert_eq!(output, [1]);
+    let error = error.unwrap();
+    assert!(matches!(error.kind(), BfErrorKind::CellOverflow));
+    assert_eq!(
+        error.description(),
+        "Cell would go below its lower bound of 0."
+    );
+    assert_eq!(error.cell(), Some(1));
+    let span = error.span().unwrap();
+    assert_eq!((span.start(), span.end()), (3, 4));
+
+    let (output, error) = run_cells(CellMode::Error, PAST_THE_TOP);
+    assert_eq!(output, []);
+    let error = error.unwrap();
+    assert!(matches!(error.kind(), BfErrorKind::CellOverflow));
+    assert_eq!(
+        error.description(),
+        "Cell would go above its upper bound of 255."
+    );
+}
+
+#[test]
+fn cell_modes_from_text() {
+    for mode in [CellMode::Wrap, CellMode::Saturate, CellMode::Error] {
+        assert_eq!(mode.to_string().parse::<CellMode>().unwrap(), mode);
+    }
+    assert!("clamp".parse::<CellMode>().is_err());
+}
+
+#[test]
+fn pointer_wraps_around() {
+    let (output, error) = run_tape(PointerPolicy::Wrap, 4, "+<++.>.>>>>.");
+    assert_eq!(output, [2, 1, 1]);
+    assert!(error.is_none());
+    assert_eq!(MemoryOptions::new().on_out_of_bounds(), PointerPolicy::Wrap);
+}
+
+#[test]
+fn tape_grows_both_ways() {
+    let script = "+<<<++.>>>.>>>>>+++.<<<<<.";
+    let (output, error) = run_tape(PointerPolicy::Grow, 2, script);
+    assert_eq!(output, [2, 1, 3, 1]);
+    assert!(error.is_none());
+    let mut options = MemoryOptions::new();
+    options.variable_length(true);
+    assert_eq!(options.on_out_of_bounds(), PointerPolicy::Grow);
+    assert!(options.is_variable_length());
+}
+
+#[test]
+fn infinite_tapes_run_like_growing_ones() {
+    let script = "+<<<++.>>>.>>>>>+++.<<<<<.[<]+++.";
+    let grown = run_tape(PointerPolicy::Grow, 2, script);
+    let (output, error) = run_tape(PointerPolicy::Infinite, 2, script);
+    assert_eq!(output, grown.0);
+    assert!(error.is_none());
+}
+
+#[test]
+fn pointer_raises_errors() {
+    let (output, error) = run_tape(PointerPolicy::Error, 3, "+.<.");
+    assert_eq!(output, [1]);
+    let error = error.unwrap();
+    assert!(matches!(error.kind(), BfErrorKind::OutOfBounds));
+    assert_eq!(
+        error.description(),
+        "Pointer would move from cell 0 to cell -1, outside of the memory \
+        tape of 3 cells."
+    );
+    assert_eq!(error.cell(), Some(0));
+    let span = error.span().unwrap();
+    assert_eq!((span.start(), span.end()), (2, 3));
+
+    let (output, error) = run_tape(PointerPolicy::Error, 3, ">>+.>.");
+    assert_eq!(output, [1]);
+    let error = error.unwrap();
+    assert!(matches!(error.kind(), BfErrorKind::OutOfBounds));
+    assert_eq!(error.cell(), Some(2));
+}
+
+#[test]
+fn pointer_policies_from_text() {
+    let policies = [
+        PointerPolicy::Wrap,
+        PointerPolicy::Grow,
+        PointerPolicy::Error,
+    ];
+    for policy in policies {
+        let text = policy.to_string();
+        assert_eq!(text.parse::<PointerPolicy>().unwrap(), policy);
+    }
+    assert!("bounce".parse::<PointerPolicy>().is_err());
+}
+
+/// Goes below the lower bound and back, clears a cell and reads a character,
+/// all of which count from the lower bound when 0 is out of range.
+const FROM_THE_LOWER_BOUND: &str = "-.+.++[-]+.>,.[-],.";
+
+#[test]
+fn cells_count_from_the_lower_bound() {
+    for (lower, upper) in [(1, 256), (-300, -45)] {
+        let mut options = MemoryOptions::new();
+        options.lower_bound(lower).upper_bound(upper);
+        let output = run(FROM_THE_LOWER_BOUND, &options, b"A");
+        assert_eq!(output, [255, 0, 1, 65, 0]);
+    }
+}
+
+#[test]
+fn cells_count_from_zero_when_they_can() {
+    let mut options = MemoryOptions::new();
+    options.lower_bound(-10).upper_bound(245);
+    assert_eq!(run("+.-[-]+.", &options, b""), [1, 1]);
+    assert_eq!(run("---[+]+.", &options, b""), [1]);
+}
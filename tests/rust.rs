@@ -0,0 +1,233 @@
+//! Checks that the Rust code made by [`RustCompiler`], both as a `main.rs`
+//! and as a module, compiles without warnings and writes the same output as
+//! [`BfRunner`] given the same input, when `rustc` can be found.
+
+#![allow(clippy::needless_return)]
+
+use std::{
+    cell::RefCell,
+    env,
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+    rc::Rc,
+};
+
+use brainfucklib::{
+    code::{BfProgram, BfRunner},
+    compile::{BfCompiler, RustCompiler},
+    debug::BfDebugger,
+    parser::{BfParser, NormalParser},
+    vm::{EofPolicy, Memory, MemoryOptions, Preload},
+};
+
+/// The most commands the interpreter may run before the program is assumed
+/// to be stuck.
+const LIMIT: usize = 1_000_000;
+
+/// Runs the `run` function of the module in `program.rs` on stdin and
+/// stdout.
+const MODULE_MAIN: &str = "mod program;
+
+fn main() {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    program::run(&mut stdin.lock(), &mut stdout.lock()).unwrap();
+}
+";
+
+fn parse(script: &str) -> BfProgram {
+    let mut program = BfProgram::new();
+    NormalParser::parse_string(script, &mut program).unwrap();
+    program.populate_map().unwrap();
+    program.calculate_map().unwrap();
+    return program;
+}
+
+fn interpret(script: &str, options: &MemoryOptions, input: &[u8]) -> Vec<u8> {
+    let memory: Memory = Memory::from(options);
+    let mut runner = BfRunner::with_buffers(
+        Box::new(parse(script)),
+        Box::new(memory),
+        None::<Rc<RefCell<BfDebugger>>>,
+        input,
+    );
+    for _i in 0..LIMIT {
+        if runner.run_once().is_none() {
+            return runner.output().clone();
+        }
+    }
+    panic!("{:?} did not finish after {} commands", script, LIMIT);
+}
+
+/// Gets `rustc`, unless it cannot be run.
+fn rustc() -> Option<String> {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let found = Command::new(&rustc)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    return match found {
+        true => Some(rustc),
+        false => None,
+    };
+}
+
+/// Builds `main` into `binary` and runs it on `input`.
+fn build_and_run(
+    rustc: &str,
+    main: &Path,
+    binary: &Path,
+    input: &[u8]
+) -> Vec<u8> {
+    let built = Command::new(rustc)
+        .args(["--edition", "2018", "-D", "warnings", "-o"])
+        .arg(binary)
+        .arg(main)
+        .output()
+        .unwrap();
+    assert!(
+        built.status.success(),
+        "{} did not compile:\n{}",
+        main.display(),
+        String::from_utf8_lossy(&built.stderr)
+    );
+    let mut child = Command::new(binary)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    return child.wait_with_output().unwrap().stdout;
+}
+
+/// Checks that `script` writes the same output when it is compiled, as a
+/// `main.rs` and as a module, as when it is interpreted.
+fn same(name: &str, script: &str, options: &MemoryOptions, input: &[u8]) {
+    let expected = interpret(script, options, input);
+    let rustc = match rustc() {
+        Some(rustc) => rustc,
+        None => return,
+    };
+    let folder = env::temp_dir()
+        .join(format!("brainfuck-rust-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&folder).unwrap();
+    let program = parse(script);
+
+    let mut source = Vec::new();
+    RustCompiler::compile(&program, options, &mut source).unwrap();
+    let main = folder.join("main.rs");
+    fs::write(&main, source).unwrap();
+    let binary = folder.join("main");
+    let output = build_and_run(&rustc, &main, &binary, input);
+    assert_eq!(output, expected, "{:?} as a main.rs", script);
+
+    let mut source = Vec::new();
+    RustCompiler::compile_module(&program, options, &mut source).unwrap();
+    fs::write(folder.join("program.rs"), source).unwrap();
+    let main = folder.join("module.rs");
+    fs::write(&main, MODULE_MAIN).unwrap();
+    let binary = folder.join("module");
+    let output = build_and_run(&rustc, &main, &binary, input);
+    assert_eq!(output, expected, "{:?} as a module", script);
+
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>\
+    ---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+#[test]
+fn hello_world() {
+    same("hello", HELLO, &MemoryOptions::new(), b"");
+}
+
+#[test]
+fn cat() {
+    let mut options = MemoryOptions::new();
+    options.eof_policy(EofPolicy::Zero);
+    same("cat", ",[.,]", &options, b"h\xc3\xa9\x7f\n");
+    same("empty", "", &options, b"");
+}
+
+#[test]
+fn end_of_input() {
+    for (name, policy) in [
+        ("unchanged", EofPolicy::Unchanged),
+        ("zero", EofPolicy::Zero),
+    ] {
+        let mut options = MemoryOptions::new();
+        options.eof_policy(policy);
+        same(name, "+++,>,,.<.", &options, b"a");
+    }
+}
+
+/// Moves off the left end of the tape one cell at a time, and then several
+/// cells at once.
+const GROW_LEFT: &str = "+>+>+>+[<]<<++.>>>.<<<<<<<<<<<<+++.>>>>>>>>>>>>>>>.";
+
+#[test]
+fn tape() {
+    let mut options = MemoryOptions::new();
+    options.initial_length(4);
+    same("wrap", "+>++>+++>++++>+++++.<<<<.", &options, b"");
+    options.variable_length(true);
+    same("grow", "+>++>+++>++++>+++++.<<<<.", &options, b"");
+    same("grow-left", GROW_LEFT, &options, b"");
+}
+
+#[test]
+fn loops() {
+    let options = MemoryOptions::new();
+    same("multiply", "+++++[>+++++++++++++<-]>.", &options, b"");
+    let scan = "+>+>+>+<<<[>]+++++++++++++++++++++++++++++++++.";
+    same("scan", scan, &options, b"");
+}
+
+#[test]
+fn cells_wrapped_by_hand() {
+    let mut options = MemoryOptions::new();
+    options.upper_bound(100);
+    let mut source = Vec::new();
+    RustCompiler::compile(&parse("+."), &options, &mut source).unwrap();
+    let source = String::from_utf8(source).unwrap();
+    assert!(source.contains("type Cell = i128;"));
+    assert!(source.contains("const RANGE: Cell = 101;"));
+    let script = "++++++++++[>++++++++++++<-]>.[-]++++++++++[>+++++<-]>+.";
+    same("wrapped", script, &options, b"");
+}
+
+/// Goes below the lower bound and back, clears a cell and reads a character,
+/// all of which count from the lower bound when 0 is out of range.
+const FROM_THE_LOWER_BOUND: &str = "-.+.++[-]+.>,.[-],.";
+
+#[test]
+fn cells_count_from_the_lower_bound() {
+    for (name, lower, upper) in [("one", 1, 256), ("negative", -300, -45)] {
+        let mut options = MemoryOptions::new();
+        options.lower_bound(lower).upper_bound(upper);
+        same(name, FROM_THE_LOWER_BOUND, &options, b"A");
+    }
+}
+
+/// Prints the cells from the second one up to the first cell which is zero.
+const PRINT_PRELOAD: &str = ">[.>]";
+
+#[test]
+fn preloaded_cells() {
+    let mut options = MemoryOptions::new();
+    options.preload(Preload::text("Hi!").at(1));
+    same("text", PRINT_PRELOAD, &options, b"");
+    options.preload(Preload::numbers([1, 2, 3]).at(1));
+    same("numbers", PRINT_PRELOAD, &options, b"");
+    options.lower_bound(1).upper_bound(256);
+    options.preload(Preload::bytes(b"\x01ok").at(1));
+    same("relative", PRINT_PRELOAD, &options, b"");
+    let mut options = MemoryOptions::new();
+    options.initial_length(2).variable_length(true);
+    options.preload(Preload::text("longer").at(1));
+    same("longer", PRINT_PRELOAD, &options, b"");
+}
@@ -0,0 +1,162 @@
+//! Checks that [`MemorySnapshot`]s survive being saved and read back, and
+//! that broken snapshot files are rejected with [`ErrorKind::InvalidData`]
+//! instead of a panic.
+
+#![allow(clippy::needless_return)]
+
+use std::{cell::RefCell, io::ErrorKind, rc::Rc};
+
+use brainfucklib::{
+    code::{BfProgram, BfRunner},
+    debug::BfDebugger,
+    parser::{BfParser, NormalParser},
+    vm::{Memory, MemoryOptions, MemorySnapshot, PointerPolicy, Preload},
+};
+
+fn parse(script: &str) -> Box<BfProgram> {
+    let mut program = Box::new(BfProgram::new());
+    NormalParser::parse_string(script, &mut program).unwrap();
+    program.populate_map().unwrap();
+    program.calculate_map().unwrap();
+    return program;
+}
+
+/// Writes `snapshot` out as text.
+fn text(snapshot: &MemorySnapshot) -> String {
+    let mut text = Vec::new();
+    snapshot.write_to(&mut text).unwrap();
+    return String::from_utf8(text).unwrap();
+}
+
+fn read(text: &str) -> ErrorKind {
+    let result = MemorySnapshot::<u8>::read_from(&mut text.as_bytes());
+    return result.map(|_| ()).unwrap_err().kind();
+}
+
+#[test]
+fn round_trip() {
+    let mut options = MemoryOptions::new();
+    options.initial_length(16);
+    let memory: Memory = Memory::new(options);
+    let snapshot = memory.snapshot();
+    let mut text = Vec::new();
+    snapshot.write_to(&mut text).unwrap();
+    let read: MemorySnapshot = MemorySnapshot::read_from(&mut &text[..])
+        .unwrap();
+    assert_eq!(read.cells(), snapshot.cells());
+}
+
+/// Fills a few cells with different values, grows the tape to the left
+/// and comes back, and then goes on to print two characters.
+const PARTLY_RUN: &str = "+++>>++++++<+++++<<<+>>>>>>+.+.";
+
+#[test]
+fn round_trip_of_a_runner() {
+    let mut options = MemoryOptions::new();
+    options.initial_length(8).pointer_policy(PointerPolicy::Grow);
+    let memory: Memory = Memory::from(&options);
+    let mut runner = BfRunner::with_buffers(
+        parse(PARTLY_RUN),
+        Box::new(memory),
+        None::<Rc<RefCell<BfDebugger>>>,
+        "",
+    );
+    for _i in 0..PARTLY_RUN.len() - 4 {
+        runner.run_once().unwrap();
+    }
+    let snapshot = runner.snapshot();
+    assert_eq!(snapshot.program_pointer(), Some(PARTLY_RUN.len() - 4));
+    assert_eq!((snapshot.index(), snapshot.origin()), (6, 2));
+    assert_eq!(snapshot.pointer(), 4);
+    let saved = text(&snapshot);
+    assert!(saved.contains("\nindex 6\norigin 2\nprogram-pointer 27\n"));
+    assert!(saved.ends_with("\ncells 10\n1 0 3 5 6 0*5\n"), "{}", saved);
+    let read: MemorySnapshot = MemorySnapshot::read_from(&mut saved.as_bytes())
+        .unwrap();
+    assert_eq!(read, snapshot);
+    while runner.run_once().is_some() {}
+    assert_eq!(*runner.output(), [1, 2]);
+    runner.restore(&read);
+    while runner.run_once().is_some() {}
+    assert_eq!(*runner.output(), [1, 2, 1, 2]);
+    assert_eq!(runner.snapshot().pointer(), 4);
+}
+
+#[test]
+fn snapshots_do_not_change_with_the_memory() {
+    let mut options = MemoryOptions::new();
+    options.initial_length(4);
+    let memory: Memory = Memory::from(&options);
+    let mut runner = BfRunner::with_buffers(
+        parse("++>+"),
+        Box::new(memory),
+        None::<Rc<RefCell<BfDebugger>>>,
+        "",
+    );
+    runner.run_once().unwrap();
+    let snapshot = runner.snapshot();
+    while runner.run_once().is_some() {}
+    let finished = runner.snapshot();
+    assert_eq!(text(&snapshot).lines().last(), Some("1 0*3"));
+    assert_eq!(text(&finished).lines().last(), Some("2 1 0*2"));
+    for _i in 0..2 {
+        runner.restore(&snapshot);
+        while runner.run_once().is_some() {}
+        assert_eq!(runner.snapshot(), finished);
+    }
+}
+
+#[test]
+fn preloaded_cells_are_saved() {
+    for preload in [
+        Preload::text("Hé").at(3),
+        Preload::bytes(b"\x00\xfe"),
+        Preload::numbers([-1, 0, 1]).at(1),
+    ] {
+        let mut options = MemoryOptions::new();
+        options.lower_bound(-1).upper_bound(254).initial_length(8);
+        options.preload(preload.clone());
+        let memory: Memory = Memory::from(&options);
+        let saved = text(&memory.snapshot());
+        let read: MemorySnapshot = MemorySnapshot::read_from(
+            &mut saved.as_bytes()
+        ).unwrap();
+        assert_eq!(*read.options().preloaded(), preload, "{}", saved);
+        assert_eq!(read, memory.snapshot());
+    }
+    let mut options = MemoryOptions::new();
+    options.preload(Preload::text("Hi").at(2));
+    let memory: Memory = Memory::from(&options);
+    assert!(text(&memory.snapshot()).contains("\npreload 2 text 72 105\n"));
+}
+
+#[test]
+fn broken_preloads() {
+    for preload in ["", "1", "x text 1", "0 words 1", "0 numbers 1 y"] {
+        let text = format!(
+            "brainfuck-snapshot 1\npreload {}\ncells 1\n0\n",
+            preload
+        );
+        assert_eq!(read(&text), ErrorKind::InvalidData, "{:?}", preload);
+    }
+    let text = "brainfuck-snapshot 1\npreload 0 numbers 300\ncells 1\n0\n";
+    assert_eq!(read(text), ErrorKind::InvalidData);
+}
+
+#[test]
+fn too_many_cells_in_the_header() {
+    let text = "brainfuck-snapshot 1\ncells 18446744073709551615\n0 1 2\n";
+    assert_eq!(read(text), ErrorKind::InvalidData);
+}
+
+#[test]
+fn too_many_cells_in_a_run() {
+    let text = "brainfuck-snapshot 1\ncells 4\n1 0*18446744073709551615\n";
+    assert_eq!(read(text), ErrorKind::InvalidData);
+}
+
+#[test]
+fn too_few_cells() {
+    let text = "brainfuck-snapshot 1\ncells 4\n1 2 3\n";
+    assert_eq!(read(text), ErrorKind::InvalidData);
+}
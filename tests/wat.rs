@@ -0,0 +1,197 @@
+//! Checks that the WebAssembly modules made by [`WatCompiler`] run on
+//! [`wasmi`] with every proposal after the first version of WebAssembly
+//! turned off, and that they write the same output as [`BfRunner`] given the
+//! same input.
+
+#![allow(clippy::needless_return)]
+
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use brainfucklib::{
+    code::{BfProgram, BfRunner},
+    compile::{BfCompiler, WatCompiler},
+    debug::BfDebugger,
+    parser::{BfParser, NormalParser},
+    vm::{EofPolicy, Memory, MemoryOptions, Preload},
+};
+use wasmi::{Caller, Config, Engine, Linker, Module, Store};
+
+/// The most commands the interpreter may run before the program is assumed
+/// to be stuck.
+const LIMIT: usize = 1_000_000;
+
+/// The input and output of a WebAssembly module.
+struct Host {
+    input: VecDeque<u8>,
+    output: Vec<u8>,
+}
+
+fn parse(script: &str) -> BfProgram {
+    let mut program = BfProgram::new();
+    NormalParser::parse_string(script, &mut program).unwrap();
+    program.populate_map().unwrap();
+    program.calculate_map().unwrap();
+    return program;
+}
+
+fn interpret(script: &str, options: &MemoryOptions, input: &[u8]) -> Vec<u8> {
+    let memory: Memory = Memory::from(options);
+    let mut runner = BfRunner::with_buffers(
+        Box::new(parse(script)),
+        Box::new(memory),
+        None::<Rc<RefCell<BfDebugger>>>,
+        input,
+    );
+    for _i in 0..LIMIT {
+        if runner.run_once().is_none() {
+            return runner.output().clone();
+        }
+    }
+    panic!("{:?} did not finish after {} commands", script, LIMIT);
+}
+
+fn compile(script: &str, options: &MemoryOptions) -> Vec<u8> {
+    let mut source = Vec::new();
+    WatCompiler::compile(&parse(script), options, &mut source).unwrap();
+    let source = String::from_utf8(source).unwrap();
+    return wat::parse_str(&source)
+        .unwrap_or_else(|error| panic!("{}\n{}", error, source));
+}
+
+fn execute(script: &str, options: &MemoryOptions, input: &[u8]) -> Vec<u8> {
+    let mut config = Config::default();
+    config
+        .wasm_mutable_global(false)
+        .wasm_sign_extension(false)
+        .wasm_saturating_float_to_int(false)
+        .wasm_multi_value(false)
+        .wasm_bulk_memory(false)
+        .wasm_reference_types(false);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, &compile(script, options)).unwrap();
+    let host = Host {input: input.iter().copied().collect(), output: vec![]};
+    let mut store = Store::new(&engine, host);
+    let mut linker = Linker::<Host>::new(&engine);
+    linker
+        .func_wrap("env", "getchar", |mut caller: Caller<'_, Host>| {
+            return match caller.data_mut().input.pop_front() {
+                Some(byte) => byte as i32,
+                None => -1,
+            };
+        })
+        .unwrap()
+        .func_wrap("env", "putchar", |mut caller: Caller<'_, Host>, c: i32| {
+            caller.data_mut().output.push(c as u8);
+        })
+        .unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .and_then(|instance| instance.start(&mut store))
+        .unwrap();
+    instance
+        .get_typed_func::<(), ()>(&store, "run")
+        .unwrap()
+        .call(&mut store, ())
+        .unwrap();
+    return store.into_data().output;
+}
+
+/// Checks that `script` writes the same output on both engines, and returns
+/// that output.
+fn same(script: &str, options: &MemoryOptions, input: &[u8]) -> Vec<u8> {
+    let expected = interpret(script, options, input);
+    assert_eq!(execute(script, options, input), expected, "{:?}", script);
+    return expected;
+}
+
+fn tape(length: usize, variable: bool) -> MemoryOptions {
+    let mut options = MemoryOptions::new();
+    options.initial_length(length).variable_length(variable);
+    return options;
+}
+
+const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>\
+    ---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+#[test]
+fn hello_world() {
+    assert_eq!(same(HELLO, &MemoryOptions::new(), b""), b"Hello World!\n");
+}
+
+#[test]
+fn bytes_are_written_as_they_are() {
+    let mut options = MemoryOptions::new();
+    options.eof_policy(EofPolicy::Zero);
+    let input = b"h\xc3\xa9\xff\n";
+    assert_eq!(same(",[.,]", &options, input), input);
+}
+
+#[test]
+fn end_of_input() {
+    for policy in [EofPolicy::Unchanged, EofPolicy::Zero, EofPolicy::Max] {
+        let mut options = MemoryOptions::new();
+        options.eof_policy(policy);
+        same("+++,>,,.<.", &options, b"a");
+    }
+}
+
+#[test]
+fn pointer_wraps_around() {
+    let options = tape(4, false);
+    same("<+.>>>>>>>>+.", &options, b"");
+    same("+>+>+<<[<]+.", &options, b"");
+    same("+++[<+>-]<.", &options, b"");
+}
+
+/// Moves off the left end of the tape one cell at a time, and then several
+/// cells at once.
+const GROW_LEFT: &str = "+>+>+>+[<]<<++.>>>.<<<<<<<<<<<<+++.>>>>>>>>>>>>>>>.";
+
+#[test]
+fn tape_grows() {
+    let options = tape(2, true);
+    same(GROW_LEFT, &options, b"");
+    same("+>++>+++>++++.<<<.", &options, b"");
+    same(HELLO, &options, b"");
+}
+
+#[test]
+fn cells_wrap_by_hand() {
+    let mut options = MemoryOptions::new();
+    options.upper_bound(99).eof_policy(EofPolicy::Zero);
+    same("++++++++++[>++++++++++++<-]>.", &options, b"");
+    same(",[.,]", &options, b"a~");
+}
+
+/// Goes below the lower bound and back, clears a cell and reads a character,
+/// all of which count from the lower bound when 0 is out of range.
+const FROM_THE_LOWER_BOUND: &str = "-.+.++[-]+.>,.[-],.";
+
+#[test]
+fn cells_count_from_the_lower_bound() {
+    for (lower, upper) in [(1, 256), (-300, -45)] {
+        let mut options = MemoryOptions::new();
+        options.lower_bound(lower).upper_bound(upper);
+        let output = same(FROM_THE_LOWER_BOUND, &options, b"A");
+        assert_eq!(output, [255, 0, 1, 65, 0]);
+    }
+}
+
+/// Prints the cells from the second one up to the first cell which is zero.
+const PRINT_PRELOAD: &str = ">[.>]";
+
+#[test]
+fn preloaded_cells() {
+    let mut options = MemoryOptions::new();
+    options.preload(Preload::text("Hi!").at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b""), b"Hi!");
+    options.preload(Preload::numbers([1, 2, 3]).at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b""), [1, 2, 3]);
+    options.lower_bound(1).upper_bound(256);
+    options.preload(Preload::bytes(b"\x01ok").at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b""), b"\x01ok");
+    let mut options = MemoryOptions::new();
+    options.initial_length(2).variable_length(true);
+    options.preload(Preload::text("longer").at(1));
+    assert_eq!(same(PRINT_PRELOAD, &options, b""), b"longer");
+}